@@ -0,0 +1,26 @@
+//! Measures waking one future of a large set. Only the woken future is polled, so the time should not grow with the size of the set.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use egui_multiwin::future_set::FuturesHashSet;
+use egui_multiwin::futures_lite::{future::block_on, StreamExt};
+
+/// Benchmark adding a ready future to sets of futures that never finish, and taking its result from the stream
+fn wake_one(c: &mut Criterion) {
+    let mut group = c.benchmark_group("wake_one");
+    for size in [10, 100, 1000] {
+        let mut set = FuturesHashSet::new();
+        for _ in 0..size {
+            set.get().add_future(std::future::pending::<u32>());
+        }
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| {
+                set.get().add_future(async { 1 });
+                assert_eq!(block_on(set.next()), Some(1));
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, wake_one);
+criterion_main!(benches);
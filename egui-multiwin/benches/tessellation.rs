@@ -0,0 +1,48 @@
+//! Compares tessellating a window with static contents every frame to reusing the tessellation of the last frame.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use egui_multiwin::egui;
+use egui_multiwin::tracked_window::LastFrame;
+
+/// Run one frame of a window showing a dense table that does not change between frames
+fn static_frame(ctx: &egui::Context) -> egui::FullOutput {
+    ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::Grid::new("table").show(ui, |ui| {
+                for row in 0..100 {
+                    for column in 0..10 {
+                        ui.label(format!("{}:{}", row, column));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    })
+}
+
+/// Benchmark tessellation of a static frame with and without the cache
+fn tessellation(c: &mut Criterion) {
+    let ctx = egui::Context::default();
+    // The first frames upload the font atlas, the frames after that are identical
+    for _ in 0..3 {
+        static_frame(&ctx);
+    }
+    let output = static_frame(&ctx);
+    let ppp = ctx.pixels_per_point();
+    let no_textures = egui::TexturesDelta::default();
+
+    c.bench_function("tessellate every frame", |b| {
+        b.iter(|| black_box(ctx.tessellate(output.shapes.clone(), ppp)))
+    });
+
+    let mut last_frame = LastFrame::default();
+    c.bench_function("reuse last tessellation", |b| {
+        b.iter(|| {
+            last_frame.changed(ppp, (800, 600), &output.shapes, &no_textures);
+            black_box(last_frame.primitives(&ctx).len())
+        })
+    });
+}
+
+criterion_group!(benches, tessellation);
+criterion_main!(benches);
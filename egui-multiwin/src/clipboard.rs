@@ -0,0 +1,351 @@
+//! Contains the clipboard given to windows. The clipboard is a [ClipboardProvider], the system clipboard by default. The system
+//! clipboard is opened when it is first used, and reopened after an operation fails. When the system clipboard is not available, such as
+//! on linux without a display server, its operations return [arboard::Error::ClipboardNotSupported]. Tests can use a [MemoryClipboard]
+//! instead, see `MultiWindow::with_clipboard`.
+//!
+//! Each window gets its own system clipboard, so a window that holds its clipboard, such as across an await in its redraw, does not
+//! block copy and paste in the other windows. Opening a system clipboard is cheap: windows and macos have one clipboard for the
+//! process, and on x11 arboard shares one connection between all of them. Under wayland the windows share one clipboard, so that only
+//! one clipboard is torn down on exit. Other providers are shared by all windows unless they implement [ClipboardProvider::for_window].
+//!
+//! On wayland, the clipboard must be closed while the connection to the display is still open, or the program can crash on exit. The
+//! `MultiWindow` closes the clipboard with [ClipboardProvider::close] after the last window closes and before the event loop exits.
+//!
+//! Images on the clipboard are only read when the user pastes, and are given to the window with `TrackedWindow::pasted_image`.
+
+use std::sync::{Arc, Mutex};
+
+/// The result of reading an image from the clipboard
+pub type ImageResult = Result<arboard::ImageData<'static>, arboard::Error>;
+
+/// The operations of a clipboard
+pub trait ClipboardProvider {
+    /// Get the text on the clipboard
+    fn get_text(&mut self) -> Result<String, arboard::Error>;
+
+    /// Place text on the clipboard
+    fn set_text(&mut self, text: &str) -> Result<(), arboard::Error>;
+
+    /// Get the image on the clipboard
+    fn get_image(&mut self) -> Result<arboard::ImageData<'static>, arboard::Error>;
+
+    /// Place an image on the clipboard
+    fn set_image(&mut self, image: arboard::ImageData<'_>) -> Result<(), arboard::Error>;
+
+    /// Place an egui image on the clipboard, such as a screenshot of a window
+    fn set_color_image(&mut self, image: &egui::ColorImage) -> Result<(), arboard::Error> {
+        let rgba: Vec<u8> = image
+            .pixels
+            .iter()
+            .flat_map(|p| p.to_srgba_unmultiplied())
+            .collect();
+        self.set_image(arboard::ImageData {
+            width: image.size[0],
+            height: image.size[1],
+            bytes: rgba.into(),
+        })
+    }
+
+    /// Read the image on the clipboard without making the caller wait. The result is sent into the returned channel, then `ready` is
+    /// called. By default the image is read immediately with [Self::get_image].
+    fn request_image(
+        &mut self,
+        ready: Box<dyn FnOnce() + Send>,
+    ) -> async_channel::Receiver<ImageResult> {
+        let (s, r) = async_channel::bounded(1);
+        let _ = s.try_send(self.get_image());
+        ready();
+        r
+    }
+
+    /// Make the clipboard for a new window, or None to give the window this clipboard, shared with the other windows. Gives None by
+    /// default.
+    fn for_window(&mut self) -> Option<Arc<Mutex<Clipboard>>> {
+        None
+    }
+
+    /// Release the resources of the clipboard, before the program exits. The clipboard may be used again afterwards. Does nothing by
+    /// default.
+    fn close(&mut self) {}
+}
+
+/// The clipboard given to windows. This is a trait object, so that code passing the clipboard along keeps working with any provider.
+pub type Clipboard = dyn ClipboardProvider;
+
+/// The clipboard of the system, opened when it is first used
+pub struct SystemClipboard {
+    /// The system clipboard, None until it is opened
+    inner: Option<arboard::Clipboard>,
+    /// False for a clipboard that never opens the system clipboard
+    enabled: bool,
+    /// True when opening the system clipboard failed and the failure was logged
+    failure_logged: bool,
+}
+
+impl SystemClipboard {
+    /// Construct the system clipboard. Nothing is opened until the clipboard is used.
+    pub fn new() -> Self {
+        Self {
+            inner: None,
+            enabled: true,
+            failure_logged: false,
+        }
+    }
+
+    /// A clipboard that does nothing
+    pub fn unavailable() -> Self {
+        Self {
+            enabled: false,
+            ..Self::new()
+        }
+    }
+
+    /// Returns true when the system clipboard is available, opening it when it is not open yet
+    pub fn is_available(&mut self) -> bool {
+        self.available().is_ok()
+    }
+
+    /// Get the system clipboard, for operations not covered here, opening it when it is not open yet
+    pub fn system(&mut self) -> Option<&mut arboard::Clipboard> {
+        self.available().ok()
+    }
+
+    /// Get the system clipboard, opening it when it is not open yet. A failure to open is logged once.
+    fn available(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+        if !self.enabled {
+            return Err(arboard::Error::ClipboardNotSupported);
+        }
+        if self.inner.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(c) => self.inner = Some(c),
+                Err(e) => {
+                    if !std::mem::replace(&mut self.failure_logged, true) {
+                        crate::trace::warn(format_args!("The clipboard is not available: {}", e));
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        self.inner
+            .as_mut()
+            .ok_or(arboard::Error::ClipboardNotSupported)
+    }
+
+    /// Run an operation on the system clipboard. The clipboard is closed when the operation fails for a reason other than the content, so
+    /// that the next operation opens it again, such as after the clipboard owner or display connection went away.
+    fn with<T>(
+        &mut self,
+        f: impl FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+    ) -> Result<T, arboard::Error> {
+        let r = f(self.available()?);
+        if let Err(e) = &r {
+            if !matches!(
+                e,
+                arboard::Error::ContentNotAvailable | arboard::Error::ConversionFailure
+            ) {
+                crate::trace::debug(format_args!(
+                    "Reopening the clipboard after an error: {}",
+                    e
+                ));
+                self.inner = None;
+            }
+        }
+        r
+    }
+}
+
+impl Default for SystemClipboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClipboardProvider for SystemClipboard {
+    fn get_text(&mut self) -> Result<String, arboard::Error> {
+        self.with(|c| c.get_text())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), arboard::Error> {
+        self.with(|c| c.set_text(text))
+    }
+
+    fn get_image(&mut self) -> Result<arboard::ImageData<'static>, arboard::Error> {
+        self.with(|c| c.get_image())
+    }
+
+    fn set_image(&mut self, image: arboard::ImageData<'_>) -> Result<(), arboard::Error> {
+        self.with(|c| c.set_image(image))
+    }
+
+    /// On x11 and wayland, reading waits for the application owning the clipboard, which can be slow for large images. The image is
+    /// read on another thread with its own connection to the clipboard, so that a slow owner does not stall drawing.
+    fn request_image(
+        &mut self,
+        ready: Box<dyn FnOnce() + Send>,
+    ) -> async_channel::Receiver<ImageResult> {
+        let (s, r) = async_channel::bounded(1);
+        if let Err(e) = self.available() {
+            let _ = s.try_send(Err(e));
+            ready();
+            return r;
+        }
+        std::thread::spawn(move || {
+            let image = arboard::Clipboard::new().and_then(|mut c| c.get_image());
+            let _ = s.try_send(image);
+            ready();
+        });
+        r
+    }
+
+    /// Every window has its own clipboard, except under wayland
+    fn for_window(&mut self) -> Option<Arc<Mutex<Clipboard>>> {
+        if cfg!(all(unix, not(target_os = "macos")))
+            && std::env::var_os("WAYLAND_DISPLAY").is_some()
+        {
+            return None;
+        }
+        Some(Arc::new(Mutex::new(Self {
+            enabled: self.enabled,
+            ..Self::new()
+        })))
+    }
+
+    /// Dropping the connection lets arboard hand the content of the clipboard over to a clipboard manager, if there is one
+    fn close(&mut self) {
+        self.inner = None;
+    }
+}
+
+/// A clipboard kept in memory, separate from the system clipboard, for testing copy and paste
+#[derive(Default)]
+pub struct MemoryClipboard {
+    /// The text on the clipboard
+    text: Option<String>,
+    /// The image on the clipboard
+    image: Option<arboard::ImageData<'static>>,
+}
+
+impl ClipboardProvider for MemoryClipboard {
+    fn get_text(&mut self) -> Result<String, arboard::Error> {
+        self.text.clone().ok_or(arboard::Error::ContentNotAvailable)
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), arboard::Error> {
+        // Like the system clipboard, the clipboard holds one kind of content at a time
+        self.image = None;
+        self.text = Some(text.to_string());
+        Ok(())
+    }
+
+    fn get_image(&mut self) -> Result<arboard::ImageData<'static>, arboard::Error> {
+        self.image
+            .clone()
+            .ok_or(arboard::Error::ContentNotAvailable)
+    }
+
+    fn set_image(&mut self, image: arboard::ImageData<'_>) -> Result<(), arboard::Error> {
+        self.text = None;
+        self.image = Some(image.to_owned_img());
+        Ok(())
+    }
+}
+
+/// Reads the image on the clipboard when the user pastes into a window, so that large images are not read on every frame
+#[derive(Default)]
+pub struct PasteState {
+    /// True when the input of the last frame contained a paste
+    requested: bool,
+    /// The image being read from the clipboard
+    pending: Option<async_channel::Receiver<ImageResult>>,
+}
+
+impl PasteState {
+    /// Look for a paste in the input of a frame, either the paste event of egui or the paste shortcut
+    pub fn check_input(&mut self, input: &egui::RawInput) {
+        self.requested |= input.events.iter().any(|e| match e {
+            egui::Event::Paste(_) => true,
+            egui::Event::Key {
+                key,
+                pressed: true,
+                repeat: false,
+                modifiers,
+                ..
+            } => *key == egui::Key::Paste || (*key == egui::Key::V && modifiers.command),
+            _ => false,
+        });
+    }
+
+    /// Start reading the image on the clipboard after a paste. A paste while the last image is still being read is ignored. `ready` is
+    /// called when the image has been read.
+    pub fn start(&mut self, clipboard: &mut Clipboard, ready: impl FnOnce() + Send + 'static) {
+        if std::mem::take(&mut self.requested) && self.pending.is_none() {
+            self.pending = Some(clipboard.request_image(Box::new(ready)));
+        }
+    }
+
+    /// Take the pasted image, once it has been read. Nothing is given when the clipboard did not contain an image.
+    pub fn take_image(&mut self) -> Option<arboard::ImageData<'static>> {
+        let result = self.pending.as_ref()?.try_recv();
+        match result {
+            Err(async_channel::TryRecvError::Empty) => None,
+            Err(async_channel::TryRecvError::Closed) => {
+                self.pending = None;
+                None
+            }
+            Ok(image) => {
+                self.pending = None;
+                image.ok()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_clipboard_holds_the_last_content() {
+        let mut memory = MemoryClipboard::default();
+        let clipboard: &mut Clipboard = &mut memory;
+        assert!(clipboard.get_text().is_err());
+        clipboard.set_text("copied").unwrap();
+        assert_eq!(clipboard.get_text().unwrap(), "copied");
+        let pixels = [0u8; 4];
+        clipboard
+            .set_image(arboard::ImageData {
+                width: 1,
+                height: 1,
+                bytes: pixels[..].into(),
+            })
+            .unwrap();
+        assert!(clipboard.get_text().is_err());
+        assert_eq!(clipboard.get_image().unwrap().width, 1);
+    }
+
+    #[test]
+    fn image_is_only_read_on_paste() {
+        let mut clipboard = MemoryClipboard::default();
+        clipboard
+            .set_color_image(&egui::ColorImage::new([2, 1], egui::Color32::RED))
+            .unwrap();
+        let mut paste = PasteState::default();
+        let mut input = egui::RawInput::default();
+        paste.check_input(&input);
+        paste.start(&mut clipboard, || {});
+        assert!(paste.take_image().is_none());
+        input.events.push(egui::Event::Key {
+            key: egui::Key::V,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::COMMAND,
+        });
+        paste.check_input(&input);
+        paste.start(&mut clipboard, || {});
+        let image = paste.take_image().unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.bytes[..4], [255, 0, 0, 255]);
+        assert!(paste.take_image().is_none());
+    }
+}
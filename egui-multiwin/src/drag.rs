@@ -0,0 +1,139 @@
+//! Contains the dragging of payloads from one window of the application to another. egui keeps the payload of a drag in the context
+//! of one window, so it never leaves the window. A window starts a drag with [start_drag], such as when `Response::drag_started` is
+//! true, and the application keeps the payload while the mouse button is held down. When the button is released, the window under the
+//! mouse receives the payload with `TrackedWindow::dropped_payload`, at the position of the mouse in that window. The payload is
+//! discarded when the button is released outside of the windows of the application.
+//!
+//! Finding the window under the mouse needs the position of the windows on the screen. Wayland does not report it, so there the
+//! payload is always discarded. Viewport windows never receive a payload.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::registry::{WindowId, WindowRegistry};
+
+/// The payload of a drag between windows
+pub type DragPayload = Arc<dyn Any + Send + Sync>;
+
+/// A payload dropped onto a window, waiting for the next frame of the window
+#[derive(Clone)]
+pub struct DroppedPayload {
+    /// The window that started the drag
+    pub source: WindowId,
+    /// The payload
+    pub payload: DragPayload,
+    /// The position of the mouse on the screen in physical pixels
+    pub position: async_winit::dpi::PhysicalPosition<i32>,
+}
+
+/// The registry and the id of the window that owns an egui context, stored in the context
+#[derive(Clone)]
+struct DragHandle {
+    /// The registry of the application
+    registry: WindowRegistry,
+    /// The id of the window
+    id: WindowId,
+}
+
+impl DragHandle {
+    /// The id used to store the handle in the egui context of the window
+    fn id() -> egui::Id {
+        egui::Id::new("egui_multiwin_drag")
+    }
+
+    /// Get the handle stored in an egui context, None for contexts not owned by a window of the application
+    fn get(ctx: &egui::Context) -> Option<Self> {
+        ctx.data(|d| d.get_temp(Self::id()))
+    }
+}
+
+/// Let the window that owns an egui context start drags and receive payloads. Called by the `MultiWindow` for every window.
+pub fn attach(ctx: &egui::Context, registry: &WindowRegistry, id: WindowId) {
+    let handle = DragHandle {
+        registry: registry.clone(),
+        id,
+    };
+    ctx.data_mut(|d| d.insert_temp(DragHandle::id(), handle));
+}
+
+/// Start dragging a payload out of the window that owns an egui context, replacing any other drag. Does nothing for a context not
+/// owned by a window of the application.
+pub fn start_drag(ctx: &egui::Context, payload: impl Any + Send + Sync) {
+    if let Some(handle) = DragHandle::get(ctx) {
+        handle.registry.start_drag(handle.id, Arc::new(payload));
+    }
+}
+
+/// Get the payload being dragged, from any window of the application, such as to highlight the places it can be dropped
+pub fn payload(ctx: &egui::Context) -> Option<DragPayload> {
+    let handle = DragHandle::get(ctx)?;
+    handle.registry.drag().map(|(_, payload)| payload)
+}
+
+/// Stop the current drag without dropping its payload
+pub fn cancel_drag(ctx: &egui::Context) {
+    if let Some(handle) = DragHandle::get(ctx) {
+        handle.registry.cancel_drag();
+    }
+}
+
+/// Drop the payload onto the window under the mouse when the mouse button was released in the last frame of the window that started
+/// the drag. Called by the `MultiWindow` after every frame.
+pub async fn finish_drag(ctx: &egui::Context) {
+    let Some(handle) = DragHandle::get(ctx) else {
+        return;
+    };
+    let dragging = handle.registry.drag().map(|(source, _)| source) == Some(handle.id);
+    if !dragging || !ctx.input(|i| i.pointer.any_released()) {
+        return;
+    }
+    match crate::tracked_window::screen_pointer_position(ctx) {
+        Some(position) => {
+            if handle.registry.drop_at(position).await.is_none() {
+                crate::trace::debug(format_args!("A payload was dropped outside of the windows"));
+            }
+        }
+        None => handle.registry.cancel_drag(),
+    }
+}
+
+/// Take the payload dropped onto the window that owns an egui context, with the position of the mouse in the window in points. Called
+/// by the `MultiWindow` during a frame, after the input of the frame is known.
+pub fn take_drop(ctx: &egui::Context) -> Option<(DragPayload, egui::Pos2)> {
+    let handle = DragHandle::get(ctx)?;
+    let dropped = handle.registry.take_drop(handle.id)?;
+    let inner = ctx.input(|i| i.viewport().inner_rect)?;
+    let pos = from_screen(inner.min, dropped.position, ctx.pixels_per_point());
+    Some((dropped.payload, pos))
+}
+
+/// Convert a position on the screen in physical pixels into a position in a window, given the top left corner of the inside of the
+/// window on the screen in points
+fn from_screen(
+    inner: egui::Pos2,
+    position: async_winit::dpi::PhysicalPosition<i32>,
+    pixels_per_point: f32,
+) -> egui::Pos2 {
+    egui::pos2(position.x as f32, position.y as f32) / pixels_per_point - inner.to_vec2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_positions_are_converted_into_the_window() {
+        let position = async_winit::dpi::PhysicalPosition::new(442, 120);
+        let pos = from_screen(egui::pos2(200.0, 50.0), position, 2.0);
+        assert_eq!(pos, egui::pos2(21.0, 10.0));
+        let ctx = egui::Context::default();
+        start_drag(&ctx, 5u32);
+        assert!(payload(&ctx).is_none());
+        attach(&ctx, &WindowRegistry::new(), WindowId::allocate());
+        start_drag(&ctx, 5u32);
+        let dragged = payload(&ctx).unwrap();
+        assert_eq!(dragged.downcast_ref::<u32>(), Some(&5));
+        cancel_drag(&ctx);
+        assert!(payload(&ctx).is_none());
+    }
+}
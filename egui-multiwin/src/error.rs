@@ -0,0 +1,79 @@
+//! Contains the error type of the crate.
+//!
+//! Errors are handled at the level they affect. A window that cannot be created is logged and skipped, an error during the frame of
+//! an open window closes that window, and only errors of the event loop itself end the application.
+
+use crate::tracked_window::ContextError;
+
+/// The errors that can occur while creating and running windows
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// The window could not be built by the windowing system
+    #[error("Unable to build the window: {0}")]
+    WindowBuild(String),
+    /// No opengl display, configuration, surface and context could be created for the window
+    #[error("Unable to create an opengl display, surface and context for the window")]
+    Display,
+    /// egui could not be initialized for the window
+    #[error("Unable to initialize egui for the window: {0}")]
+    EguiInit(ContextError),
+    /// The opengl context of the window failed
+    #[error(transparent)]
+    Context(#[from] ContextError),
+    /// A channel between the event loop and a window was closed, because the other side is gone
+    #[error("A channel between the event loop and a window was closed")]
+    ChannelClosed,
+    /// The window panicked while drawing a frame
+    #[error("The window panicked: {0}")]
+    Panic(String),
+}
+
+/// Get the message of a panic from its payload
+pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+impl<T> From<async_channel::SendError<T>> for Error {
+    fn from(_: async_channel::SendError<T>) -> Self {
+        Self::ChannelClosed
+    }
+}
+
+impl From<async_channel::RecvError> for Error {
+    fn from(_: async_channel::RecvError) -> Self {
+        Self::ChannelClosed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_channel_is_an_error() {
+        let (sender, receiver) = async_channel::bounded::<bool>(1);
+        drop(receiver);
+        let e: Error = futures_lite::future::block_on(sender.send(true))
+            .unwrap_err()
+            .into();
+        assert!(matches!(e, Error::ChannelClosed));
+        let (sender, receiver) = async_channel::bounded::<bool>(1);
+        drop(sender);
+        let e: Error = futures_lite::future::block_on(receiver.recv())
+            .unwrap_err()
+            .into();
+        assert!(matches!(e, Error::ChannelClosed));
+    }
+
+    #[test]
+    fn panic_message_is_found() {
+        let payload = std::panic::catch_unwind(|| panic!("frame {}", 3)).unwrap_err();
+        assert_eq!(panic_message(&*payload), "frame 3");
+        let payload = std::panic::catch_unwind(|| panic!("static")).unwrap_err();
+        assert_eq!(panic_message(&*payload), "static");
+    }
+}
@@ -0,0 +1,195 @@
+//! Contains the font configuration that is installed into the egui instance of every window
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::sync::LockRecover;
+
+lazy_static::lazy_static! {
+    /// Font bytes that were loaded at runtime. They are kept for the life of the program so that every window borrows them instead of
+    /// keeping its own copy.
+    static ref SHARED_FONT_BYTES: Mutex<Vec<&'static [u8]>> = Mutex::new(Vec::new());
+}
+
+/// Convert font data that owns its bytes into font data that borrows bytes shared by every window. Identical bytes are only kept once,
+/// so loading the same font again does not use more memory.
+fn shared(data: egui::FontData) -> egui::FontData {
+    let bytes = match data.font {
+        Cow::Borrowed(_) => return data,
+        Cow::Owned(bytes) => bytes,
+    };
+    let mut shared = SHARED_FONT_BYTES.lock_recover();
+    let bytes: &'static [u8] = match shared.iter().find(|b| **b == bytes[..]) {
+        Some(b) => b,
+        None => {
+            let b: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+            shared.push(b);
+            b
+        }
+    };
+    egui::FontData {
+        font: Cow::Borrowed(bytes),
+        index: data.index,
+        tweak: data.tweak,
+    }
+}
+
+/// The errors that can occur when loading a font
+#[derive(thiserror::Error, Debug)]
+pub enum FontLoadError {
+    /// The font file could not be read
+    #[error("Unable to read the font file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The font data could not be parsed
+    #[error("Invalid font data: {0}")]
+    Invalid(#[from] ab_glyph::InvalidFont),
+}
+
+/// Create font data from bytes, checking that the font can be parsed so that egui does not panic on it later
+pub fn font_from_bytes(data: Vec<u8>) -> Result<egui::FontData, FontLoadError> {
+    ab_glyph::FontRef::try_from_slice(&data)?;
+    Ok(egui::FontData::from_owned(data))
+}
+
+/// Load font data from a file, checking that the font can be parsed
+pub fn font_from_file(path: impl AsRef<Path>) -> Result<egui::FontData, FontLoadError> {
+    font_from_bytes(std::fs::read(path)?)
+}
+
+/// Load font data from a file without blocking, checking that the font can be parsed. The file is read with tokio when the `tokio`
+/// feature is enabled, otherwise on the thread pool of the blocking crate.
+pub async fn font_from_file_async(
+    path: impl AsRef<Path>,
+) -> Result<egui::FontData, FontLoadError> {
+    #[cfg(feature = "tokio")]
+    let data = tokio::fs::read(path).await?;
+    #[cfg(not(feature = "tokio"))]
+    let data = {
+        let path = path.as_ref().to_owned();
+        blocking::unblock(move || std::fs::read(path)).await?
+    };
+    font_from_bytes(data)
+}
+
+/// Where a font is placed within a built in font family
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FontPlacement {
+    /// The font is placed before the default fonts of the family, making it the preferred font of the family
+    Prepend,
+    /// The font is placed after the default fonts of the family, making it a fallback font for the family
+    Append,
+}
+
+/// A font to install into windows
+#[derive(Clone)]
+pub struct FontEntry {
+    /// The name of the font
+    pub name: String,
+    /// The font data
+    pub data: egui::FontData,
+    /// The built in families that the font is added to, in addition to the family named after the font
+    pub families: Vec<(egui::FontFamily, FontPlacement)>,
+}
+
+/// An ordered set of fonts to install into windows. Fonts are installed in the order they were added. The bytes of every font are shared
+/// by all windows, so cloning a set or installing it into many windows does not copy them.
+#[derive(Clone, Default)]
+pub struct FontSet {
+    /// The fonts, in order of insertion
+    fonts: Vec<FontEntry>,
+    /// The names of fonts that have been removed. The families named after these fonts are kept so that text still using them does not panic.
+    removed: Vec<String>,
+}
+
+impl FontSet {
+    /// Construct a new empty set of fonts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a font to the set. A font with the same name is replaced, keeping its position in the set.
+    pub fn insert(&mut self, mut entry: FontEntry) {
+        entry.data = shared(entry.data);
+        self.removed.retain(|n| *n != entry.name);
+        if let Some(f) = self.fonts.iter_mut().find(|f| f.name == entry.name) {
+            *f = entry;
+        } else {
+            self.fonts.push(entry);
+        }
+    }
+
+    /// Remove a font from the set, returning true if the font was present. Text using the family named after the removed font
+    /// falls back to the proportional family.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.fonts.len();
+        self.fonts.retain(|f| f.name != name);
+        let removed = self.fonts.len() != len;
+        if removed {
+            self.removed.push(name.to_string());
+        }
+        removed
+    }
+
+    /// Build a new set containing the fonts of this set, with the fonts of another set added to it. Fonts in the other set replace fonts
+    /// of the same name.
+    pub fn merged(&self, other: &FontSet) -> FontSet {
+        let mut s = self.clone();
+        for f in &other.fonts {
+            s.insert(f.clone());
+        }
+        s
+    }
+
+    /// Build the font definitions for an egui instance
+    pub fn definitions(&self) -> egui::FontDefinitions {
+        let mut fonts = egui::FontDefinitions::default();
+        let mut prepended: std::collections::HashMap<egui::FontFamily, usize> =
+            std::collections::HashMap::new();
+        for f in &self.fonts {
+            fonts.font_data.insert(f.name.clone(), f.data.clone());
+            fonts.families.insert(
+                egui::FontFamily::Name(f.name.to_owned().into()),
+                vec![f.name.to_owned()],
+            );
+            for (family, placement) in &f.families {
+                let list = fonts.families.entry(family.clone()).or_default();
+                match placement {
+                    FontPlacement::Prepend => {
+                        let index = prepended.entry(family.clone()).or_insert(0);
+                        list.insert(*index, f.name.clone());
+                        *index += 1;
+                    }
+                    FontPlacement::Append => list.push(f.name.clone()),
+                }
+            }
+        }
+        let fallback = fonts
+            .families
+            .get(&egui::FontFamily::Proportional)
+            .cloned()
+            .unwrap_or_default();
+        for name in &self.removed {
+            fonts.families.insert(
+                egui::FontFamily::Name(name.to_owned().into()),
+                fallback.clone(),
+            );
+        }
+        fonts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_fonts_share_bytes() {
+        let a = shared(egui::FontData::from_owned(vec![1, 2, 3, 4]));
+        let b = shared(egui::FontData::from_owned(vec![1, 2, 3, 4]));
+        match (a.font, b.font) {
+            (Cow::Borrowed(a), Cow::Borrowed(b)) => assert_eq!(a.as_ptr(), b.as_ptr()),
+            _ => panic!("font data was not shared"),
+        }
+    }
+}
@@ -1,17 +1,88 @@
 //! Contains code for a hashset of futures that can be awaited
+//!
+//! The sets store futures that are not [Send] by default. The `Send` variants, such as [SendFuturesHashSet], only accept futures that
+//! are [Send], so that the sets themselves are [Send] and [Sync] and can be moved between the threads of a multi-threaded executor.
+//!
+//! A set is not locked while its futures are polled or dropped, so a future can add futures to its own set or cancel them, such as a
+//! background future that starts a timer.
 
 use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
     future::Future,
+    ops::{Deref, DerefMut},
     pin::Pin,
     sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Wake, Waker},
 };
 
+use crate::sync::LockRecover;
+
+/// A boxed future that can only be used on the thread that created it
+pub type LocalFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// A boxed future that can be sent to another thread
+pub type SendFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// A future stored in a set, with the waker given to it when it is polled
+type StoredFuture<B> = (B, Waker);
+
+/// The futures of a set that were woken, shared with the wakers given to the futures
+#[derive(Default)]
+struct WakeList {
+    /// The ids of the futures that were woken since they were last polled
+    woken: Mutex<BTreeSet<u32>>,
+    /// The waker of the task that last polled the set
+    parent: Mutex<Option<Waker>>,
+}
+
+impl WakeList {
+    /// Record that a future was woken and wake the task awaiting the set
+    fn wake_future(&self, index: u32) {
+        self.woken.lock_recover().insert(index);
+        self.wake_parent();
+    }
+
+    /// Wake the task that last polled the set
+    fn wake_parent(&self) {
+        let parent = self.parent.lock_recover().clone();
+        if let Some(w) = parent {
+            w.wake();
+        }
+    }
+}
+
+/// The waker given to a single future of a set, so that only the futures that were woken get polled
+struct FutureWaker {
+    /// The id of the future
+    index: u32,
+    /// The list the future is added to when woken
+    list: Arc<WakeList>,
+}
+
+impl Wake for FutureWaker {
+    fn wake(self: Arc<Self>) {
+        self.list.wake_future(self.index);
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.list.wake_future(self.index);
+    }
+}
+
 /// A set of futures, that finishes when any of the futures finishes
-pub struct FuturesHashSetFirst<T> {
-    i: Arc<Mutex<FuturesHashSetInternal<T>>>,
+pub struct FuturesHashSetFirst<T, B = LocalFuture<T>> {
+    i: Arc<Mutex<FuturesHashSetInternal<T, B>>>,
 }
 
-impl<T> Clone for FuturesHashSetFirst<T> {
+/// A set of [Send] futures, that finishes when any of the futures finishes
+///
+/// ```
+/// fn assert_send_sync<T: Send + Sync>() {}
+/// assert_send_sync::<egui_multiwin::future_set::SendFuturesHashSetFirst<u32>>();
+/// ```
+pub type SendFuturesHashSetFirst<T> = FuturesHashSetFirst<T, SendFuture<T>>;
+
+impl<T, B> Clone for FuturesHashSetFirst<T, B> {
     fn clone(&self) -> Self {
         Self { i: self.i.clone() }
     }
@@ -20,50 +91,114 @@ impl<T> Clone for FuturesHashSetFirst<T> {
 impl<T> FuturesHashSetFirst<T> {
     /// Construct a new self
     pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, B> Default for FuturesHashSetFirst<T, B> {
+    fn default() -> Self {
         Self {
             i: Arc::new(Mutex::new(FuturesHashSetInternal::new())),
         }
     }
+}
 
+impl<T, B> FuturesHashSetFirst<T, B> {
     /// Get a reference to the inside
-    pub fn get(&self) -> MutexGuard<'_, FuturesHashSetInternal<T>> {
-        self.i.lock().unwrap()
+    pub fn get(&self) -> SetGuard<'_, T, B> {
+        SetGuard::new(&self.i)
     }
 }
 
-impl<T> std::future::Future for FuturesHashSetFirst<T> {
+impl<T, B: Future<Output = T> + Unpin> std::future::Future for FuturesHashSetFirst<T, B> {
     type Output = T;
 
     fn poll(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
-        let mut s = self.i.lock().unwrap();
-        if s.futures.is_empty() {
-            return std::task::Poll::Pending;
+        match FuturesHashSetInternal::poll_woken(&self.i, cx, true).pop() {
+            Some(ret) => Poll::Ready(ret),
+            None => Poll::Pending,
         }
-        for f in s.futures.values_mut() {
-            if let std::task::Poll::Ready(ret) = f.as_mut().poll(cx) {
-                return std::task::Poll::Ready(ret);
-            }
-        }
-        return std::task::Poll::Pending;
     }
 }
 
-/// A set of futures
-pub struct FuturesHashSetInternal<T> {
-    futures: std::collections::HashMap<u32, Pin<Box<dyn Future<Output = T>>>>,
+/// A set of futures, stored as the boxed future type `B`
+pub struct FuturesHashSetInternal<T, B = LocalFuture<T>> {
+    futures: BTreeMap<u32, StoredFuture<B>>,
     gathered_outs: Vec<T>,
     last_index: u32,
+    /// The id to start polling from, just after the future that was served last, so that every future gets a turn
+    next_start: u32,
+    /// Results of futures that finished, waiting to be returned by the stream
+    ready: VecDeque<T>,
+    /// The futures that were woken, and the task awaiting the set
+    wake: Arc<WakeList>,
+    /// Set when the stream ends once the remaining futures finish
+    closed: bool,
+    /// Set when the set closes itself when it becomes empty after having held a future
+    close_when_empty: bool,
+    /// The futures taken out of the set while they are polled
+    polling: BTreeSet<u32>,
+    /// Futures that were removed without finishing, dropped once the set is unlocked
+    dropped: Vec<B>,
+}
+
+/// Access to the inside of a set. Futures removed through the guard are dropped after the set is unlocked, so that code running when
+/// they are dropped, such as a [DropGuard], can use the set.
+pub struct SetGuard<'a, T, B> {
+    /// The locked set, None once the guard is released
+    guard: Option<MutexGuard<'a, FuturesHashSetInternal<T, B>>>,
+}
+
+impl<'a, T, B> SetGuard<'a, T, B> {
+    /// Lock a set
+    fn new(set: &'a Mutex<FuturesHashSetInternal<T, B>>) -> Self {
+        Self {
+            guard: Some(set.lock_recover()),
+        }
+    }
+}
+
+impl<T, B> Deref for SetGuard<'_, T, B> {
+    type Target = FuturesHashSetInternal<T, B>;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard.as_ref().unwrap()
+    }
 }
 
-/// A set of futures, that finishes when all of the futures finishes
-pub struct FuturesHashSetAll<T> {
-    i: Arc<Mutex<FuturesHashSetInternal<T>>>,
+impl<T, B> DerefMut for SetGuard<'_, T, B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard.as_mut().unwrap()
+    }
+}
+
+impl<T, B> Drop for SetGuard<'_, T, B> {
+    fn drop(&mut self) {
+        if let Some(mut guard) = self.guard.take() {
+            let dropped = std::mem::take(&mut guard.dropped);
+            drop(guard);
+            drop(dropped);
+        }
+    }
 }
 
-impl<T> Clone for FuturesHashSetAll<T> {
+/// A set of futures, that finishes when all of the futures finishes. The results of the futures are given in the order the futures finished.
+pub struct FuturesHashSetAll<T, B = LocalFuture<T>> {
+    i: Arc<Mutex<FuturesHashSetInternal<T, B>>>,
+}
+
+/// A set of [Send] futures, that finishes when all of the futures finishes
+///
+/// ```
+/// fn assert_send_sync<T: Send + Sync>() {}
+/// assert_send_sync::<egui_multiwin::future_set::SendFuturesHashSetAll<u32>>();
+/// ```
+pub type SendFuturesHashSetAll<T> = FuturesHashSetAll<T, SendFuture<T>>;
+
+impl<T, B> Clone for FuturesHashSetAll<T, B> {
     fn clone(&self) -> Self {
         Self { i: self.i.clone() }
     }
@@ -72,79 +207,294 @@ impl<T> Clone for FuturesHashSetAll<T> {
 impl<T> FuturesHashSetAll<T> {
     /// Construct a new self
     pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, B> Default for FuturesHashSetAll<T, B> {
+    fn default() -> Self {
         Self {
             i: Arc::new(Mutex::new(FuturesHashSetInternal::new())),
         }
     }
+}
 
+impl<T, B> FuturesHashSetAll<T, B> {
     /// Get a reference to the inside
-    pub fn get(&self) -> MutexGuard<'_, FuturesHashSetInternal<T>> {
-        self.i.lock().unwrap()
+    pub fn get(&self) -> SetGuard<'_, T, B> {
+        SetGuard::new(&self.i)
     }
 }
 
 impl<T> FuturesHashSetInternal<T> {
+    /// Add a future to the list, returning an identifier that can be used to remove the future later
+    pub fn add_future<F: Future<Output = T> + 'static>(&mut self, elem: F) -> u32 {
+        self.insert(Box::pin(elem))
+    }
+}
+
+impl<T> FuturesHashSetInternal<T, SendFuture<T>> {
+    /// Add a future to the list, returning an identifier that can be used to remove the future later
+    pub fn add_future<F: Future<Output = T> + Send + 'static>(&mut self, elem: F) -> u32 {
+        self.insert(Box::pin(elem))
+    }
+}
+
+impl<T, B> FuturesHashSetInternal<T, B> {
     /// Construct a new self
     pub fn new() -> Self {
         Self {
-            futures: std::collections::HashMap::new(),
+            futures: BTreeMap::new(),
             gathered_outs: Vec::new(),
             last_index: 0,
+            next_start: 0,
+            ready: VecDeque::new(),
+            wake: Arc::new(WakeList::default()),
+            closed: false,
+            close_when_empty: false,
+            polling: BTreeSet::new(),
+            dropped: Vec::new(),
         }
     }
 
-    /// Add a future to the list, returning an identifier that can be used to remove the future later
-    pub fn add_future<F: Future<Output = T> + 'static>(&mut self, elem: F) -> u32 {
-        let mut e = self.last_index + 1;
-        loop {
-            if !self.futures.contains_key(&e) {
-                break;
-            }
-            e += 1;
+    /// Store a boxed future, returning its identifier
+    fn insert(&mut self, elem: B) -> u32 {
+        let mut e = self.last_index.wrapping_add(1);
+        while self.futures.contains_key(&e) || self.polling.contains(&e) {
+            e = e.wrapping_add(1);
         }
-        self.futures.insert(e, Box::pin(elem));
+        self.last_index = e;
+        let waker = Waker::from(Arc::new(FutureWaker {
+            index: e,
+            list: self.wake.clone(),
+        }));
+        self.futures.insert(e, (elem, waker));
+        if self.close_when_empty {
+            // The set closes when it becomes empty again
+            self.closed = true;
+        }
+        // The new future has not been polled yet, so it starts out woken
+        self.wake.wake_future(e);
         e
     }
 
     /// Remove a future previously added
     pub fn remove_future(&mut self, index: u32) {
-        self.futures.remove(&index);
+        self.cancel(index);
+    }
+
+    /// Remove a future and drop it without letting it finish, returning false if there is no future with the id. The future is
+    /// dropped when the set is unlocked, or after its poll when it is being polled. The task awaiting the set is woken, as a set waiting
+    /// for all of its futures may now be finished.
+    pub fn cancel(&mut self, index: u32) -> bool {
+        let found = match self.futures.remove(&index) {
+            Some((f, _)) => {
+                self.dropped.push(f);
+                true
+            }
+            None => self.polling.remove(&index),
+        };
+        if found {
+            self.wake.wake_parent();
+        }
+        found
+    }
+
+    /// Remove and drop every future in the set, waking the task awaiting the set
+    pub fn clear(&mut self) {
+        let futures = std::mem::take(&mut self.futures);
+        self.dropped.extend(futures.into_values().map(|(f, _)| f));
+        self.polling.clear();
+        self.wake.wake_parent();
+    }
+
+    /// Close the set, so that the stream ends once the remaining futures finish. Futures can still be added until then.
+    pub fn close(&mut self) {
+        self.closed = true;
+        self.wake.wake_parent();
+    }
+
+    /// Close the set when it becomes empty after having held a future
+    pub fn close_when_empty(&mut self) {
+        self.close_when_empty = true;
+        if !self.futures.is_empty() {
+            self.closed = true;
+        }
+    }
+
+    /// Returns true when the set is closed and has no futures left, the stream has ended
+    pub fn is_finished(&self) -> bool {
+        self.closed && self.is_empty() && self.ready.is_empty()
+    }
+
+    /// Returns true when the set contains a future with the id
+    pub fn contains(&self, index: u32) -> bool {
+        self.futures.contains_key(&index) || self.polling.contains(&index)
+    }
+
+    /// Returns the number of futures in the set
+    pub fn len(&self) -> usize {
+        self.futures.len() + self.polling.len()
+    }
+
+    /// Returns true when there are no futures in the set
+    pub fn is_empty(&self) -> bool {
+        self.futures.is_empty() && self.polling.is_empty()
+    }
+
+    /// Record that a future was served, so that the next poll starts with the future after it
+    fn served(&mut self, index: u32) {
+        self.next_start = index.wrapping_add(1);
+    }
+
+    /// Take a future out of the set to poll it
+    fn start_polling(&mut self, index: u32) -> Option<StoredFuture<B>> {
+        let f = self.futures.remove(&index)?;
+        self.polling.insert(index);
+        Some(f)
+    }
+
+    /// Poll the futures that were woken since they were last polled, removing the futures that finish and returning their results.
+    /// When `first_only` is set, polling stops at the first future that finishes, the other woken futures are polled next time.
+    /// The set is unlocked while each future is polled.
+    fn poll_woken(set: &Mutex<Self>, cx: &mut Context<'_>, first_only: bool) -> Vec<T>
+    where
+        B: Future<Output = T> + Unpin,
+    {
+        let (mut woken, order) = {
+            let s = set.lock_recover();
+            *s.wake.parent.lock_recover() = Some(cx.waker().clone());
+            let woken = std::mem::take(&mut *s.wake.woken.lock_recover());
+            // Start after the future that was served last, so that every future gets a turn
+            let order: Vec<u32> = woken
+                .range(s.next_start..)
+                .chain(woken.range(..s.next_start))
+                .copied()
+                .collect();
+            (woken, order)
+        };
+        let mut results = Vec::new();
+        for i in order {
+            woken.remove(&i);
+            let Some((mut f, waker)) = set.lock_recover().start_polling(i) else {
+                continue;
+            };
+            let poll = Pin::new(&mut f).poll(&mut Context::from_waker(&waker));
+            let mut s = set.lock_recover();
+            // A future cancelled while it was polled is dropped, even when it finished
+            if !s.polling.remove(&i) {
+                drop(s);
+                continue;
+            }
+            match poll {
+                Poll::Ready(ret) => {
+                    s.served(i);
+                    drop(s);
+                    results.push(ret);
+                    if first_only {
+                        break;
+                    }
+                }
+                Poll::Pending => {
+                    s.futures.insert(i, (f, waker));
+                }
+            }
+        }
+        let s = set.lock_recover();
+        woken.retain(|i| s.futures.contains_key(i));
+        s.wake.woken.lock_recover().extend(woken);
+        results
     }
 }
 
-impl<T: Clone> std::future::Future for FuturesHashSetAll<T> {
+impl<T, B: Future<Output = T> + Unpin> std::future::Future for FuturesHashSetAll<T, B> {
     type Output = Vec<T>;
 
     fn poll(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
-        let mut s = self.i.lock().unwrap();
-        let mut remove_me = Vec::new();
-        let mut new_rets = Vec::new();
-        for (i, f) in s.futures.iter_mut() {
-            if let std::task::Poll::Ready(ret) = f.as_mut().poll(cx) {
-                new_rets.push(ret);
-                remove_me.push(i.to_owned());
+        let mut results = FuturesHashSetInternal::poll_woken(&self.i, cx, false);
+        let mut s = self.i.lock_recover();
+        s.gathered_outs.append(&mut results);
+        if s.is_empty() {
+            // The results are handed over once. Polling the finished set again gives the results of futures added since then,
+            // which is an empty list when no futures were added.
+            return Poll::Ready(std::mem::take(&mut s.gathered_outs));
+        }
+        Poll::Pending
+    }
+}
+
+/// Await a list of futures at the same time, returning their outputs in the order of the list
+pub async fn join_all<F: Future>(futures: Vec<F>) -> Vec<F::Output> {
+    let mut futures: Vec<Pin<Box<F>>> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+    std::future::poll_fn(|cx| {
+        let mut finished = true;
+        for (f, out) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if out.is_none() {
+                match f.as_mut().poll(cx) {
+                    Poll::Ready(v) => *out = Some(v),
+                    Poll::Pending => finished = false,
+                }
             }
         }
-        s.gathered_outs.append(&mut new_rets);
-        for i in remove_me {
-            s.futures.remove(&i);
+        if finished {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
         }
-        if s.futures.is_empty() {
-            return std::task::Poll::Ready(s.gathered_outs.clone());
+    })
+    .await;
+    outputs.into_iter().flatten().collect()
+}
+
+/// Runs a function when dropped. A future that is cancelled is dropped without finishing, a guard held by the future runs its
+/// cleanup either way.
+pub struct DropGuard<F: FnOnce()> {
+    /// The function to run
+    f: Option<F>,
+}
+
+impl<F: FnOnce()> DropGuard<F> {
+    /// Construct a guard that runs the function when dropped
+    pub fn new(f: F) -> Self {
+        Self { f: Some(f) }
+    }
+}
+
+impl<F: FnOnce()> Drop for DropGuard<F> {
+    fn drop(&mut self) {
+        if let Some(f) = self.f.take() {
+            f();
         }
-        return std::task::Poll::Pending;
     }
 }
 
-/// A set of futures, returning a stream of T
-pub struct FuturesHashSet<T> {
-    i: Arc<Mutex<FuturesHashSetInternal<T>>>,
+/// A set of futures, returning a stream of T. The stream waits for more futures when the set is empty, unless the set is closed
+/// with [FuturesHashSetInternal::close] or [Self::closing_when_empty].
+pub struct FuturesHashSet<T, B = LocalFuture<T>> {
+    i: Arc<Mutex<FuturesHashSetInternal<T, B>>>,
 }
 
-impl<T> Clone for FuturesHashSet<T> {
+/// A set of [Send] futures, returning a stream of T
+///
+/// ```
+/// fn assert_send_sync<T: Send + Sync>() {}
+/// assert_send_sync::<egui_multiwin::future_set::SendFuturesHashSet<u32>>();
+/// ```
+///
+/// Futures that are not [Send] cannot be added.
+///
+/// ```compile_fail
+/// let set = egui_multiwin::future_set::SendFuturesHashSet::<u32>::default();
+/// let local = std::rc::Rc::new(5);
+/// set.get().add_future(async move { *local });
+/// ```
+pub type SendFuturesHashSet<T> = FuturesHashSet<T, SendFuture<T>>;
+
+impl<T, B> Clone for FuturesHashSet<T, B> {
     fn clone(&self) -> Self {
         Self { i: self.i.clone() }
     }
@@ -153,41 +503,278 @@ impl<T> Clone for FuturesHashSet<T> {
 impl<T> FuturesHashSet<T> {
     /// Construct a new self
     pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, B> Default for FuturesHashSet<T, B> {
+    fn default() -> Self {
         Self {
             i: Arc::new(Mutex::new(FuturesHashSetInternal::new())),
         }
     }
+}
+
+impl<T, B> FuturesHashSet<T, B> {
+    /// Make the stream end when the set becomes empty after having held a future, see [FuturesHashSetInternal::close_when_empty]
+    pub fn closing_when_empty(self) -> Self {
+        self.get().close_when_empty();
+        self
+    }
 
     /// Get a reference to the inside
-    pub fn get(&self) -> MutexGuard<'_, FuturesHashSetInternal<T>> {
-        self.i.lock().unwrap()
+    pub fn get(&self) -> SetGuard<'_, T, B> {
+        SetGuard::new(&self.i)
     }
 }
 
-impl<T: Clone> futures_lite::Stream for FuturesHashSet<T> {
+impl<T, B: Future<Output = T> + Unpin> futures_lite::Stream for FuturesHashSet<T, B> {
     type Item = T;
 
     fn poll_next(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        let mut s = self.i.lock().unwrap();
-        let mut remove_me = Vec::new();
-        let mut next_val = None;
-        for (i, f) in s.futures.iter_mut() {
-            if let std::task::Poll::Ready(ret) = f.as_mut().poll(cx) {
-                remove_me.push(i.to_owned());
-                next_val = Some(ret);
-                break;
-            }
+        let results = FuturesHashSetInternal::poll_woken(&self.i, cx, false);
+        let mut s = self.i.lock_recover();
+        // Results that are not returned now are kept for the next calls
+        s.ready.extend(results);
+        match s.ready.pop_front() {
+            Some(ret) => Poll::Ready(Some(ret)),
+            None if s.is_finished() => Poll::Ready(None),
+            None => Poll::Pending,
         }
-        for i in remove_me {
-            s.futures.remove(&i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// A waker that records when it is woken
+    #[derive(Default)]
+    struct FlagWaker(AtomicBool);
+
+    impl Wake for FlagWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
         }
-        if next_val.is_some() {
-            std::task::Poll::Ready(next_val)
-        } else {
-            std::task::Poll::Pending
+    }
+
+    impl FlagWaker {
+        /// Returns true if the waker was woken, clearing the flag
+        fn woken(&self) -> bool {
+            self.0.swap(false, Ordering::SeqCst)
+        }
+    }
+
+    #[test]
+    fn first_wakes_when_a_future_is_added() {
+        let flag = Arc::new(FlagWaker::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut set = FuturesHashSetFirst::new();
+        assert!(Pin::new(&mut set).poll(&mut cx).is_pending());
+        set.get().add_future(async { 5 });
+        assert!(flag.woken());
+        assert_eq!(Pin::new(&mut set).poll(&mut cx), Poll::Ready(5));
+    }
+
+    #[test]
+    fn stream_wakes_when_a_future_is_added() {
+        let flag = Arc::new(FlagWaker::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut set = FuturesHashSet::new();
+        assert!(futures_lite::Stream::poll_next(Pin::new(&mut set), &mut cx).is_pending());
+        set.get().add_future(async { 5 });
+        assert!(flag.woken());
+        assert_eq!(
+            futures_lite::Stream::poll_next(Pin::new(&mut set), &mut cx),
+            Poll::Ready(Some(5))
+        );
+    }
+
+    #[test]
+    fn stream_wakes_when_a_future_is_added_mid_stream() {
+        let flag = Arc::new(FlagWaker::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut set = FuturesHashSet::new();
+        set.get().add_future(async { 1 });
+        set.get().add_future(std::future::pending::<u8>());
+        assert_eq!(
+            futures_lite::Stream::poll_next(Pin::new(&mut set), &mut cx),
+            Poll::Ready(Some(1))
+        );
+        assert!(futures_lite::Stream::poll_next(Pin::new(&mut set), &mut cx).is_pending());
+        flag.woken();
+        set.get().add_future(async { 2 });
+        assert!(flag.woken());
+        assert_eq!(
+            futures_lite::Stream::poll_next(Pin::new(&mut set), &mut cx),
+            Poll::Ready(Some(2))
+        );
+    }
+
+    #[test]
+    fn stream_yields_every_future_that_finishes_together() {
+        let flag = Arc::new(FlagWaker::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut set = FuturesHashSet::new();
+        for i in 0..3 {
+            set.get().add_future(async move { i });
+        }
+        let mut results = Vec::new();
+        while let Poll::Ready(Some(r)) =
+            futures_lite::Stream::poll_next(Pin::new(&mut set), &mut cx)
+        {
+            results.push(r);
+        }
+        results.sort();
+        assert_eq!(results, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn only_woken_futures_are_polled() {
+        let flag = Arc::new(FlagWaker::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let polls = Arc::new(AtomicUsize::new(0));
+        let mut set = FuturesHashSet::new();
+        for _ in 0..10 {
+            let polls = polls.clone();
+            set.get()
+                .add_future(futures_lite::future::poll_fn(move |_| {
+                    polls.fetch_add(1, Ordering::SeqCst);
+                    Poll::<u8>::Pending
+                }));
         }
+        assert!(futures_lite::Stream::poll_next(Pin::new(&mut set), &mut cx).is_pending());
+        assert_eq!(polls.load(Ordering::SeqCst), 10);
+        set.get().add_future(async { 1 });
+        assert_eq!(
+            futures_lite::Stream::poll_next(Pin::new(&mut set), &mut cx),
+            Poll::Ready(Some(1))
+        );
+        assert_eq!(polls.load(Ordering::SeqCst), 10);
+    }
+
+    #[test]
+    fn all_hands_over_results_once() {
+        /// A result that cannot be cloned
+        #[derive(Debug, PartialEq)]
+        struct Handle(u8);
+        let flag = Arc::new(FlagWaker::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut set = FuturesHashSetAll::new();
+        set.get().add_future(async { Handle(1) });
+        assert_eq!(
+            Pin::new(&mut set).poll(&mut cx),
+            Poll::Ready(vec![Handle(1)])
+        );
+        assert_eq!(Pin::new(&mut set).poll(&mut cx), Poll::Ready(Vec::new()));
+    }
+
+    #[test]
+    fn all_wakes_when_the_last_future_is_removed() {
+        let flag = Arc::new(FlagWaker::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut set = FuturesHashSetAll::new();
+        let id = set.get().add_future(std::future::pending::<u8>());
+        assert!(Pin::new(&mut set).poll(&mut cx).is_pending());
+        set.get().remove_future(id);
+        assert!(flag.woken());
+        assert_eq!(Pin::new(&mut set).poll(&mut cx), Poll::Ready(Vec::new()));
+    }
+
+    #[test]
+    fn cancelled_future_runs_its_guard() {
+        let flag = Arc::new(FlagWaker::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let cleaned = Arc::new(AtomicBool::new(false));
+        let c = cleaned.clone();
+        let mut set = FuturesHashSetAll::new();
+        let id = set.get().add_future(async move {
+            let _guard = DropGuard::new(move || c.store(true, Ordering::SeqCst));
+            std::future::pending::<()>().await;
+        });
+        assert!(Pin::new(&mut set).poll(&mut cx).is_pending());
+        assert!(set.get().contains(id));
+        assert_eq!(set.get().len(), 1);
+        assert!(set.get().cancel(id));
+        assert!(cleaned.load(Ordering::SeqCst));
+        assert!(flag.woken());
+        assert!(!set.get().cancel(id));
+        assert_eq!(Pin::new(&mut set).poll(&mut cx), Poll::Ready(Vec::new()));
+    }
+
+    #[test]
+    fn future_cancels_itself_while_polled() {
+        let flag = Arc::new(FlagWaker::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut set = FuturesHashSet::new();
+        let other = set.clone();
+        let id = Arc::new(AtomicUsize::new(0));
+        let own = id.clone();
+        let added = set.get().add_future(async move {
+            other.get().cancel(own.load(Ordering::SeqCst) as u32);
+            1
+        });
+        id.store(added as usize, Ordering::SeqCst);
+        assert!(futures_lite::Stream::poll_next(Pin::new(&mut set), &mut cx).is_pending());
+        assert!(set.get().is_empty());
+    }
+
+    #[test]
+    fn closed_stream_ends_after_the_futures_finish() {
+        let flag = Arc::new(FlagWaker::default());
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut set = FuturesHashSet::new();
+        set.get().add_future(async { 1 });
+        set.get().close();
+        let next = |set: &mut FuturesHashSet<u8>, cx: &mut Context<'_>| {
+            futures_lite::Stream::poll_next(Pin::new(set), cx)
+        };
+        assert_eq!(next(&mut set, &mut cx), Poll::Ready(Some(1)));
+        assert_eq!(next(&mut set, &mut cx), Poll::Ready(None));
+
+        let mut set = FuturesHashSet::new().closing_when_empty();
+        assert!(next(&mut set, &mut cx).is_pending());
+        set.get().add_future(async { 2 });
+        assert!(flag.woken());
+        assert_eq!(next(&mut set, &mut cx), Poll::Ready(Some(2)));
+        assert_eq!(next(&mut set, &mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn send_set_is_polled_on_another_thread() {
+        let set = SendFuturesHashSetFirst::default();
+        set.get().add_future(async { 7 });
+        let other = set.clone();
+        let result = std::thread::spawn(move || futures_lite::future::block_on(other))
+            .join()
+            .unwrap();
+        assert_eq!(result, 7);
+        assert!(set.get().is_empty());
+    }
+
+    #[test]
+    fn join_all_keeps_the_order_of_the_list() {
+        let slow = |n: u8, yields: u8| async move {
+            for _ in 0..yields {
+                futures_lite::future::yield_now().await;
+            }
+            n
+        };
+        let joined = join_all(vec![slow(1, 3), slow(2, 0), slow(3, 1)]);
+        assert_eq!(futures_lite::future::block_on(joined), [1, 2, 3]);
     }
 }
@@ -0,0 +1,117 @@
+//! Contains the global hotkeys of the application. Global hotkeys are reported even when no window of the application has focus.
+//! Registering global hotkeys requires the `hotkeys` feature. On linux, global hotkeys require an x11 display.
+
+/// The errors that can occur when registering global hotkeys
+#[derive(thiserror::Error, Debug)]
+pub enum HotkeyError {
+    /// Global hotkeys are not supported on this platform, or the `hotkeys` feature is not enabled
+    #[error("Global hotkeys are not supported")]
+    Unsupported,
+    /// The description of the hotkey could not be parsed
+    #[error("Invalid hotkey {0}")]
+    Parse(String),
+    /// The hotkey is already registered, by this application or another application
+    #[error("The hotkey {0} is already registered")]
+    Conflict(String),
+    /// The operating system reported an error
+    #[error("Unable to register the hotkey: {0}")]
+    Os(String),
+}
+
+/// The global hotkeys of an application. Every hotkey is unregistered when this is dropped. Only one set of global hotkeys can report
+/// events in an application.
+pub struct GlobalHotkeys {
+    /// The manager of the hotkeys
+    #[cfg(feature = "hotkeys")]
+    manager: global_hotkey::GlobalHotKeyManager,
+    /// The registered hotkeys
+    #[cfg(feature = "hotkeys")]
+    hotkeys: Vec<global_hotkey::hotkey::HotKey>,
+    /// Receives the ids of hotkeys when they are pressed
+    events: async_channel::Receiver<u32>,
+}
+
+impl GlobalHotkeys {
+    /// Prepare for registering global hotkeys
+    pub fn new() -> Result<Self, HotkeyError> {
+        #[cfg(feature = "hotkeys")]
+        {
+            #[cfg(target_os = "linux")]
+            if std::env::var_os("DISPLAY").is_none() {
+                return Err(HotkeyError::Unsupported);
+            }
+            let manager = global_hotkey::GlobalHotKeyManager::new()
+                .map_err(|e| HotkeyError::Os(e.to_string()))?;
+            let (sender, events) = async_channel::unbounded();
+            global_hotkey::GlobalHotKeyEvent::set_event_handler(Some(
+                move |e: global_hotkey::GlobalHotKeyEvent| {
+                    if e.state == global_hotkey::HotKeyState::Pressed {
+                        let _ = sender.try_send(e.id);
+                    }
+                },
+            ));
+            Ok(Self {
+                manager,
+                hotkeys: Vec::new(),
+                events,
+            })
+        }
+        #[cfg(not(feature = "hotkeys"))]
+        Err(HotkeyError::Unsupported)
+    }
+
+    /// Register a hotkey from a description such as `"ctrl+shift+KeyP"`. The returned id is reported when the hotkey is pressed.
+    pub fn register(&mut self, hotkey: &str) -> Result<u32, HotkeyError> {
+        #[cfg(feature = "hotkeys")]
+        {
+            let hk: global_hotkey::hotkey::HotKey =
+                hotkey
+                    .parse()
+                    .map_err(|e: global_hotkey::hotkey::HotKeyParseError| {
+                        HotkeyError::Parse(e.to_string())
+                    })?;
+            if self.hotkeys.contains(&hk) {
+                return Err(HotkeyError::Conflict(hotkey.to_string()));
+            }
+            self.manager.register(hk).map_err(|e| match e {
+                global_hotkey::Error::AlreadyRegistered(_)
+                | global_hotkey::Error::FailedToRegister(_) => {
+                    HotkeyError::Conflict(hotkey.to_string())
+                }
+                e => HotkeyError::Os(e.to_string()),
+            })?;
+            self.hotkeys.push(hk);
+            Ok(hk.id())
+        }
+        #[cfg(not(feature = "hotkeys"))]
+        {
+            let _ = hotkey;
+            Err(HotkeyError::Unsupported)
+        }
+    }
+
+    /// Unregister a hotkey by the id returned from [Self::register]
+    pub fn unregister(&mut self, id: u32) {
+        #[cfg(feature = "hotkeys")]
+        if let Some(i) = self.hotkeys.iter().position(|h| h.id() == id) {
+            let hk = self.hotkeys.remove(i);
+            let _ = self.manager.unregister(hk);
+        }
+        #[cfg(not(feature = "hotkeys"))]
+        let _ = id;
+    }
+
+    /// Wait for the next hotkey to be pressed, returning the id of the hotkey. Returns None if events can no longer be received.
+    pub async fn next_event(&self) -> Option<u32> {
+        self.events.recv().await.ok()
+    }
+}
+
+impl Drop for GlobalHotkeys {
+    fn drop(&mut self) {
+        #[cfg(feature = "hotkeys")]
+        if let Err(e) = self.manager.unregister_all(&self.hotkeys) {
+            crate::trace::warn(format_args!("Unable to unregister global hotkeys: {}", e));
+        }
+    }
+}
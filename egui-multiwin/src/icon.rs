@@ -0,0 +1,91 @@
+//! Contains the icons that can be given to windows
+
+/// The errors that can occur when creating a window icon
+#[derive(thiserror::Error, Debug)]
+pub enum IconError {
+    /// The width or height of the icon is zero
+    #[error("The icon has no pixels")]
+    Empty,
+    /// The number of bytes does not match the dimensions of the icon
+    #[error("The icon is {width}x{height} and needs {expected} bytes of rgba data, but {actual} bytes were given")]
+    WrongSize {
+        /// The width of the icon
+        width: u32,
+        /// The height of the icon
+        height: u32,
+        /// The number of bytes required
+        expected: usize,
+        /// The number of bytes given
+        actual: usize,
+    },
+    /// The icon was rejected by the windowing system
+    #[error("The icon is invalid: {0}")]
+    Invalid(#[from] async_winit::window::BadIcon),
+    /// The image could not be decoded
+    #[cfg(feature = "image")]
+    #[error("Unable to decode the icon image: {0}")]
+    Decode(#[from] image::ImageError),
+}
+
+/// An icon for a window, shown in the title bar and taskbar on platforms that support it. The data is checked when the icon is created.
+#[derive(Clone)]
+pub struct WindowIcon {
+    /// The checked icon
+    icon: async_winit::window::Icon,
+}
+
+impl WindowIcon {
+    /// Create an icon from rgba pixel data, 4 bytes per pixel, row by row starting from the top left
+    pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, IconError> {
+        check_size(rgba.len(), width, height)?;
+        Ok(Self {
+            icon: async_winit::window::Icon::from_rgba(rgba, width, height)?,
+        })
+    }
+
+    /// Create an icon from the bytes of a png image
+    #[cfg(feature = "image")]
+    pub fn from_png(data: &[u8]) -> Result<Self, IconError> {
+        let image =
+            image::load_from_memory_with_format(data, image::ImageFormat::Png)?.into_rgba8();
+        let (width, height) = image.dimensions();
+        Self::from_rgba(image.into_raw(), width, height)
+    }
+
+    /// Get the icon for giving to a window builder
+    pub fn icon(&self) -> async_winit::window::Icon {
+        self.icon.clone()
+    }
+}
+
+/// Check that the length of rgba data matches the dimensions of an icon
+fn check_size(len: usize, width: u32, height: u32) -> Result<(), IconError> {
+    if width == 0 || height == 0 {
+        return Err(IconError::Empty);
+    }
+    let expected = width as usize * height as usize * 4;
+    if len != expected {
+        return Err(IconError::WrongSize {
+            width,
+            height,
+            expected,
+            actual: len,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icon_size_is_checked() {
+        assert!(check_size(16, 2, 2).is_ok());
+        assert!(matches!(check_size(0, 0, 2), Err(IconError::Empty)));
+        assert!(matches!(
+            check_size(15, 2, 2),
+            Err(IconError::WrongSize { expected: 16, .. })
+        ));
+    }
+}
@@ -17,6 +17,10 @@
 //!
 //! It will be useful to run `cargo doc --open` on your application to fully see the documentation for this module. This is because the majority of the code is generated by a pair of macros.
 //!
+//! The event loop is driven by async_winit, so the application may use any executor. The `tokio` feature (enabled by default) reads font
+//! files with tokio, which then needs a tokio runtime. The `smol` and `async-std` features re-export those executors for applications,
+//! without the `tokio` feature files are read on the thread pool of the blocking crate.
+//!
 //! See the examples in the repository for example applications that can be used to start your application.
 //!
 //! Check github issues to see if wayland (linux) still has a problem with the clipboard. That issue should give a temporary solution to a segfault that
@@ -31,34 +35,180 @@
 #![deny(clippy::missing_docs_in_private_items)]
 
 pub use {
-    arboard, async_channel, async_winit, egui, egui_glow_async, enum_dispatch, futures_lite,
-    glutin, rand, raw_window_handle_5, raw_window_handle_6, thiserror,
+    arboard, async_channel, async_lock, async_winit, egui, egui_glow_async, enum_dispatch,
+    futures_lite, glutin, log, raw_window_handle_5, raw_window_handle_6, thiserror,
 };
+
+#[cfg(feature = "async-std")]
+pub use async_std;
+#[cfg(feature = "image-loaders")]
+pub use egui_extras;
+#[cfg(feature = "smol")]
+pub use smol;
+#[cfg(feature = "tokio")]
+pub use tokio;
+pub mod clipboard;
+pub mod drag;
+pub mod error;
+pub mod fonts;
+pub mod hotkeys;
+pub mod icon;
+pub mod modal;
+pub mod monitor;
 pub mod multi_window;
+pub mod opacity;
+pub mod owner;
+pub mod persistence;
+pub mod power;
+pub mod profile;
+pub mod registry;
+pub mod screenshot;
+pub mod session;
+pub mod sync;
+pub mod testing;
+pub mod textures;
+pub mod trace;
 pub mod tracked_window;
+pub mod tray;
+pub mod viewport;
 
 pub mod future_set;
 
-/// Represents the events that we care about
+pub use error::Error;
+
+/// Represents the events that we care about. The window futures hold locks on their windows while awaiting, so they are not [Send]
+/// and the local future sets are used, even though the event loop is [async_winit::ThreadSafe].
 pub struct Events {
     /// For root windows
     pub window_close: future_set::FuturesHashSetAll<()>,
     /// For non-root windows
     pub non_root_windows: future_set::FuturesHashSet<()>,
+    /// For background futures that do not keep the application alive. They are cancelled when the application exits.
+    pub daemons: future_set::FuturesHashSet<()>,
+    /// Asks a window to close, by window id. Used to close the windows that are still open when the application exits.
+    pub close_handles: std::collections::HashMap<registry::WindowId, sync::Notify>,
 }
 
 impl Events {
     /// Construct a new event handler
     pub fn new() -> Self {
+        Self::with_daemons(future_set::FuturesHashSet::new())
+    }
+
+    /// Construct a new event handler, using a set of background futures that may already contain futures
+    pub fn with_daemons(daemons: future_set::FuturesHashSet<()>) -> Self {
         Self {
             window_close: future_set::FuturesHashSetAll::new(),
             non_root_windows: future_set::FuturesHashSet::new(),
+            daemons,
+            close_handles: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Remember the handle that asks a window to close. The handles of windows that already closed are discarded.
+    pub fn add_close_handle(&mut self, id: registry::WindowId, handle: sync::Notify) {
+        self.close_handles.retain(|_, h| !h.is_closed());
+        self.close_handles.insert(id, handle);
+    }
+
+    /// Ask every open window to close. Each window decides with `can_quit`, the same as when its close button is clicked.
+    pub fn request_close_all(&self) {
+        for h in self.close_handles.values() {
+            h.notify();
         }
     }
-}
 
-lazy_static::lazy_static! {
-    /// Mutex used for drawing
-    pub static ref DRAW_MUTEX: tokio::sync::Mutex<bool> = tokio::sync::Mutex::new(false);
+    /// Cancel the background futures, because the application is exiting
+    pub fn cancel_daemons(&self) {
+        let mut d = self.daemons.get();
+        d.clear();
+        d.close();
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Waker};
+
+    #[test]
+    fn daemons_do_not_keep_the_application_alive() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let c = cancelled.clone();
+        let events = Events::new();
+        events.daemons.get().add_future(async move {
+            let _guard = future_set::DropGuard::new(move || c.store(true, Ordering::SeqCst));
+            // A ticker that never stops
+            loop {
+                futures_lite::future::yield_now().await;
+            }
+        });
+        events.window_close.get().add_future(async {});
+        let mut daemons = events.daemons.clone();
+        assert!(futures_lite::Stream::poll_next(Pin::new(&mut daemons), &mut cx).is_pending());
+        let mut wc = events.window_close.clone();
+        assert_eq!(Pin::new(&mut wc).poll(&mut cx), Poll::Ready(vec![()]));
+        events.cancel_daemons();
+        assert!(cancelled.load(Ordering::SeqCst));
+        assert_eq!(
+            futures_lite::Stream::poll_next(Pin::new(&mut daemons), &mut cx),
+            Poll::Ready(None)
+        );
+    }
+
+    #[test]
+    fn daemons_can_add_and_cancel_daemons() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let events = Events::new();
+        let started = Arc::new(AtomicBool::new(false));
+        let (daemons, s) = (events.daemons.clone(), started.clone());
+        events.daemons.get().add_future(async move {
+            let other = daemons.clone();
+            // The guard of the ticker uses the set of daemons while the daemons are being cancelled
+            let ticker = daemons.get().add_future(async move {
+                let _guard = future_set::DropGuard::new(move || {
+                    other.get().cancel(0);
+                });
+                s.store(true, Ordering::SeqCst);
+                std::future::pending::<()>().await;
+            });
+            assert!(daemons.get().contains(ticker));
+        });
+        let mut d = events.daemons.clone();
+        assert_eq!(
+            futures_lite::Stream::poll_next(Pin::new(&mut d), &mut cx),
+            Poll::Ready(Some(()))
+        );
+        assert!(futures_lite::Stream::poll_next(Pin::new(&mut d), &mut cx).is_pending());
+        assert!(started.load(Ordering::SeqCst));
+        events.cancel_daemons();
+        assert!(events.daemons.get().is_empty());
+    }
+
+    #[test]
+    fn close_handles_of_closed_windows_are_discarded() {
+        let mut events = Events::new();
+        let first = sync::Notify::new();
+        events.add_close_handle(registry::WindowId::allocate(), first.clone());
+        first.close();
+        let second = sync::Notify::new();
+        events.add_close_handle(registry::WindowId::allocate(), second.clone());
+        assert_eq!(events.close_handles.len(), 1);
+        events.request_close_all();
+        assert!(futures_lite::future::block_on(second.wait()));
+    }
+
+    /// A waker that does nothing
+    struct NoopWaker;
+
+    impl std::task::Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+}
@@ -0,0 +1,118 @@
+//! Contains the modal windows of the application. A modal window blocks its parent window while it is open: the parent ignores the
+//! keyboard and pointer, is drawn dimmed by a scrim, and focusing the parent focuses the modal window instead. A modal window can have
+//! a modal window of its own, which blocks it in turn, so that only the newest modal window of a chain receives input. The parent is
+//! unblocked when its modal window closes.
+
+use std::sync::{Arc, Mutex};
+
+use crate::registry::WindowId;
+use crate::sync::LockRecover;
+
+/// The color drawn over the parent of a modal window, unless the modal window chooses another
+pub const DEFAULT_SCRIM: egui::Color32 = egui::Color32::from_black_alpha(96);
+
+/// A modal window that is open
+struct Modal {
+    /// The id of the modal window
+    id: WindowId,
+    /// The window blocked by the modal window
+    parent: WindowId,
+    /// The color drawn over the parent, None to not dim the parent
+    scrim: Option<egui::Color32>,
+}
+
+/// The modal windows that are open. Cloning gives another handle to the same modal windows.
+#[derive(Clone, Default)]
+pub struct Modals {
+    /// The modal windows, in the order they were opened
+    i: Arc<Mutex<Vec<Modal>>>,
+}
+
+impl Modals {
+    /// Make a window modal for a parent window, drawing a scrim of a color over the parent while it is blocked
+    pub fn open(&self, id: WindowId, parent: WindowId, scrim: Option<egui::Color32>) {
+        if id == parent {
+            crate::trace::warn(format_args!("Window {} can not be modal for itself", id));
+            return;
+        }
+        self.i.lock_recover().push(Modal { id, parent, scrim });
+    }
+
+    /// Forget a window that closed, returning the parent it was blocking when it was a modal window. Modal windows of the closed window
+    /// no longer block anything.
+    pub fn window_closed(&self, id: WindowId) -> Option<WindowId> {
+        let mut i = self.i.lock_recover();
+        let parent = i.iter().find(|m| m.id == id).map(|m| m.parent);
+        i.retain(|m| m.id != id && m.parent != id);
+        parent
+    }
+
+    /// The newest modal window blocking a window, None when the window receives input
+    pub fn blocker(&self, id: WindowId) -> Option<WindowId> {
+        self.blocking(id).map(|(modal, _)| modal)
+    }
+
+    /// The color drawn over a window blocked by a modal window, None when the window is not blocked or is not dimmed
+    pub fn scrim(&self, id: WindowId) -> Option<egui::Color32> {
+        self.blocking(id).and_then(|(_, scrim)| scrim)
+    }
+
+    /// The modal window at the end of the chain of modal windows blocking a window, which is the window receiving input. None when the
+    /// window is not blocked.
+    pub fn top(&self, id: WindowId) -> Option<WindowId> {
+        let mut top = self.blocker(id)?;
+        // Each step moves to a newer modal window, so the chain ends
+        while let Some(next) = self.blocker(top) {
+            top = next;
+        }
+        Some(top)
+    }
+
+    /// The newest modal window blocking a window and its scrim
+    fn blocking(&self, id: WindowId) -> Option<(WindowId, Option<egui::Color32>)> {
+        self.i
+            .lock_recover()
+            .iter()
+            .rev()
+            .find(|m| m.parent == id)
+            .map(|m| (m.id, m.scrim))
+    }
+}
+
+/// Dim a window blocked by a modal window, drawing a color over the whole window above everything drawn in the frame
+pub fn paint_scrim(ctx: &egui::Context, color: egui::Color32) {
+    let layer = egui::LayerId::new(
+        egui::Order::Tooltip,
+        egui::Id::new("egui_multiwin_modal_scrim"),
+    );
+    ctx.layer_painter(layer)
+        .rect_filled(ctx.screen_rect(), 0.0, color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_modals_stack() {
+        let modals = Modals::default();
+        let (main, dialog, confirm) = (
+            WindowId::allocate(),
+            WindowId::allocate(),
+            WindowId::allocate(),
+        );
+        modals.open(dialog, main, Some(DEFAULT_SCRIM));
+        modals.open(confirm, dialog, None);
+        assert_eq!(modals.blocker(main), Some(dialog));
+        assert_eq!(modals.top(main), Some(confirm));
+        assert_eq!(modals.scrim(main), Some(DEFAULT_SCRIM));
+        assert_eq!(modals.blocker(dialog), Some(confirm));
+        assert_eq!(modals.scrim(dialog), None);
+        assert_eq!(modals.top(confirm), None);
+        assert_eq!(modals.window_closed(confirm), Some(dialog));
+        assert_eq!(modals.top(main), Some(dialog));
+        assert_eq!(modals.blocker(dialog), None);
+        assert_eq!(modals.window_closed(dialog), Some(main));
+        assert_eq!(modals.blocker(main), None);
+    }
+}
@@ -0,0 +1,292 @@
+//! Contains the information about monitors, and the selection of a monitor for placing windows.
+
+/// Information about a monitor. Positions and sizes are in physical pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorInfo {
+    /// The name of the monitor, if it is known
+    pub name: Option<String>,
+    /// The position of the top left corner of the monitor
+    pub position: (i32, i32),
+    /// The size of the monitor
+    pub size: (u32, u32),
+    /// The scale factor of the monitor
+    pub scale_factor: f64,
+    /// The refresh rate of the monitor in millihertz, if it is known
+    pub refresh_rate_millihertz: Option<u32>,
+    /// True when this is the primary monitor
+    pub primary: bool,
+}
+
+impl MonitorInfo {
+    /// Get the information of a monitor
+    fn from_handle(
+        m: &async_winit::monitor::MonitorHandle,
+        primary: Option<&async_winit::monitor::MonitorHandle>,
+    ) -> Self {
+        Self {
+            name: m.name(),
+            position: (m.position().x, m.position().y),
+            size: (m.size().width, m.size().height),
+            scale_factor: m.scale_factor(),
+            refresh_rate_millihertz: m.refresh_rate_millihertz(),
+            primary: primary
+                .map(|p| p.name() == m.name() && p.position() == m.position())
+                .unwrap_or(false),
+        }
+    }
+
+    /// Returns true when a point is on the monitor
+    fn contains(&self, point: (i32, i32)) -> bool {
+        let (x, y) = (self.position.0 as i64, self.position.1 as i64);
+        let (px, py) = (point.0 as i64, point.1 as i64);
+        px >= x && px < x + self.size.0 as i64 && py >= y && py < y + self.size.1 as i64
+    }
+
+    /// Compute the position of a window moved from another monitor to this monitor, keeping its placement relative to the monitor.
+    /// A window that was not on a known monitor is placed near the top left corner of this monitor.
+    pub fn place(&self, from: Option<&MonitorInfo>, position: (i32, i32)) -> (i32, i32) {
+        let (fx, fy) = match from {
+            Some(f) if f.size.0 > 0 && f.size.1 > 0 => (
+                (position.0 as i64 - f.position.0 as i64) as f64 / f.size.0 as f64,
+                (position.1 as i64 - f.position.1 as i64) as f64 / f.size.1 as f64,
+            ),
+            _ => (0.1, 0.1),
+        };
+        (
+            self.position.0 + (fx.clamp(0.0, 1.0) * self.size.0 as f64) as i32,
+            self.position.1 + (fy.clamp(0.0, 1.0) * self.size.1 as f64) as i32,
+        )
+    }
+}
+
+/// Selects a monitor for placing a window
+#[derive(Clone, Debug, PartialEq)]
+pub enum MonitorSelector {
+    /// The primary monitor
+    Primary,
+    /// The monitor the window is on. New windows use the primary monitor.
+    Current,
+    /// A monitor by its position in the list of monitors, see [monitors]
+    Index(usize),
+    /// A monitor by its name
+    Name(String),
+    /// The monitor after the one the window is on, wrapping around to the first monitor
+    Next,
+}
+
+impl MonitorSelector {
+    /// Choose a monitor, given the index of the monitor the window is currently on. A monitor that is not available, such as a monitor
+    /// that was unplugged, selects the primary monitor instead, or the first monitor when the primary monitor is not known.
+    /// Returns None only when there are no monitors.
+    pub fn select<'a>(
+        &self,
+        monitors: &'a [MonitorInfo],
+        current: Option<usize>,
+    ) -> Option<&'a MonitorInfo> {
+        self.select_index(monitors, current).map(|i| &monitors[i])
+    }
+
+    /// Choose a monitor like [Self::select], returning the index of the monitor
+    pub fn select_index(&self, monitors: &[MonitorInfo], current: Option<usize>) -> Option<usize> {
+        let primary = monitors.iter().position(|m| m.primary);
+        let chosen = match self {
+            Self::Primary => None,
+            Self::Current => current.filter(|i| *i < monitors.len()),
+            Self::Index(i) => Some(*i).filter(|i| *i < monitors.len()),
+            Self::Name(name) => monitors
+                .iter()
+                .position(|m| m.name.as_deref() == Some(name.as_str())),
+            Self::Next => current
+                .or(primary)
+                .filter(|_| !monitors.is_empty())
+                .map(|i| (i + 1) % monitors.len()),
+        };
+        chosen
+            .or(primary)
+            .or_else(|| Some(0).filter(|_| !monitors.is_empty()))
+    }
+}
+
+/// How a window is shown fullscreen
+#[derive(Clone, Debug, PartialEq)]
+pub enum FullscreenMode {
+    /// Borderless fullscreen on a monitor, keeping the video mode of the monitor
+    Borderless(MonitorSelector),
+    /// Exclusive fullscreen on a monitor, changing the video mode of the monitor
+    Exclusive {
+        /// The monitor to use
+        monitor: MonitorSelector,
+        /// The size of the video mode. The largest size is used when not given, otherwise the closest size.
+        size: Option<(u32, u32)>,
+        /// The refresh rate of the video mode. The highest refresh rate is used when not given, otherwise the closest refresh rate.
+        refresh_rate_millihertz: Option<u32>,
+    },
+}
+
+/// Choose the video mode closest to a size and refresh rate from a list of sizes, refresh rates and bit depths. Returns the index of
+/// the mode, or None when there are no modes. The size is matched first, then the refresh rate, then the highest bit depth is preferred.
+pub fn closest_video_mode(
+    modes: &[((u32, u32), u32, u16)],
+    size: Option<(u32, u32)>,
+    refresh_rate_millihertz: Option<u32>,
+) -> Option<usize> {
+    modes
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, ((w, h), rate, depth))| {
+            let size_key = match size {
+                Some((sw, sh)) => (*w as u64).abs_diff(sw as u64) + (*h as u64).abs_diff(sh as u64),
+                None => u64::MAX - *w as u64 * *h as u64,
+            };
+            let rate_key = match refresh_rate_millihertz {
+                Some(r) => rate.abs_diff(r),
+                None => u32::MAX - rate,
+            };
+            (size_key, rate_key, std::cmp::Reverse(*depth))
+        })
+        .map(|(i, _)| i)
+}
+
+/// Get the index of the monitor containing a point, such as the position of a window
+pub fn monitor_at(monitors: &[MonitorInfo], point: (i32, i32)) -> Option<usize> {
+    monitors.iter().position(|m| m.contains(point))
+}
+
+/// Get the monitors that are available
+pub async fn monitors(
+    elwt: &async_winit::event_loop::EventLoopWindowTarget<async_winit::ThreadSafe>,
+) -> Vec<MonitorInfo> {
+    let primary = elwt.primary_monitor().await;
+    elwt.available_monitors()
+        .await
+        .map(|m| MonitorInfo::from_handle(&m, primary.as_ref()))
+        .collect()
+}
+
+/// Get the monitors that are available, and the index of the monitor a window is on
+pub async fn window_monitors(
+    window: &async_winit::window::Window<async_winit::ThreadSafe>,
+) -> (Vec<MonitorInfo>, Option<usize>) {
+    let (_, monitors, current) = window_monitor_handles(window).await;
+    (monitors, current)
+}
+
+/// Get the handles and information of the monitors that are available, and the index of the monitor a window is on
+async fn window_monitor_handles(
+    window: &async_winit::window::Window<async_winit::ThreadSafe>,
+) -> (
+    Vec<async_winit::monitor::MonitorHandle>,
+    Vec<MonitorInfo>,
+    Option<usize>,
+) {
+    let primary = window.primary_monitor().await;
+    let handles: Vec<_> = window.available_monitors().await.collect();
+    let monitors: Vec<MonitorInfo> = handles
+        .iter()
+        .map(|m| MonitorInfo::from_handle(m, primary.as_ref()))
+        .collect();
+    let current = window.current_monitor().await.and_then(|c| {
+        let c = MonitorInfo::from_handle(&c, None);
+        monitors
+            .iter()
+            .position(|m| m.name == c.name && m.position == c.position)
+    });
+    (handles, monitors, current)
+}
+
+/// Convert a fullscreen mode for a window. Returns None when no monitor or video mode is available.
+pub async fn fullscreen(
+    window: &async_winit::window::Window<async_winit::ThreadSafe>,
+    mode: &FullscreenMode,
+) -> Option<async_winit::window::Fullscreen> {
+    let (handles, monitors, current) = window_monitor_handles(window).await;
+    match mode {
+        FullscreenMode::Borderless(selector) => {
+            let i = selector.select_index(&monitors, current)?;
+            Some(async_winit::window::Fullscreen::Borderless(Some(
+                handles[i].clone(),
+            )))
+        }
+        FullscreenMode::Exclusive {
+            monitor,
+            size,
+            refresh_rate_millihertz,
+        } => {
+            let i = monitor.select_index(&monitors, current)?;
+            let modes: Vec<_> = handles[i].video_modes().collect();
+            let keys: Vec<_> = modes
+                .iter()
+                .map(|m| {
+                    (
+                        (m.size().width, m.size().height),
+                        m.refresh_rate_millihertz(),
+                        m.bit_depth(),
+                    )
+                })
+                .collect();
+            let mode = closest_video_mode(&keys, *size, *refresh_rate_millihertz)?;
+            Some(async_winit::window::Fullscreen::Exclusive(
+                modes[mode].clone(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Make a monitor for testing
+    fn monitor(name: &str, x: i32, primary: bool) -> MonitorInfo {
+        MonitorInfo {
+            name: Some(name.to_string()),
+            position: (x, 0),
+            size: (1920, 1080),
+            scale_factor: 1.0,
+            refresh_rate_millihertz: None,
+            primary,
+        }
+    }
+
+    #[test]
+    fn missing_monitor_selects_the_primary() {
+        let monitors = [monitor("left", 0, false), monitor("right", 1920, true)];
+        let unplugged = MonitorSelector::Name("gone".to_string());
+        assert_eq!(unplugged.select(&monitors, None), Some(&monitors[1]));
+        assert_eq!(
+            MonitorSelector::Next.select(&monitors, Some(1)),
+            Some(&monitors[0])
+        );
+        assert_eq!(MonitorSelector::Index(4).select(&[], None), None);
+    }
+
+    #[test]
+    fn window_keeps_its_place_on_the_next_monitor() {
+        let (left, right) = (monitor("left", 0, true), monitor("right", 1920, false));
+        assert_eq!(
+            monitor_at(&[left.clone(), right.clone()], (2000, 10)),
+            Some(1)
+        );
+        assert_eq!(right.place(Some(&left), (960, 540)), (2880, 540));
+    }
+
+    #[test]
+    fn closest_video_mode_is_chosen() {
+        let modes = [
+            ((1280, 720), 60000, 32),
+            ((1920, 1080), 60000, 32),
+            ((1920, 1080), 144000, 24),
+            ((1920, 1080), 144000, 32),
+        ];
+        assert_eq!(closest_video_mode(&modes, None, None), Some(3));
+        assert_eq!(
+            closest_video_mode(&modes, Some((1366, 768)), Some(75000)),
+            Some(0)
+        );
+        assert_eq!(
+            closest_video_mode(&modes, Some((1920, 1080)), Some(60000)),
+            Some(1)
+        );
+        assert_eq!(closest_video_mode(&[], None, None), None);
+    }
+}
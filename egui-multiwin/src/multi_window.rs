@@ -1,35 +1,504 @@
 //! This defines the MultiWindow struct. This is the main struct used in the main function of a user application.
 
-/// Create the dynamic tracked_window module for a egui_multiwin application. Takes three arguments. First argument is the type name of the common data structure for your application.
-/// Second argument is the type for custom events (or egui_multiwin::NoEvent if that functionality is not desired). Third argument is the enum of all windows. It needs to be enum_dispatch.
+/// The errors that can occur when registering an application wide keyboard shortcut
+#[derive(thiserror::Error, Debug)]
+pub enum ShortcutError {
+    /// The shortcut is already registered
+    #[error("The shortcut {0:?} is already registered")]
+    Conflict(egui::KeyboardShortcut),
+}
+
+/// The errors that can occur when creating a `MultiWindow`
+#[derive(thiserror::Error, Debug)]
+pub enum MultiWindowError {
+    /// The event loop could not be created, such as on linux without a display server
+    #[error("Unable to create the event loop: {0}")]
+    EventLoop(String),
+}
+
+use std::sync::{Arc, Mutex};
+
+use crate::registry::{WindowId, WindowRegistry};
+use crate::sync::LockRecover;
+use crate::tracked_window::{RepaintThrottle, SharedSetting, ThemePreference};
+
+/// The action run when a window panics while drawing a frame, given the id of the window and the payload of the panic
+pub type PanicAction = Arc<dyn Fn(WindowId, &(dyn std::any::Any + Send)) + Send + Sync>;
+
+/// Run on the egui context of every window before its first frame, see `MultiWindow::with_context_hook`
+pub type ContextHook = Arc<dyn Fn(&egui::Context) + Send + Sync>;
+
+/// Changes the input of a window before each frame, given the id of the window, see `MultiWindow::set_input_filter`
+pub type InputFilter = Arc<dyn Fn(WindowId, &mut egui::RawInput) + Send + Sync>;
+
+/// The settings of the `MultiWindow` that are shared with every window. `L` is the `ShortcutList` of the module generated by the
+/// `multi_window` macro, which names these settings `SharedSettings`.
+#[derive(Clone)]
+pub struct SharedSettings<L> {
+    /// The application wide keyboard shortcuts
+    pub shortcuts: L,
+    /// The theme preference for all windows
+    pub theme: Arc<Mutex<ThemePreference>>,
+    /// Limits the repaints of windows in the background, unless a window overrides it in its options
+    pub repaint_throttle: Arc<Mutex<RepaintThrottle>>,
+    /// Limits the repaints of every window, including the window with focus
+    pub frame_limit: Arc<Mutex<RepaintThrottle>>,
+    /// The power profile that chose the repaint limits
+    pub power_profile: Arc<Mutex<crate::power::PowerProfile>>,
+    /// The style applied to every window
+    pub style: SharedSetting<Option<egui::Style>>,
+    /// The fonts installed in every window
+    pub fonts: SharedSetting<crate::fonts::FontSet>,
+    /// The opengl context shared by windows, when enabled
+    pub gl_share: crate::tracked_window::SharedGlContext,
+    /// Tracks which window has its opengl context current
+    pub contexts: crate::tracked_window::ContextTracker,
+    /// Held by a window while it uses opengl. The draw futures of different windows can interleave at await points, this keeps
+    /// the context operations of one window from running in the middle of the frame of another window.
+    pub draw_lock: Arc<async_lock::Mutex<()>>,
+    /// The icon for windows that do not specify their own icon
+    pub icon: Arc<Mutex<Option<crate::icon::WindowIcon>>>,
+    /// The saved geometry of windows, when persistence is enabled
+    pub persistence: Option<crate::persistence::GeometryStore>,
+    /// The windows that are reopened on the next launch
+    pub session: crate::session::Session,
+    /// Run when a window panics while drawing a frame
+    pub window_panic: Option<PanicAction>,
+    /// Run on the egui context of every window before its first frame
+    pub context_hooks: Vec<ContextHook>,
+    /// The textures shared by all windows
+    pub textures: crate::textures::AppTextureManager,
+    /// Changes the input of every window before each frame
+    pub input_filter: Arc<Mutex<Option<InputFilter>>>,
+    /// The modal windows that are open and the windows they block
+    pub modals: crate::modal::Modals,
+}
+
+impl<L: Default> Default for SharedSettings<L> {
+    fn default() -> Self {
+        Self {
+            shortcuts: L::default(),
+            theme: Arc::new(Mutex::new(ThemePreference::default())),
+            repaint_throttle: Arc::new(Mutex::new(RepaintThrottle::default())),
+            frame_limit: Arc::new(Mutex::new(RepaintThrottle::Unlimited)),
+            power_profile: Arc::new(Mutex::new(crate::power::PowerProfile::default())),
+            style: SharedSetting::new(None),
+            fonts: SharedSetting::new(crate::fonts::FontSet::new()),
+            gl_share: crate::tracked_window::SharedGlContext::default(),
+            contexts: crate::tracked_window::ContextTracker::default(),
+            draw_lock: Arc::new(async_lock::Mutex::new(())),
+            icon: Arc::new(Mutex::new(None)),
+            persistence: None,
+            session: crate::session::Session::default(),
+            window_panic: None,
+            context_hooks: Vec::new(),
+            textures: crate::textures::AppTextureManager::new(),
+            input_filter: Arc::new(Mutex::new(None)),
+            modals: crate::modal::Modals::default(),
+        }
+    }
+}
+
+impl<L> SharedSettings<L> {
+    /// Make a new window modal for its parent and owned by its owner. A modal window is owned by its parent unless it has another
+    /// owner. A parent or owner that is already closed is logged and the window opens as an ordinary window.
+    pub fn attach_window(
+        &self,
+        registry: &WindowRegistry,
+        id: WindowId,
+        window: &async_winit::window::Window<async_winit::ThreadSafe>,
+        modal_for: Option<WindowId>,
+        owned_by: Option<WindowId>,
+        scrim: Option<egui::Color32>,
+    ) {
+        if let Some(parent) = modal_for {
+            match registry.get(parent) {
+                Some(p) => {
+                    self.modals.open(id, parent, scrim);
+                    // The parent is drawn again without input and dimmed
+                    p.window.request_redraw();
+                }
+                None => crate::trace::warn(format_args!(
+                    "The parent {} of modal window {} is closed, opening an ordinary window",
+                    parent, id
+                )),
+            }
+        }
+        if let Some(owner) = owned_by.or(modal_for) {
+            match registry.get(owner) {
+                Some(o) => {
+                    if !crate::owner::set_owner(window, Some(&o.window)) {
+                        crate::trace::debug(format_args!(
+                            "The platform can not set the owner of window {}",
+                            id
+                        ));
+                    }
+                    registry.set_owner(id, owner);
+                }
+                None => crate::trace::warn(format_args!(
+                    "The owner {} of window {} is closed, opening an ordinary window",
+                    owner, id
+                )),
+            }
+        }
+    }
+
+    /// A window gained or lost the focus. Like a native dialog, a modal window of the window takes the focus and asks for attention.
+    pub async fn focus_changed(&self, registry: &WindowRegistry, id: WindowId, focused: bool) {
+        registry.set_focused(id, focused);
+        let modal = self
+            .modals
+            .top(id)
+            .filter(|_| focused)
+            .and_then(|m| registry.get(m));
+        if let Some(modal) = modal {
+            modal.window.focus_window().await;
+            modal
+                .window
+                .request_user_attention(Some(async_winit::window::UserAttentionType::Informational))
+                .await;
+        }
+    }
+
+    /// Forget a window that closed. The window leaves the session and the registry, its textures are freed, and the parent of a modal
+    /// window receives input again. Owned windows close with their owner. They are released first, so that the platform does not
+    /// destroy them along with the owner while they are still running.
+    pub fn window_closed(&self, registry: &WindowRegistry, id: WindowId) {
+        let last_root = registry.task(id).map(|t| t.root).unwrap_or(false) && registry.roots() == 1;
+        self.session.window_closed(id, last_root);
+        for owned in registry.owned(id) {
+            if let Some(info) = registry.get(owned) {
+                crate::owner::set_owner(&info.window, None);
+                info.request_close();
+            }
+        }
+        registry.remove(id);
+        self.textures.window_closed(id);
+        if let Some(parent) = self.modals.window_closed(id).and_then(|p| registry.get(p)) {
+            parent.window.request_redraw();
+        }
+    }
+}
+
+/// The things the run loop of an application waits for. `W` is the `NewWindowRequest` and `T` is the `TimerAction` of the module
+/// generated by the `multi_window` macro.
+pub enum LoopEvent<W, T> {
+    /// Every root window has closed
+    RootsClosed,
+    /// A non-root window has closed
+    WindowClosed,
+    /// A background future has finished
+    DaemonDone,
+    /// A window is to be cancelled
+    Cancel(WindowId),
+    /// An event of the tray icon
+    Tray(crate::tray::TrayEvent),
+    /// A global hotkey was pressed
+    Hotkey(u32),
+    /// The power source of the computer changed
+    Power(crate::power::PowerSource),
+    /// A timer fired with an action for the common data
+    Timer(T),
+    /// Windows were requested
+    NewWindows(Vec<W>),
+}
+
+/// The sources of the [LoopEvent]s of the run loop of an application
+pub struct LoopSources<W, T> {
+    /// Finishes when every root window has closed
+    roots: crate::future_set::FuturesHashSetAll<()>,
+    /// The non-root windows
+    non_roots: crate::future_set::FuturesHashSet<()>,
+    /// The background futures
+    daemons: crate::future_set::FuturesHashSet<()>,
+    /// Receives the windows to be created
+    windows: async_channel::Receiver<Vec<W>>,
+    /// Receives the ids of windows to be cancelled
+    cancel: async_channel::Receiver<WindowId>,
+    /// Receives the actions of timers
+    timers: async_channel::Receiver<T>,
+    /// Watches the power source of the computer
+    power: crate::power::PowerMonitor,
+}
+
+impl<W, T> LoopSources<W, T> {
+    /// Construct the sources for the windows and background futures of the events and the channels of the `MultiWindow`
+    pub fn new(
+        events: &crate::Events,
+        windows: async_channel::Receiver<Vec<W>>,
+        cancel: async_channel::Receiver<WindowId>,
+        timers: async_channel::Receiver<T>,
+    ) -> Self {
+        Self {
+            roots: events.window_close.clone(),
+            non_roots: events.non_root_windows.clone(),
+            daemons: events.daemons.clone(),
+            windows,
+            cancel,
+            timers,
+            power: crate::power::PowerMonitor::new(),
+        }
+    }
+
+    /// Take every window that has been requested, without waiting, so that they are created together
+    pub fn pending_windows(&self) -> Vec<W> {
+        let mut windows = Vec::new();
+        Self::drain(&self.windows, &mut windows);
+        windows
+    }
+
+    /// Take the windows waiting in a channel
+    fn drain(receiver: &async_channel::Receiver<Vec<W>>, windows: &mut Vec<W>) {
+        while let Ok(batch) = receiver.try_recv() {
+            windows.extend(batch);
+        }
+    }
+
+    /// Wait for the next event. Every root window closing is only reported with `exit_check`, which is cleared when exiting is denied
+    /// until another root window is opened. Sources that have ended, such as the set of non-root windows after it is closed, never wake
+    /// the loop again. The sources are raced in a random order, so that a busy source does not starve the others.
+    pub async fn next(
+        &mut self,
+        exit_check: bool,
+        tray: Option<&crate::tray::Tray>,
+        hotkeys: Option<&crate::hotkeys::GlobalHotkeys>,
+    ) -> LoopEvent<W, T> {
+        use futures_lite::FutureExt;
+        let Self {
+            roots,
+            non_roots,
+            daemons,
+            windows,
+            cancel,
+            timers,
+            power,
+        } = self;
+        let roots = async {
+            if exit_check {
+                roots.await;
+                LoopEvent::RootsClosed
+            } else {
+                std::future::pending().await
+            }
+        };
+        let non_root = async {
+            match futures_lite::StreamExt::next(non_roots).await {
+                Some(_) => LoopEvent::WindowClosed,
+                None => std::future::pending().await,
+            }
+        };
+        // Background futures never take part in the decision to exit
+        let daemon = async {
+            match futures_lite::StreamExt::next(daemons).await {
+                Some(_) => LoopEvent::DaemonDone,
+                None => std::future::pending().await,
+            }
+        };
+        let cancel = async {
+            match cancel.recv().await {
+                Ok(id) => LoopEvent::Cancel(id),
+                Err(_) => std::future::pending().await,
+            }
+        };
+        let tray_event = async {
+            match tray {
+                Some(t) => match t.next_event().await {
+                    Some(event) => LoopEvent::Tray(event),
+                    None => std::future::pending().await,
+                },
+                None => std::future::pending().await,
+            }
+        };
+        let hotkey_event = async {
+            match hotkeys {
+                Some(h) => match h.next_event().await {
+                    Some(id) => LoopEvent::Hotkey(id),
+                    None => std::future::pending().await,
+                },
+                None => std::future::pending().await,
+            }
+        };
+        let power_event = async { LoopEvent::Power(power.changed().await) };
+        let timer = async {
+            match timers.recv().await {
+                Ok(action) => LoopEvent::Timer(action),
+                Err(_) => std::future::pending().await,
+            }
+        };
+        let new_windows = async {
+            match windows.recv().await {
+                Ok(mut batch) => {
+                    // Windows requested in the meantime are created together
+                    Self::drain(windows, &mut batch);
+                    LoopEvent::NewWindows(batch)
+                }
+                Err(_) => std::future::pending().await,
+            }
+        };
+        roots
+            .race(non_root)
+            .race(daemon)
+            .race(cancel)
+            .race(tray_event)
+            .race(hotkey_event)
+            .race(power_event)
+            .race(timer)
+            .race(new_windows)
+            .await
+    }
+
+    /// Close the windows that are still open, because the application exits. The non-root windows do not keep the application alive,
+    /// but they get a chance to close themselves with `can_quit`, the same as when their close button is clicked. Windows that have
+    /// not closed within the timeout are dropped. Then the background futures are cancelled.
+    pub async fn shutdown(mut self, events: &crate::Events, timeout: std::time::Duration) {
+        self.non_roots.get().close();
+        events.request_close_all();
+        let non_roots = &mut self.non_roots;
+        let closing = async { while futures_lite::StreamExt::next(non_roots).await.is_some() {} };
+        let late = async {
+            async_winit::Timer::after(timeout).await;
+            crate::trace::warn(format_args!(
+                "Dropping the windows that did not close in time"
+            ));
+        };
+        futures_lite::future::or(closing, late).await;
+        self.non_roots.get().clear();
+        events.cancel_daemons();
+    }
+}
+
+/// Create the dynamic tracked_window module for a egui_multiwin application. First argument is the type name of the common data structure for your application.
+/// Second argument is the enum of all windows. It needs to be enum_dispatch. The async_winit event loop and windows are
+/// [async_winit::ThreadSafe], which the module defines as `ThreadSafety`, so windows can name their window as `Window<ThreadSafety>`.
+///
+/// The enum of the windows implements the generated `TrackedWindow` trait through enum_dispatch, with every window in a variant. The common data
+/// must be `Send` and `'static`, see [crate::tracked_window::CommonData]. The macro checks both, giving an error that names the enum or the
+/// common data when they are not set up this way.
+///
+/// ```no_run
+/// use egui_multiwin::enum_dispatch::enum_dispatch;
+/// use egui_multiwin_dynamic::tracked_window::{RedrawResponse, ThreadSafety, TrackedWindow};
+/// use std::sync::{Arc, Mutex};
+///
+/// /// Macro generated code
+/// pub mod egui_multiwin_dynamic {
+///     egui_multiwin::tracked_window!(crate::AppCommon, crate::MyWindows);
+///     egui_multiwin::multi_window!(crate::AppCommon, crate::MyWindows);
+/// }
+///
+/// /// The data shared by all windows
+/// pub struct AppCommon {}
+///
+/// /// The windows of the application, the attribute names the generated trait
+/// #[enum_dispatch(TrackedWindow)]
+/// pub enum MyWindows {
+///     /// The main window
+///     Main(MainWindow),
+/// }
+///
+/// /// The main window
+/// pub struct MainWindow {}
+///
+/// impl TrackedWindow for MainWindow {
+///     async fn redraw(
+///         &mut self,
+///         _c: &mut AppCommon,
+///         egui: &mut egui_multiwin::egui_glow_async::EguiGlow,
+///         _window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+///         _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+///     ) -> RedrawResponse {
+///         egui_multiwin::egui::CentralPanel::default().show(&egui.egui_ctx, |ui| ui.label("Hello"));
+///         RedrawResponse::default()
+///     }
+/// }
+///
+/// fn main() {}
+/// ```
+///
+/// The generated modules are `pub mod tracked_window` and `pub mod multi_window` by default. The visibility and names of both modules can be
+/// given after a semicolon, tracked_window first, for a window system that is private to a library or for several window systems in one module.
+/// Both macros must be given the same names, since the modules refer to each other. Two window systems have two `TrackedWindow` traits with
+/// the same name, which enum_dispatch can not tell apart, so the windows of at least one system implement the trait without enum_dispatch.
+///
+/// ```no_run
+/// use std::sync::{Arc, Mutex};
+/// use egui_multiwin::egui_glow_async::EguiGlow;
+///
+/// egui_multiwin::tracked_window!(crate::HostCommon, crate::HostWindow; pub(crate) host_windows, host_multi_window);
+/// egui_multiwin::multi_window!(crate::HostCommon, crate::HostWindow; pub(crate) host_windows, host_multi_window);
+/// egui_multiwin::tracked_window!(crate::PluginCommon, crate::PluginWindow; plugin_windows, plugin_multi_window);
+/// egui_multiwin::multi_window!(crate::PluginCommon, crate::PluginWindow; plugin_windows, plugin_multi_window);
+///
+/// /// The data shared by the windows of the host
+/// pub struct HostCommon {}
+///
+/// /// The data shared by the windows of the plugin
+/// pub struct PluginCommon {}
+///
+/// /// The only window of the host
+/// pub struct HostWindow {}
+///
+/// /// The only window of the plugin
+/// pub struct PluginWindow {}
+///
+/// impl host_windows::TrackedWindow for HostWindow {
+///     async fn redraw(
+///         &mut self,
+///         _c: &mut HostCommon,
+///         _egui: &mut EguiGlow,
+///         _window: &egui_multiwin::async_winit::window::Window<host_windows::ThreadSafety>,
+///         _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+///     ) -> host_windows::RedrawResponse {
+///         host_windows::RedrawResponse::default()
+///     }
+/// }
+///
+/// impl plugin_windows::TrackedWindow for PluginWindow {
+///     async fn redraw(
+///         &mut self,
+///         _c: &mut PluginCommon,
+///         _egui: &mut EguiGlow,
+///         _window: &egui_multiwin::async_winit::window::Window<plugin_windows::ThreadSafety>,
+///         _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+///     ) -> plugin_windows::RedrawResponse {
+///         plugin_windows::RedrawResponse::default()
+///     }
+/// }
+///
+/// fn main() {
+///     let _host = host_multi_window::MultiWindow::new_or_panic();
+///     let _plugin: Option<plugin_multi_window::MultiWindow> = None;
+/// }
+/// ```
 #[macro_export]
 macro_rules! tracked_window {
     ($common:ty,$window:ty) => {
-        pub mod tracked_window {
+        egui_multiwin::tracked_window!($common, $window; pub tracked_window, multi_window);
+    };
+    ($common:ty,$window:ty; $vis:vis $tw:ident, $mw:ident) => {
+        $vis mod $tw {
             //! This module covers definition and functionality for an individual window.
 
+            /// The thread safety of the event loop and the windows
+            pub type ThreadSafety = egui_multiwin::async_winit::ThreadSafe;
+
             use std::collections::HashMap;
             use std::{mem, sync::{Arc, Mutex, MutexGuard}};
 
-            use super::multi_window::NewWindowRequest;
+            use super::$mw::{NewWindowRequest, SharedSettings};
 
             use egui_multiwin::egui;
             use egui::viewport::{DeferredViewportUiCallback, ViewportBuilder, ViewportId, ViewportIdSet};
             use egui_multiwin::egui_glow_async::EguiGlow;
             use egui_multiwin::egui_glow_async::{self, glow};
-            use egui_multiwin::glutin::context::{NotCurrentContext, PossiblyCurrentContext};
-            use egui_multiwin::glutin::prelude::{GlConfig, GlDisplay};
-            use egui_multiwin::glutin::surface::SurfaceAttributesBuilder;
-            use egui_multiwin::glutin::surface::WindowSurface;
-            use egui_multiwin::raw_window_handle_5::{HasRawDisplayHandle, HasRawWindowHandle};
-            use egui_multiwin::tracked_window::{ContextHolder, TrackedWindowOptions};
+            use egui_multiwin::tracked_window::{ContextError, ContextHolder, FileDropQueue, FrameStats, GlInfo, ImeState, LastFrame, TouchState, TrackedWindowOptions, WindowCommand, WindowEventDescriptor};
+            use egui_multiwin::sync::LockRecover;
+            use egui_multiwin::viewport::ViewportState;
             use egui_multiwin::async_winit::{
                 event::Event,
                 event_loop::{ControlFlow, EventLoopWindowTarget},
             };
-            use egui_multiwin::{arboard, glutin, async_winit};
-
-            use $window;
+            use egui_multiwin::{arboard, async_winit};
 
             /// The internally used return value for redrawing a window
             pub struct InternalRedrawResponse {
@@ -43,10 +512,16 @@ macro_rules! tracked_window {
 
             /// The return value of the redraw function of trait `TrackedWindow`
             pub struct RedrawResponse {
-                /// Should the window exit?
+                /// Should the window exit? The window closes when `TrackedWindow::can_quit` also returns true.
                 pub quit: bool,
-                /// A list of windows that the window desires to have created.
+                /// A list of windows that the window desires to have created. They are created by the event loop after the frame, with the
+                /// same shared fonts, style and registry entry as the windows added with `MultiWindow::add`.
                 pub new_windows: Vec<NewWindowRequest>,
+                /// A list of commands to apply to the window after the frame is complete.
+                pub commands: Vec<WindowCommand>,
+                /// Draw the frame even when egui drew the same thing as the last frame. Frames that look the same are normally
+                /// skipped, leaving the last frame on the screen. Windows doing custom opengl rendering that changes every frame should set this.
+                pub force_redraw: bool,
             }
 
             impl Default for RedrawResponse {
@@ -54,13 +529,30 @@ macro_rules! tracked_window {
                     Self {
                         quit: false,
                         new_windows: Vec::new(),
+                        commands: Vec::new(),
+                        force_redraw: false,
                     }
                 }
             }
 
+            /// Fails to compile with an error naming the window enum or the common data when they are not set up for the macro, instead of
+            /// errors deep inside the generated code
+            const _: fn() = || {
+                /// Requires the enum of the windows to implement the trait, which enum_dispatch does
+                fn window_enum_needs_enum_dispatch<T: TrackedWindow>() {}
+                window_enum_needs_enum_dispatch::<$window>();
+                /// Requires the bounds on the common data for the thread safety
+                fn common_data_bounds<T: egui_multiwin::tracked_window::CommonData<ThreadSafety> + ?Sized>() {}
+                common_data_bounds::<$common>();
+            };
+
             /// A window being tracked by a `MultiWindow`. All tracked windows will be forwarded all events
             /// received on the `MultiWindow`'s event loop.
             #[egui_multiwin::enum_dispatch::enum_dispatch]
+            #[diagnostic::on_unimplemented(
+                message = "`{Self}` does not implement `TrackedWindow`",
+                note = "the enum of the windows needs `#[enum_dispatch(TrackedWindow)]`, and every window in it needs `impl TrackedWindow`"
+            )]
             pub trait TrackedWindow {
                 /// Returns true if the window is a root window. Root windows will close all other windows when closed. Windows are not root windows by default.
                 /// It is completely valid to have more than one root window open at the same time. The program will exit when all root windows are closed.
@@ -76,14 +568,54 @@ macro_rules! tracked_window {
                 /// Sets whether or not the window is a root window. Does nothing by default
                 fn set_root(&mut self, _root: bool) {}
 
-                /// Runs the redraw for the window. See RedrawResponse for the return value.
+                /// Called when the window gains or loses focus. Does nothing by default.
+                fn focus_changed(&mut self, _focused: bool) {}
+
+                /// Called with the relative motion of the mouse while the window grabs the cursor, see
+                /// [egui_multiwin::tracked_window::WindowCommand::SetCursorGrab]. The motion is not limited by the edges of the window,
+                /// which suits camera controls. Does nothing by default.
+                fn raw_mouse_motion(&mut self, _c: &mut $common, _delta: (f64, f64)) {}
+
+                /// Called with the image on the clipboard after the user pastes into the window, with ctrl+v, cmd+v or the paste key. The
+                /// image has rgba bytes, and is read on another thread so a slow clipboard never stalls drawing, so it arrives a frame or
+                /// more after the paste. Not called when the clipboard holds no image. Does nothing by default.
+                fn pasted_image(&mut self, _c: &mut $common, _image: &egui_multiwin::arboard::ImageData<'static>) {}
+
+                /// Called with a payload dragged from a window of the application, started with [egui_multiwin::drag::start_drag], when
+                /// the mouse button is released over this window. The position is where the mouse was released, in points in this window.
+                /// The payload is given before the redraw of the next frame. Does nothing by default.
+                fn dropped_payload(&mut self, _c: &mut $common, _payload: &egui_multiwin::drag::DragPayload, _pos: egui::Pos2) {}
+
+                /// Receives raw events for the window before egui processes them. Return true to mark the event as consumed,
+                /// preventing egui from receiving it. Does nothing by default.
+                async fn handle_event(&mut self, _c: &mut $common, _event: &WindowEventDescriptor) -> bool {
+                    false
+                }
+
+                /// Runs the redraw for the window. See RedrawResponse for the return value. The clipboard is the clipboard provider of the
+                /// `MultiWindow`, the system clipboard unless [MultiWindow::with_clipboard] replaced it.
                 async fn redraw(
                     &mut self,
                     c: &mut $common,
                     egui: &mut EguiGlow,
-                    window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>,
-                    clipboard: Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                    clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
                 ) -> RedrawResponse;
+                /// Called once the opengl context of the window is created, with the version of the context that was actually created and
+                /// whether the window is drawn by a software rasterizer. Does nothing by default.
+                fn opengl_init(&mut self, _info: &GlInfo) {}
+
+                /// Called after the opengl context of the window was lost and recreated, such as after a reset of the graphics driver.
+                /// All opengl resources created by the window were lost with the old context and must be created again. Does nothing by default.
+                fn context_recreated(&mut self, _info: &GlInfo) {}
+
+                /// Called when the application is suspended, only on android. The window is not drawn until the application resumes.
+                /// Does nothing by default.
+                fn suspended(&mut self, _c: &mut $common) {}
+
+                /// Called when the application resumes after being suspended, only on android. Does nothing by default.
+                fn resumed(&mut self, _c: &mut $common) {}
+
                 /// Allows opengl rendering to be done underneath all of the egui stuff of the window
                 /// # Safety
                 ///
@@ -106,6 +638,86 @@ macro_rules! tracked_window {
                 }
             }
 
+            /// A window kept in a box, for applications that use this as the window type of the macros instead of an enum with
+            /// enum_dispatch. The window implements [egui_multiwin::tracked_window::TrackedWindowDyn].
+            pub type DynWindow = Box<dyn egui_multiwin::tracked_window::TrackedWindowDyn<$common, RedrawResponse>>;
+
+            impl TrackedWindow for DynWindow {
+                fn is_root(&self) -> bool {
+                    (**self).is_root()
+                }
+
+                fn can_quit(&mut self, c: &mut $common) -> bool {
+                    (**self).can_quit(c)
+                }
+
+                fn set_root(&mut self, root: bool) {
+                    (**self).set_root(root)
+                }
+
+                fn focus_changed(&mut self, focused: bool) {
+                    (**self).focus_changed(focused)
+                }
+
+                fn raw_mouse_motion(&mut self, c: &mut $common, delta: (f64, f64)) {
+                    (**self).raw_mouse_motion(c, delta)
+                }
+
+                fn pasted_image(&mut self, c: &mut $common, image: &egui_multiwin::arboard::ImageData<'static>) {
+                    (**self).pasted_image(c, image)
+                }
+
+                fn dropped_payload(&mut self, c: &mut $common, payload: &egui_multiwin::drag::DragPayload, pos: egui::Pos2) {
+                    (**self).dropped_payload(c, payload, pos)
+                }
+
+                async fn handle_event(&mut self, c: &mut $common, event: &WindowEventDescriptor) -> bool {
+                    (**self).handle_event(c, event).await
+                }
+
+                async fn redraw(
+                    &mut self,
+                    c: &mut $common,
+                    egui: &mut EguiGlow,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                    clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+                ) -> RedrawResponse {
+                    (**self).redraw(c, egui, window, clipboard).await
+                }
+
+                fn opengl_init(&mut self, info: &GlInfo) {
+                    (**self).opengl_init(info)
+                }
+
+                fn context_recreated(&mut self, info: &GlInfo) {
+                    (**self).context_recreated(info)
+                }
+
+                fn suspended(&mut self, c: &mut $common) {
+                    (**self).suspended(c)
+                }
+
+                fn resumed(&mut self, c: &mut $common) {
+                    (**self).resumed(c)
+                }
+
+                async unsafe fn opengl_before(
+                    &mut self,
+                    c: &mut $common,
+                    gl: &Arc<egui_multiwin::egui_glow_async::painter::Context>,
+                ) {
+                    (**self).opengl_before(c, gl).await
+                }
+
+                async unsafe fn opengl_after(
+                    &mut self,
+                    c: &mut $common,
+                    gl: &Arc<egui_multiwin::egui_glow_async::painter::Context>,
+                ) {
+                    (**self).opengl_after(c, gl).await
+                }
+            }
+
             /// Contains the differences between window types
             pub enum WindowInstanceThings {
                 /// A root window
@@ -145,30 +757,192 @@ macro_rules! tracked_window {
                 /// The optional callback for the window
                 viewport_callback: &'a Option<Arc<DeferredViewportUiCallback>>,
                 /// Separate window id
-                id: u32,
+                id: egui_multiwin::registry::WindowId,
+                /// The zoom factor for the window
+                zoom_factor: f32,
+                /// The files hovered or dropped on the window
+                file_drops: &'a Arc<Mutex<FileDropQueue>>,
+                /// The ime state of the window
+                ime: &'a Arc<Mutex<ImeState>>,
+                /// The touches of the window
+                touch: &'a Arc<Mutex<TouchState>>,
+                /// True when the window has focus
+                focused: bool,
+                /// True when the mouse passes through the window
+                mouse_passthrough: bool,
+                /// False when the window ignores keyboard and pointer input
+                input_enabled: bool,
+                /// The captures waiting for the next frame
+                capture: &'a mut egui_multiwin::screenshot::CaptureState,
+                /// The image being read from the clipboard after a paste
+                paste: &'a mut egui_multiwin::clipboard::PasteState,
+                /// The theme reported by the operating system for the window
+                system_theme: Option<async_winit::window::Theme>,
+                /// The generation of the shared style last applied to the window
+                style_generation: &'a mut Option<u64>,
+                /// The generation of the shared fonts last applied to the window
+                font_generation: &'a mut Option<u64>,
+                /// The fonts specific to this window
+                font_overrides: &'a Option<egui_multiwin::fonts::FontSet>,
+                /// The style specific to this window
+                style_override: &'a Option<egui::Style>,
+                /// The state shared with the parent window, when the window displays a viewport
+                viewport_state: &'a Option<Arc<Mutex<ViewportState>>>,
+                /// The options used for the viewport windows created by this window
+                viewport_options: TrackedWindowOptions,
+                /// The options the window was created with
+                options: TrackedWindowOptions,
+                /// True when the custom opengl rendering of the window uses srgb conversion
+                srgb: bool,
+                /// The last frame drawn by the window
+                last_frame: &'a mut LastFrame,
             }
 
             impl<'a> TrackedWindowContainerInstance<'a> {
-                /// Take input and run egui begin_frame
-                async fn begin_frame(&mut self, window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>) {
+                /// Take input and run egui begin_frame. Key presses matching an application shortcut are removed from the input
+                /// when the shortcut accepts them, returning the windows requested by the shortcuts.
+                async fn begin_frame(&mut self,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                    c: &mut $common,
+                    settings: &SharedSettings,
+                ) -> Vec<NewWindowRequest> {
                     let mut egui = &mut self.egui;
                     let mut l = egui.egui_winit.lock();
-                    let input = l.take_egui_input(window).await;
+                    let mut input = l.take_egui_input(window).await;
                     drop(l);
-                    egui.egui_ctx.begin_frame(input);
+                    // The input state of the window always reports the root viewport, so the input is moved to the viewport of this window
+                    if input.viewport_id != *self.viewportid {
+                        if let Some(info) = input.viewports.remove(&input.viewport_id) {
+                            input.viewports.insert(*self.viewportid, info);
+                        }
+                        input.viewport_id = *self.viewportid;
+                    }
+                    egui_multiwin::viewport::deliver_close_events(&egui_multiwin::viewport::viewports(&egui.egui_ctx), &mut input);
+                    if let Some(state) = self.viewport_state {
+                        if state.lock_recover().take_viewport_close_event() {
+                            input.viewports.entry(input.viewport_id).or_default().events.push(egui::ViewportEvent::Close);
+                        }
+                    }
+                    self.file_drops.lock_recover().apply(&mut input);
+                    self.ime.lock_recover().apply(&mut input);
+                    self.capture.apply(&mut input);
+                    input.focused = self.focused;
+                    if self.mouse_passthrough {
+                        egui_multiwin::tracked_window::remove_pointer_input(&mut input);
+                    }
+                    let pixels_per_point = self.zoom_factor * window.scale_factor().await as f32;
+                    self.touch.lock_recover().apply(&mut input, pixels_per_point);
+                    let viewport_id = input.viewport_id;
+                    egui_multiwin::tracked_window::update_viewport_info(
+                        input.viewports.entry(viewport_id).or_default(),
+                        window,
+                        pixels_per_point,
+                        self.focused,
+                    ).await;
+                    // A window blocked by a modal window keeps drawing without input
+                    if !self.input_enabled || settings.modals.blocker(self.id).is_some() {
+                        egui_multiwin::tracked_window::remove_input(&mut input);
+                    }
+                    let filter = settings.input_filter.lock_recover().clone();
+                    if let Some(filter) = filter {
+                        filter(self.id, &mut input);
+                    }
+                    self.paste.check_input(&input);
+                    let mut new_windows = Vec::new();
+                    {
+                        let shortcuts = settings.shortcuts.lock_recover();
+                        input.events.retain(|e| {
+                            if let egui::Event::Key { key, pressed: true, modifiers, .. } = e {
+                                for (shortcut, action) in shortcuts.iter() {
+                                    if shortcut.logical_key == *key && modifiers.matches_logically(shortcut.modifiers) {
+                                        if let Some(mut w) = action(c) {
+                                            new_windows.append(&mut w);
+                                            return false;
+                                        }
+                                    }
+                                }
+                            }
+                            true
+                        });
+                    }
+                    self.apply_shared_settings(settings);
+                    let egui = &mut self.egui;
+                    // egui multiplies the zoom factor with the native pixels per point of the window
+                    egui.egui_ctx.set_zoom_factor(self.zoom_factor);
+                    settings.theme.lock_recover().apply(&egui.egui_ctx, self.system_theme);
+                    if let Some(immediate) = self.immediate() {
+                        // The parent window runs the frame for an immediate viewport
+                        immediate.lock_recover().queue_input(input);
+                    }
+                    else {
+                        egui_multiwin::viewport::install_immediate_renderer();
+                        egui.egui_ctx.begin_frame(input);
+                    }
+                    new_windows
+                }
+
+                /// Apply the shared fonts and style to the window if they changed since they were last applied. The fonts and style specific to
+                /// this window are merged over the shared settings.
+                fn apply_shared_settings(&mut self, settings: &SharedSettings) {
+                    let ctx = &self.egui.egui_ctx;
+                    if let Some((generation, fonts)) = settings.fonts.changed_since(*self.font_generation) {
+                        *self.font_generation = Some(generation);
+                        let fonts = match self.font_overrides {
+                            Some(o) => fonts.merged(o),
+                            None => fonts,
+                        };
+                        ctx.set_fonts(fonts.definitions());
+                    }
+                    if let Some((generation, style)) = settings.style.changed_since(*self.style_generation) {
+                        *self.style_generation = Some(generation);
+                        if let Some(style) = self.style_override.clone().or(style) {
+                            ctx.set_style(style);
+                        }
+                    }
+                }
+
+                /// Get the viewport state shared with the parent window, when the window displays an immediate viewport
+                fn immediate(&self) -> Option<&'a Arc<Mutex<ViewportState>>> {
+                    self.viewport_state.as_ref().filter(|s| s.lock_recover().is_immediate())
                 }
 
-                /// run egui end_frame
-                fn end_frame(&mut self) -> egui::FullOutput {
+                /// run egui end_frame. For an immediate viewport, this is the output produced by the parent window, if there is any.
+                fn end_frame(&mut self) -> Option<egui::FullOutput> {
+                    if let Some(immediate) = self.immediate() {
+                        return immediate.lock_recover().take_output();
+                    }
                     let mut egui = &mut self.egui;
-                    egui.egui_ctx.end_frame()
+                    Some(egui.egui_ctx.end_frame())
+                }
+
+                /// Update the ime settings of the window based on the output of egui
+                async fn update_ime(&mut self,
+                    ime: Option<egui::output::IMEOutput>,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                ) {
+                    let ppp = self.egui.egui_ctx.pixels_per_point();
+                    ImeState::update_window(self.ime, window, ime, ppp).await;
+                }
+
+                /// Give an image pasted from the clipboard to the window
+                fn pasted_image(&mut self, c: &mut $common, image: &egui_multiwin::arboard::ImageData<'static>) {
+                    if let Some(window_data) = self.window.window_data() {
+                        window_data.lock_recover().pasted_image(c, image);
+                    }
+                }
+
+                /// Give a payload dragged from a window of the application to the window
+                fn dropped_payload(&mut self, c: &mut $common, payload: &egui_multiwin::drag::DragPayload, pos: egui::Pos2) {
+                    if let Some(window_data) = self.window.window_data() {
+                        window_data.lock_recover().dropped_payload(c, payload, pos);
+                    }
                 }
 
                 /// Redraw the contents of the window
                 async fn redraw(&mut self,
                     c: &mut $common,
-                    window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>,
-                    clipboard: std::sync::Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                    clipboard: std::sync::Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
                 ) -> Option<RedrawResponse> {
                     if let Some(cb) = self.viewport_callback {
                         let egui = &self.egui;
@@ -176,23 +950,25 @@ macro_rules! tracked_window {
                         None
                     }
                     else if let Some(window_data) = self.window.window_data() {
-                        Some(window_data.lock().unwrap().redraw(c, &mut self.egui, window, clipboard).await)
+                        Some(window_data.lock_recover().redraw(c, &mut self.egui, window, clipboard).await)
                     }
                     else {
                         None
                     }
                 }
 
-                /// Clear the window by filling the window with transparency
+                /// Clear the window by filling the window with transparency, also clearing the depth and stencil buffers when the window has them
                 fn gl_clear(&mut self) {
-                    let color = egui_multiwin::egui::Rgba::from_white_alpha(0.0);
-                    let mut egui = &mut self.egui;
-                    unsafe {
-                        use glow::HasContext as _;
-                        egui.painter
-                            .gl()
-                            .clear_color(color[0], color[1], color[2], color[3]);
-                        egui.painter.gl().clear(glow::COLOR_BUFFER_BIT);
+                    egui_multiwin::tracked_window::clear_window(self.egui.painter.gl(), &self.options);
+                }
+
+                /// Enable srgb conversion for the custom opengl rendering, when the window uses it. The painter of egui disables it again.
+                fn gl_srgb(&mut self) {
+                    if self.srgb {
+                        unsafe {
+                            use glow::HasContext as _;
+                            self.egui.painter.gl().enable(glow::FRAMEBUFFER_SRGB);
+                        }
                     }
                 }
 
@@ -200,37 +976,49 @@ macro_rules! tracked_window {
                 async fn gl_before(&mut self,
                     c: &mut $common,
                 ) {
+                    self.gl_srgb();
                     let mut egui = &mut self.egui;
                     // draw things behind egui here
                     if let Some(window) = self.window.window_data() {
-                        unsafe { window.lock().unwrap().opengl_before(c, egui.painter.gl()).await };
+                        unsafe { window.lock_recover().opengl_before(c, egui.painter.gl()).await };
                     }
                 }
 
+                /// Draw the egui contents of the window, reusing the tessellation of the last frame when the shapes did not change
                 async fn draw_main(&mut self,
-                    full_output: egui::FullOutput,
-                    window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>,
+                    textures_delta: &egui::TexturesDelta,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
                 ) {
+                    let size = window.inner_size().await;
                     let mut egui = &mut self.egui;
                     let ppp = egui.egui_ctx.pixels_per_point();
-                    let prim = egui
-                        .egui_ctx
-                        .tessellate(full_output.shapes, ppp);
-                    egui.painter.paint_and_update_textures(
-                        window.inner_size().await.into(),
-                        ppp,
-                        &prim[..],
-                        &full_output.textures_delta,
-                    );
+                    let prim = {
+                        egui_multiwin::profile_scope!("tessellate");
+                        self.last_frame.primitives(&egui.egui_ctx)
+                    };
+                    {
+                        egui_multiwin::profile_scope!("texture upload");
+                        for (id, delta) in &textures_delta.set {
+                            egui.painter.set_texture(*id, delta);
+                        }
+                    }
+                    {
+                        egui_multiwin::profile_scope!("paint");
+                        egui.painter.paint_primitives(size.into(), ppp, prim);
+                    }
+                    for id in &textures_delta.free {
+                        egui.painter.free_texture(*id);
+                    }
                 }
 
                 /// Run the gl after callback
                 async fn gl_after(&mut self,
                     c: &mut $common,
                 ) {
+                    self.gl_srgb();
                     let mut egui = &mut self.egui;
                     if let Some(window) = self.window.window_data() {
-                        unsafe { window.lock().unwrap().opengl_after(c, egui.painter.gl()).await };
+                        unsafe { window.lock_recover().opengl_after(c, egui.painter.gl()).await };
                     }
                 }
             }
@@ -252,81 +1040,247 @@ macro_rules! tracked_window {
                     }
                 }
 
+                /// Deliver a raw event to the window, returning true if the window consumed the event
+                pub async fn handle_event(&mut self,
+                    c: &std::sync::Arc<Mutex<$common>>,
+                    event: &WindowEventDescriptor,
+                ) -> bool {
+                    match event {
+                        WindowEventDescriptor::Focused(f) => {
+                            self.common_mut().focused = *f;
+                            // A window without focus never keeps the cursor, so the user is never stuck
+                            if !*f {
+                                self.release_cursor().await;
+                            }
+                        }
+                        WindowEventDescriptor::ThemeChanged(t) => self.common_mut().system_theme = Some(*t),
+                        _ => {}
+                    }
+                    if let Some(window) = self.get_window_data() {
+                        let mut com = c.lock_recover();
+                        let mut window = window.lock_recover();
+                        if let WindowEventDescriptor::Focused(f) = event {
+                            window.focus_changed(*f);
+                        }
+                        if let WindowEventDescriptor::RawMouseMotion(dx, dy) = event {
+                            window.raw_mouse_motion(&mut com, (*dx, *dy));
+                        }
+                        window.handle_event(&mut com, event).await
+                    }
+                    else {
+                        false
+                    }
+                }
+
+                /// Destroy the surface of the window because the application was suspended, notifying the window
+                pub async fn suspend(&mut self,
+                    c: &std::sync::Arc<Mutex<$common>>,
+                    settings: &SharedSettings,
+                ) -> Result<(), ContextError> {
+                    let draw_lock = settings.draw_lock.lock().await;
+                    let id = self.common().id();
+                    settings.contexts.suspend(id, self.gl_window_mut())?;
+                    drop(draw_lock);
+                    if let Some(window) = self.get_window_data() {
+                        window.lock_recover().suspended(&mut c.lock_recover());
+                    }
+                    Ok(())
+                }
+
+                /// Create the surface of the window again after the application resumed, notifying the window and requesting a repaint
+                pub async fn resume(&mut self,
+                    c: &std::sync::Arc<Mutex<$common>>,
+                    settings: &SharedSettings,
+                ) -> Result<(), ContextError> {
+                    let draw_lock = settings.draw_lock.lock().await;
+                    self.gl_window_mut().resume().await?;
+                    drop(draw_lock);
+                    self.common_mut().last_frame = LastFrame::default();
+                    if let Some(window) = self.get_window_data() {
+                        window.lock_recover().resumed(&mut c.lock_recover());
+                    }
+                    self.gl_window().window().request_redraw();
+                    Ok(())
+                }
+
+                /// Draw the next frame of the window even when it looks the same as the last frame, because the platform discarded the
+                /// pixels of the window
+                pub fn damaged(&mut self) {
+                    self.common_mut().last_frame = LastFrame::default();
+                }
+
                 /// Perform a redraw of the window
                 pub async fn redraw(&mut self,
                     c: &std::sync::Arc<Mutex<$common>>,
-                    clipboard: &std::sync::Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
-                    el: &EventLoopWindowTarget,
-                ) -> Option<InternalRedrawResponse>
+                    clipboard: &std::sync::Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+                    el: &EventLoopWindowTarget<ThreadSafety>,
+                    settings: &SharedSettings,
+                ) -> Result<Option<InternalRedrawResponse>, ContextError>
                 {
-                    let mut gl_window = self.gl_window_option().take().unwrap().make_current();
-                    let mut com = c.lock().unwrap();
+                    // Only one window uses opengl at a time, from making its context current until it is not current
+                    let draw_lock = settings.draw_lock.lock().await;
+                    let start = std::time::Instant::now();
+                    let id = self.common().id();
+                    egui_multiwin::profile::new_frame();
+                    egui_multiwin::profile_scope!("window", id.to_string());
+                    settings.contexts.make_current(id, self.gl_window_mut())?;
+                    let window = self.gl_window().window();
+                    // The surface follows the size of the window, such as after the window enters or leaves fullscreen
+                    if let Err(e) = self.gl_window().resize(window.inner_size().await) {
+                        egui_multiwin::trace::warn(format_args!("Unable to resize the surface of a window: {}", e));
+                    }
+                    let mut com = c.lock_recover();
                     let mut rr = None;
+                    let mut commands = Vec::new();
+                    let mut swap = false;
                     if let Some(mut s) = self.prepare_for_events() {
-                        let mut viewportset = s.viewportset.lock().unwrap();
                         rr = {
-                            let gl_window2 = gl_window.context().unwrap();
-                            s.begin_frame(&gl_window2.window).await;
+                            if let Some(state) = s.viewport_state {
+                                state.lock_recover().attach(&s.egui.egui_ctx, &window);
+                            }
+                            let mut shortcut_windows = {
+                                egui_multiwin::profile_scope!("begin_frame");
+                                s.begin_frame(&window, &mut com, settings).await
+                            };
+                            let paste_window = window.clone();
+                            s.paste.start(&mut *clipboard.lock_recover(), move || paste_window.request_redraw());
+                            if let Some(image) = s.paste.take_image() {
+                                s.pasted_image(&mut com, &image);
+                            }
+                            if let Some((payload, pos)) = egui_multiwin::drag::take_drop(&s.egui.egui_ctx) {
+                                s.dropped_payload(&mut com, &payload, pos);
+                            }
+                            settings.textures.frame(s.egui);
                             let mut rr = RedrawResponse::default();
-                            if let Some(rr2) = s.redraw(&mut com, &gl_window2.window, clipboard.to_owned()).await {
-                                rr = rr2;
+                            let user_rr = {
+                                egui_multiwin::profile_scope!("redraw");
+                                s.redraw(&mut com, &window, clipboard.to_owned()).await
+                            };
+                            if let Some(scrim) = settings.modals.scrim(s.id) {
+                                egui_multiwin::modal::paint_scrim(&s.egui.egui_ctx, scrim);
                             }
-                            let full_output = s.end_frame();
-
-                            if s.viewport_callback.is_none() {
-                                let mut remove_id = Vec::new();
-                                for id in viewportset.iter() {
-                                    if !full_output.viewport_output.contains_key(&id) {
-                                        remove_id.push(id.to_owned());
-                                    }
-                                }
-                                for id in remove_id {
-                                    viewportset.remove(&id);
-                                }
+                            if let Some(rr2) = user_rr {
+                                rr = rr2;
                             }
-                            else {
-                                if !viewportset.contains(s.viewportid) {
+                            rr.new_windows.append(&mut shortcut_windows);
+                            let is_viewport = s.viewport_state.is_some();
+                            if let Some(state) = s.viewport_state {
+                                // The viewport set is shared with the other windows of the parent, so it is never held across an await
+                                let open = s.viewportset.lock_recover().contains(s.viewportid);
+                                if !open || state.lock_recover().is_closed() {
                                     rr.quit = true;
                                 }
                             }
+                            let mut repaint_after = None;
+                            if let Some(full_output) = s.end_frame() {
+                                s.update_ime(full_output.platform_output.ime, &window).await;
+                                let copied = &full_output.platform_output.copied_text;
+                                if !copied.is_empty() {
+                                    if let Err(e) = clipboard.lock_recover().set_text(copied) {
+                                        egui_multiwin::trace::warn(format_args!("Unable to copy text to the clipboard: {}", e));
+                                    }
+                                }
 
-                            for (viewport_id, viewport_output) in &full_output.viewport_output {
-                                if viewport_id != &egui::viewport::ViewportId::ROOT && !viewportset.contains(viewport_id) {
-                                    let builder = egui_multiwin::egui_glow_async::egui_async_winit::create_winit_window_builder(
-                                        &s.egui.egui_ctx,
-                                        el,
-                                        viewport_output.builder.to_owned(),
-                                    ).await;
-                                    let options = TrackedWindowOptions {
-                                        shader: None,
-                                        vsync: false,
-                                    };
-                                    let vp = NewWindowRequest::new_viewport(
-                                        builder,
-                                        options,
-                                        viewport_output.builder.clone(),
-                                        viewport_id.to_owned(),
-                                        s.viewportset.to_owned(),
-                                        viewport_output.viewport_ui_cb.to_owned(),
-                                    );
-                                    viewportset.insert(viewport_id.to_owned());
-                                    rr.new_windows.push(vp);
+                                let mut viewportset = s.viewportset.lock_recover();
+                                if !is_viewport {
+                                    let mut remove_id = Vec::new();
+                                    for id in viewportset.iter() {
+                                        if !full_output.viewport_output.contains_key(&id) {
+                                            remove_id.push(id.to_owned());
+                                        }
+                                    }
+                                    let viewports = egui_multiwin::viewport::viewports(&s.egui.egui_ctx);
+                                    let mut viewports = viewports.lock_recover();
+                                    for id in remove_id {
+                                        viewportset.remove(&id);
+                                        viewports.remove(&id);
+                                    }
                                 }
-                            }
 
-                            let vp_output = full_output
-                                .viewport_output
-                                .get(s.viewportid);
-                            let repaint_after = vp_output.map(|v| v.repaint_delay).or_else(||None);
+                                for (viewport_id, viewport_output) in &full_output.viewport_output {
+                                    if viewport_id == &egui::viewport::ViewportId::ROOT || viewport_id == s.viewportid {
+                                        continue;
+                                    }
+                                    let state = egui_multiwin::viewport::viewport_state(&s.egui.egui_ctx, *viewport_id);
+                                    if viewportset.contains(viewport_id) {
+                                        state.lock_recover().update_builder(&viewport_output.builder);
+                                    }
+                                    else {
+                                        let builder = egui_multiwin::viewport::window_builder(
+                                            &viewport_output.builder,
+                                            s.egui.egui_ctx.zoom_factor(),
+                                        );
+                                        let mut options = s.viewport_options;
+                                        if viewport_output.builder.transparent == Some(true) {
+                                            options.transparent = true;
+                                        }
+                                        let mut vp = NewWindowRequest::new_viewport(
+                                            builder,
+                                            options,
+                                            viewport_output.builder.clone(),
+                                            viewport_id.to_owned(),
+                                            s.viewportset.to_owned(),
+                                            viewport_output.viewport_ui_cb.to_owned(),
+                                        );
+                                        vp.fonts = s.font_overrides.clone();
+                                        vp.style = s.style_override.clone();
+                                        vp.viewport_options = Some(s.viewport_options);
+                                        {
+                                            let mut st = state.lock_recover();
+                                            st.update_builder(&viewport_output.builder);
+                                            st.take_builder_changes();
+                                            st.set_parent(&window);
+                                            if viewport_output.class == egui::ViewportClass::Immediate {
+                                                st.set_immediate();
+                                            }
+                                        }
+                                        vp.viewport_state = Some(state);
+                                        viewportset.insert(viewport_id.to_owned());
+                                        rr.new_windows.push(vp);
+                                    }
+                                }
+                                drop(viewportset);
+
+                                let vp_output = full_output
+                                    .viewport_output
+                                    .get(s.viewportid);
+                                repaint_after = vp_output.map(|v| v.repaint_delay).or_else(||None);
+                                if let Some(v) = vp_output {
+                                    rr.commands.extend(egui_multiwin::viewport::window_commands(&v.commands));
+                                    if v.commands.contains(&egui::ViewportCommand::Screenshot) {
+                                        s.capture.request_egui();
+                                    }
+                                }
 
-                            {
-                                s.gl_clear();
-                                s.gl_before(&mut com).await;
-                                s.draw_main(full_output, &gl_window2.window).await;
-                                s.gl_after(&mut com).await;
-                                let e = gl_window2.swap_buffers();
-                                drop(gl_window2);
+                                let size = window.inner_size().await;
+                                let changed = s.last_frame.changed(
+                                    s.egui.egui_ctx.pixels_per_point(),
+                                    (size.width, size.height),
+                                    &full_output.shapes,
+                                    &full_output.textures_delta,
+                                );
+                                let draw = rr.force_redraw || changed || s.capture.wanted();
+                                FrameStats::record(&s.egui.egui_ctx, draw);
+                                if draw {
+                                    s.gl_clear();
+                                    s.gl_before(&mut com).await;
+                                    s.draw_main(&full_output.textures_delta, &window).await;
+                                    s.gl_after(&mut com).await;
+                                    if s.capture.wanted() {
+                                        let image = egui_multiwin::screenshot::read_framebuffer(
+                                            s.egui.painter.gl(),
+                                            (size.width, size.height),
+                                            s.options.transparent,
+                                        );
+                                        s.capture.deliver(image);
+                                        if s.capture.egui_pending() {
+                                            window.request_redraw();
+                                        }
+                                    }
+                                    swap = true;
+                                }
                             }
+                            commands = rr.commands;
                             let irr = InternalRedrawResponse {
                                 new_windows: rr.new_windows,
                                 quit: rr.quit,
@@ -335,308 +1289,544 @@ macro_rules! tracked_window {
                             Some(irr)
                         };
                     }
-                    self.gl_window_option().replace(gl_window.make_not_current());
-                    rr
+                    // Errors from swapping are ignored, unless the context was lost
+                    let swapped = match swap {
+                        true => {
+                            egui_multiwin::profile_scope!("swap");
+                            self.gl_window().swap_buffers()
+                        }
+                        false => Ok(()),
+                    };
+                    let not_current = settings.contexts.make_not_current(id, self.gl_window_mut());
+                    drop(draw_lock);
+                    if let Some(egui) = &self.common().egui {
+                        FrameStats::record_time(&egui.egui_ctx, start.elapsed());
+                    }
+                    // The window opens hidden, so that it does not show a blank or garbage frame before egui has painted it
+                    if swap && swapped.is_ok() && mem::take(&mut self.common_mut().show_after_draw) {
+                        window.set_visible(true).await;
+                        if mem::take(&mut self.common_mut().focus_when_shown) {
+                            window.focus_window().await;
+                        }
+                    }
+                    self.apply_commands(commands, &window).await;
+                    self.apply_builder_changes(&window).await;
+                    if let Err(e) = swapped {
+                        if e.is_context_lost() {
+                            return Err(e);
+                        }
+                    }
+                    not_current?;
+                    Ok(rr)
                 }
-            }
-
-            /// The common data for all window types
-            pub struct CommonWindowData {
-                /// The context for the window
-                pub gl_window: Option<IndeterminateWindowedContext>,
-                /// The egui instance for this window, each window has a separate egui instance.
-                pub egui: Option<EguiGlow>,
-                /// The viewport set
-                viewportset: Arc<Mutex<ViewportIdSet>>,
-                /// The viewport id for the window
-                viewportid: ViewportId,
-                /// The optional shader version for the window
-                pub shader: Option<egui_multiwin::egui_glow_async::ShaderVersion>,
-                /// The viewport builder
-                pub vb: Option<ViewportBuilder>,
-                /// The viewport callback
-                viewportcb: Option<std::sync::Arc<DeferredViewportUiCallback>>,
-                /// A seperate id from the window id
-                id: u32,
-            }
-
-            /// The container for a viewport window
-            pub struct ViewportWindowContainer {
-                /// The common data
-                common: CommonWindowData,
-            }
-
-            /// The main container for a root window.
-            pub struct PlainWindowContainer {
-                /// The common data
-                common: CommonWindowData,
-                /// The actual window
-                pub window: Arc<Mutex<$window>>,
-            }
 
-            impl TrackedWindowContainer {
-                /// Apply viewport data if required to do so
-                pub async fn check_viewport_builder(&mut self) {
+                /// Apply changes made by the parent window to the builder of the viewport displayed by the window
+                async fn apply_builder_changes(&mut self,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                ) {
                     let common = self.common();
-                    if let Some(vb) = &common.vb {
-                        let gl_window = self.gl_window();
+                    let changes = common.viewport_state.as_ref().and_then(|s| s.lock_recover().take_builder_changes());
+                    if let (Some(changes), Some(egui)) = (changes, &common.egui) {
                         egui_multiwin::egui_glow_async::egui_async_winit::apply_viewport_builder_to_window(
-                            &common.egui.as_ref().unwrap().egui_ctx,
-                            &gl_window.unwrap().window(),
-                            vb,
+                            &egui.egui_ctx,
+                            window,
+                            &changes,
                         ).await;
                     }
                 }
 
-                /// Get the optional window data contained by the window
-                pub fn get_window_data(&self) -> Option<Arc<Mutex<$window>>> {
-                    match self {
-                        Self::PlainWindow(w) => Some(w.window.clone()),
-                        Self::Viewport(_) => None,
-                    }
-                }
-
-                /// Get the common data for the window
-                pub fn common(&self) -> &CommonWindowData {
-                    match self {
-                        Self::PlainWindow(w) => &w.common,
-                        Self::Viewport(w) => &w.common,
+                /// Show the window fullscreen, or leave fullscreen. The position and size of the window are remembered when it enters
+                /// fullscreen, and restored when it leaves.
+                async fn set_fullscreen(&mut self,
+                    mode: Option<&egui_multiwin::monitor::FullscreenMode>,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                ) {
+                    match mode {
+                        Some(mode) => {
+                            let Some(fullscreen) = egui_multiwin::monitor::fullscreen(window, mode).await else {
+                                egui_multiwin::trace::warn(format_args!("No monitor is available for showing a window fullscreen"));
+                                return;
+                            };
+                            if self.common().windowed_geometry.is_none() {
+                                let position = window.outer_position().await.ok().map(|p| (p.x, p.y));
+                                let size = window.inner_size().await;
+                                self.common_mut().windowed_geometry = Some((position, (size.width, size.height)));
+                            }
+                            window.set_fullscreen(Some(fullscreen)).await;
+                        }
+                        None => {
+                            window.set_fullscreen(None).await;
+                            if let Some((position, size)) = self.common_mut().windowed_geometry.take() {
+                                if let Some((x, y)) = position {
+                                    window.set_outer_position(async_winit::dpi::PhysicalPosition::new(x, y)).await;
+                                }
+                                window.set_inner_size(async_winit::dpi::PhysicalSize::new(size.0, size.1)).await;
+                            }
+                        }
                     }
+                    window.request_redraw();
                 }
 
-                /// Get the common data, mutably, for the window
-                pub fn common_mut(&mut self) -> &mut CommonWindowData {
-                    match self {
-                        Self::PlainWindow(w) => &mut w.common,
-                        Self::Viewport(w) => &mut w.common,
+                /// Grab the cursor, or release it with [egui::viewport::CursorGrab::None]. Locking falls back to confining the cursor.
+                async fn set_cursor_grab(&mut self,
+                    grab: egui::viewport::CursorGrab,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                ) {
+                    if egui_multiwin::tracked_window::grab_cursor(window, grab).await {
+                        let grabbed = grab != egui::viewport::CursorGrab::None;
+                        self.common().cursor_grab.store(grabbed, std::sync::atomic::Ordering::Relaxed);
                     }
                 }
 
-                /// Get the gl window for the container
-                pub fn gl_window(&self) -> Option<&IndeterminateWindowedContext> {
-                    match self {
-                        Self::PlainWindow(w) => w.common.gl_window.as_ref(),
-                        Self::Viewport(w) => w.common.gl_window.as_ref(),
+                /// Let the mouse pass through the window, or let the window receive the mouse again
+                async fn set_mouse_passthrough(&mut self,
+                    passthrough: bool,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                ) {
+                    if let Err(e) = window.set_cursor_hittest(!passthrough).await {
+                        egui_multiwin::trace::warn(format_args!("Unable to change the mouse passthrough of a window: {}", e));
+                        return;
                     }
+                    self.common_mut().mouse_passthrough = passthrough;
+                    // One more frame tells egui the pointer is gone, after that pointer events no longer cause repaints
+                    window.request_redraw();
                 }
 
-                /// Get the gl_window option
-                pub fn gl_window_option(&mut self) -> &mut Option<IndeterminateWindowedContext> {
-                    match self {
-                        Self::PlainWindow(w) => &mut w.common.gl_window,
-                        Self::Viewport(w) => &mut w.common.gl_window,
+                /// Let the window receive input, or make it ignore input while it keeps drawing
+                fn set_input_enabled(&mut self,
+                    enabled: bool,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                ) {
+                    let common = self.common_mut();
+                    if common.input_enabled != enabled {
+                        common.input_enabled = enabled;
+                        // The next frame removes the hover effects of the pointer
+                        window.request_redraw();
                     }
                 }
 
-                /// Get the gl window, mutably for the container
-                pub fn gl_window_mut(&mut self) -> Option<&mut IndeterminateWindowedContext> {
-                    match self {
-                        Self::PlainWindow(w) => w.common.gl_window.as_mut(),
-                        Self::Viewport(w) => w.common.gl_window.as_mut(),
+                /// Release the cursor and show it again, if the window grabbed it
+                async fn release_cursor(&mut self) {
+                    if self.common().cursor_grab.swap(false, std::sync::atomic::Ordering::Relaxed) {
+                        let window = self.gl_window().window();
+                        let _e = window.set_cursor_grab(egui_multiwin::async_winit::window::CursorGrabMode::None).await;
+                        window.set_cursor_visible(true).await;
                     }
                 }
 
-                /// Create a new window.
-                pub async fn create(
-                    window: Option<Arc<Mutex<$window>>>,
-                    viewportset: Arc<Mutex<ViewportIdSet>>,
-                    viewportid: &ViewportId,
-                    viewportcb: Option<std::sync::Arc<DeferredViewportUiCallback>>,
-                    window_builder: egui_multiwin::async_winit::window::WindowBuilder,
-                    event_loop: &egui_multiwin::async_winit::event_loop::EventLoopWindowTarget,
-                    options: &TrackedWindowOptions,
-                    vb: Option<ViewportBuilder>
-                ) -> Result<TrackedWindowContainer, DisplayCreationError> {
-                    let rdh = event_loop.raw_display_handle();
-                    let winitwindow = window_builder.build().await.unwrap();
-                    let rwh = winitwindow.raw_window_handle();
-                    #[cfg(target_os = "windows")]
-                    let pref = glutin::display::DisplayApiPreference::Wgl(Some(rwh));
-                    #[cfg(target_os = "linux")]
-                    let pref = egui_multiwin::glutin::display::DisplayApiPreference::Egl;
-                    #[cfg(target_os = "macos")]
-                    let pref = glutin::display::DisplayApiPreference::Cgl;
-                    let display = unsafe { glutin::display::Display::new(rdh, pref) };
-                    if let Ok(display) = display {
-                        let configt = glutin::config::ConfigTemplateBuilder::default().build();
-                        let mut configs: Vec<glutin::config::Config> =
-                            unsafe { display.find_configs(configt) }.unwrap().collect();
-                        configs.sort_by(|a, b| a.num_samples().cmp(&b.num_samples()));
-                        // Try all configurations until one works
-                        for config in configs {
-                            let sab: SurfaceAttributesBuilder<WindowSurface> =
-                                egui_multiwin::glutin::surface::SurfaceAttributesBuilder::default();
-                            let sa = sab.build(
-                                rwh,
-                                std::num::NonZeroU32::new(winitwindow.inner_size().await.width).unwrap(),
-                                std::num::NonZeroU32::new(winitwindow.inner_size().await.height).unwrap(),
-                            );
-                            let ws = unsafe { display.create_window_surface(&config, &sa) };
-                            if let Ok(ws) = ws {
-                                let attr =
-                                    egui_multiwin::glutin::context::ContextAttributesBuilder::new()
-                                        .build(Some(rwh));
-
-                                let gl_window =
-                                    unsafe { display.create_context(&config, &attr) }.unwrap();
-
-                                let wcommon = CommonWindowData {
-                                    viewportid: viewportid.to_owned(),
-                                    viewportset: viewportset.clone(),
-                                    gl_window: Some(IndeterminateWindowedContext::NotCurrent(
-                                        egui_multiwin::tracked_window::ContextHolder::new(
-                                            gl_window,
-                                            winitwindow,
-                                            ws,
-                                            display,
-                                            *options,
-                                        )
-                                    )),
-                                    vb,
-                                    viewportcb,
-                                    egui: None,
-                                    shader: options.shader,
-                                    id: egui_multiwin::rand::Rng::gen(&mut egui_multiwin::rand::thread_rng()),
-                                };
-                                if let Some(window) = window {
-                                    let w = PlainWindowContainer {
-                                        window,
-                                        common: wcommon,
-                                    };
-                                    return Ok(TrackedWindowContainer::PlainWindow(w));
+                /// Apply commands requested by the window during a frame
+                async fn apply_commands(&mut self,
+                    commands: Vec<WindowCommand>,
+                    window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+                ) {
+                    for command in commands {
+                        match command {
+                            WindowCommand::SetZoomFactor(zoom) => {
+                                let common = self.common_mut();
+                                if common.zoom_factor != zoom {
+                                    common.zoom_factor = zoom;
+                                    window.request_redraw();
                                 }
-                                else {
-                                    let w = ViewportWindowContainer {
-                                        common: wcommon,
-                                    };
-                                    return Ok(TrackedWindowContainer::Viewport(w));
+                            }
+                            WindowCommand::SetSwapMode(mode) => {
+                                self.gl_window_mut().set_swap_mode(mode);
+                                window.request_redraw();
+                            }
+                            WindowCommand::SetVisible(visible) => {
+                                window.set_visible(visible).await;
+                            }
+                            WindowCommand::SetFullscreen(mode) => {
+                                self.set_fullscreen(mode.as_ref(), window).await;
+                            }
+                            WindowCommand::SetDecorations(decorations) => {
+                                window.set_decorations(decorations).await;
+                                // The inner size changes, the next frame resizes the surface and updates the viewport info of egui
+                                window.request_redraw();
+                            }
+                            WindowCommand::SetResizable(resizable) => {
+                                window.set_resizable(resizable).await;
+                            }
+                            WindowCommand::SetCursorGrab(grab) => {
+                                self.set_cursor_grab(grab, window).await;
+                            }
+                            WindowCommand::SetCursorVisible(visible) => {
+                                window.set_cursor_visible(visible).await;
+                            }
+                            WindowCommand::SetMousePassthrough(passthrough) => {
+                                self.set_mouse_passthrough(passthrough, window).await;
+                            }
+                            WindowCommand::SetInputEnabled(enabled) => {
+                                self.set_input_enabled(enabled, window);
+                            }
+                            WindowCommand::CaptureFrame(sender) => {
+                                self.common_mut().capture.request(sender);
+                                window.request_redraw();
+                            }
+                            WindowCommand::SetOpacity(opacity) => {
+                                egui_multiwin::opacity::set_opacity(window, opacity);
+                            }
+                            WindowCommand::StartDrag => {
+                                if let Err(e) = window.drag_window().await {
+                                    egui_multiwin::trace::warn(format_args!("Unable to drag a window: {}", e));
                                 }
                             }
-                        }
-                    }
-                    panic!("No window created");
-                }
-
-                /// Build an instance that can have events dispatched to it
-                fn prepare_for_events(&mut self) -> Option<TrackedWindowContainerInstance> {
-                    match self {
-                        Self::PlainWindow(w) => {
-                            if let Some(egui) = &mut w.common.egui {
-                                let w2 = WindowInstanceThings::PlainWindow { window: w.window.clone(), };
-                                Some(TrackedWindowContainerInstance { egui,
-                                    window: w2,
-                                    viewportset: &w.common.viewportset,
-                                    viewportid: &w.common.viewportid,
-                                    viewport_callback: &w.common.viewportcb,
-                                    id: w.common.id,
-                                })
-                            }
-                            else {
-                                None
+                            WindowCommand::StartResize(direction) => {
+                                let direction = egui_multiwin::viewport::resize_direction(direction);
+                                if let Err(e) = window.drag_resize_window(direction).await {
+                                    egui_multiwin::trace::warn(format_args!("Unable to resize a window: {}", e));
+                                }
                             }
-                        }
-                        Self::Viewport(w) => {
-                            if let Some(egui) = &mut w.common.egui {
-                                let w2 = WindowInstanceThings::Viewport { b: 42, };
-                                Some(TrackedWindowContainerInstance { egui,
-                                    window: w2,
-                                    viewportset: &w.common.viewportset,
-                                    viewportid: &w.common.viewportid,
-                                    viewport_callback: &w.common.viewportcb,
-                                    id: w.common.id,
-                                })
-                            }
-                            else {
-                                None
+                            WindowCommand::MoveToMonitor(selector) => {
+                                let (monitors, current) = egui_multiwin::monitor::window_monitors(window).await;
+                                if let (Some(to), Ok(p)) = (selector.select(&monitors, current), window.outer_position().await) {
+                                    let (x, y) = to.place(current.and_then(|i| monitors.get(i)), (p.x, p.y));
+                                    window.set_outer_position(async_winit::dpi::PhysicalPosition::new(x, y)).await;
+                                }
                             }
                         }
                     }
                 }
+            }
 
-                fn try_quit(&mut self, c: &mut $common) {
-                    match self {
-                        Self::PlainWindow(w) => {
-                            if w.window.lock().unwrap().can_quit(c) {
-                                if let Some(egui) = &mut w.common.egui {
-                                    egui.destroy();
-                                }
-                            }
-                        }
-                        Self::Viewport(w) => {
-                            w.common.egui = None;
-                        }
-                    }
+            /// The common data for all window types
+            pub struct CommonWindowData {
+                /// The context for the window
+                pub gl_window: ContextHolder,
+                /// The egui instance for this window, each window has a separate egui instance.
+                pub egui: Option<EguiGlow>,
+                /// The viewport set
+                viewportset: Arc<Mutex<ViewportIdSet>>,
+                /// The viewport id for the window
+                viewportid: ViewportId,
+                /// The optional shader version for the window
+                pub shader: Option<egui_multiwin::egui_glow_async::ShaderVersion>,
+                /// The viewport builder
+                pub vb: Option<ViewportBuilder>,
+                /// The viewport callback
+                viewportcb: Option<std::sync::Arc<DeferredViewportUiCallback>>,
+                /// The id of the window, also used in the registry and in log messages
+                id: egui_multiwin::registry::WindowId,
+                /// The zoom factor of the window, applied on top of the native scale factor
+                pub zoom_factor: f32,
+                /// Files hovered or dropped on the window, waiting for the next frame
+                pub file_drops: Arc<Mutex<FileDropQueue>>,
+                /// The ime state of the window
+                pub ime: Arc<Mutex<ImeState>>,
+                /// The touches of the window, each window only receives the touches made on that window
+                pub touch: Arc<Mutex<TouchState>>,
+                /// True when the window has focus
+                pub focused: bool,
+                /// True when the mouse passes through the window to the windows underneath
+                pub mouse_passthrough: bool,
+                /// False when the window ignores keyboard and pointer input, see [WindowCommand::SetInputEnabled]
+                pub input_enabled: bool,
+                /// The captures waiting for the next frame
+                capture: egui_multiwin::screenshot::CaptureState,
+                /// The image being read from the clipboard after a paste
+                paste: egui_multiwin::clipboard::PasteState,
+                /// True when the window was built hidden and is shown once its first frame is drawn
+                show_after_draw: bool,
+                /// True when the window is focused once it is shown after its first frame
+                focus_when_shown: bool,
+                /// The theme reported by the operating system for the window
+                pub system_theme: Option<async_winit::window::Theme>,
+                /// The generation of the shared style last applied to the window
+                style_generation: Option<u64>,
+                /// The generation of the shared fonts last applied to the window
+                font_generation: Option<u64>,
+                /// The fonts specific to this window, merged over the shared fonts
+                pub font_overrides: Option<egui_multiwin::fonts::FontSet>,
+                /// The style specific to this window, used instead of the shared style
+                pub style_override: Option<egui::Style>,
+                /// The state shared with the parent window, when the window displays a viewport
+                viewport_state: Option<Arc<Mutex<ViewportState>>>,
+                /// The options the window was created with
+                pub options: TrackedWindowOptions,
+                /// The options for the viewport windows created by this window, when they differ from the options of this window
+                pub viewport_options: Option<TrackedWindowOptions>,
+                /// True when the custom opengl rendering of the window uses srgb conversion
+                pub srgb: bool,
+                /// The last frame drawn by the window
+                last_frame: LastFrame,
+                /// True when the opengl context of the window shares objects with the contexts of other windows
+                pub shared_context: bool,
+                /// Information about the opengl context of the window, available once the context is initialized
+                pub gl_info: Option<GlInfo>,
+                /// The key the geometry and egui memory of the window are saved under, when persistence is enabled
+                pub persist_key: Option<String>,
+                /// The position and size of the window before it entered fullscreen, restored when it leaves fullscreen
+                windowed_geometry: Option<(Option<(i32, i32)>, (u32, u32))>,
+                /// True while the window grabs the cursor, raw mouse motion is only delivered to the window while it grabs the cursor
+                cursor_grab: Arc<std::sync::atomic::AtomicBool>,
+            }
+
+            impl CommonWindowData {
+                /// Get the id of the window
+                pub fn id(&self) -> egui_multiwin::registry::WindowId {
+                    self.id
                 }
             }
 
-            /// Enum of the potential options for a window context
-            pub enum IndeterminateWindowedContext {
-                /// The window context is possibly current
-                PossiblyCurrent(ContextHolder<PossiblyCurrentContext>),
-                /// The window context is not current
-                NotCurrent(ContextHolder<NotCurrentContext>),
-                /// The window context is empty
-                None,
+            /// The container for a viewport window
+            pub struct ViewportWindowContainer {
+                /// The common data
+                common: CommonWindowData,
             }
 
-            impl IndeterminateWindowedContext {
-                /// Get the window handle
-                pub fn window(&self) -> Arc<async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>> {
-                    match self {
-                        IndeterminateWindowedContext::PossiblyCurrent(pc) => pc.window(),
-                        IndeterminateWindowedContext::NotCurrent(nc) => nc.window(),
-                        IndeterminateWindowedContext::None => panic!("No window"),
+            /// The main container for a root window.
+            pub struct PlainWindowContainer {
+                /// The common data
+                common: CommonWindowData,
+                /// The actual window
+                pub window: Arc<Mutex<$window>>,
+            }
+
+            impl TrackedWindowContainer {
+                /// Apply viewport data if required to do so
+                pub async fn check_viewport_builder(&mut self) {
+                    let common = self.common();
+                    if let Some(vb) = &common.vb {
+                        egui_multiwin::egui_glow_async::egui_async_winit::apply_viewport_builder_to_window(
+                            &common.egui.as_ref().unwrap().egui_ctx,
+                            &common.gl_window.window(),
+                            vb,
+                        ).await;
                     }
                 }
 
-                /// Get the proc address from glutin
-                pub fn get_proc_address(&self, s: &str) -> *const std::ffi::c_void {
+                /// Get the optional window data contained by the window
+                pub fn get_window_data(&self) -> Option<Arc<Mutex<$window>>> {
                     match self {
-                        IndeterminateWindowedContext::PossiblyCurrent(pc) => pc.get_proc_address(s),
-                        IndeterminateWindowedContext::NotCurrent(nc) => nc.get_proc_address(s),
-                        IndeterminateWindowedContext::None => panic!("No window"),
+                        Self::PlainWindow(w) => Some(w.window.clone()),
+                        Self::Viewport(_) => None,
                     }
                 }
 
-                /// Attempt to make the current context current
-                pub fn make_current(self) -> Self {
+                /// Apply the shared fonts and style to the window, if they changed since they were last applied
+                pub fn apply_shared_settings(&mut self, settings: &SharedSettings) {
+                    if let Some(mut s) = self.prepare_for_events() {
+                        s.apply_shared_settings(settings);
+                    }
+                }
+
+                /// Get the common data for the window
+                pub fn common(&self) -> &CommonWindowData {
                     match self {
-                        IndeterminateWindowedContext::PossiblyCurrent(mut pc) => {
-                            pc.make_current();
-                            IndeterminateWindowedContext::PossiblyCurrent(pc)
-                        }
-                        IndeterminateWindowedContext::NotCurrent(mut nc) => {
-                            let c = nc.make_current().unwrap();
-                            IndeterminateWindowedContext::PossiblyCurrent(c)
-                        }
-                        IndeterminateWindowedContext::None => {
-                            IndeterminateWindowedContext::None
-                        }
+                        Self::PlainWindow(w) => &w.common,
+                        Self::Viewport(w) => &w.common,
                     }
                 }
 
-                /// Make the current context not current
-                pub fn make_not_current(self) -> Self {
+                /// Get the common data, mutably, for the window
+                pub fn common_mut(&mut self) -> &mut CommonWindowData {
                     match self {
-                        IndeterminateWindowedContext::PossiblyCurrent(mut pc) => {
-                            let newc = pc.make_not_current().unwrap();
-                            IndeterminateWindowedContext::NotCurrent(newc)
-                        }
-                        IndeterminateWindowedContext::NotCurrent(nc) => {
-                            IndeterminateWindowedContext::NotCurrent(nc)
-                        }
-                        IndeterminateWindowedContext::None => {
-                            IndeterminateWindowedContext::None
+                        Self::PlainWindow(w) => &mut w.common,
+                        Self::Viewport(w) => &mut w.common,
+                    }
+                }
+
+                /// Get the gl window for the container
+                pub fn gl_window(&self) -> &ContextHolder {
+                    &self.common().gl_window
+                }
+
+                /// Get the gl window, mutably for the container
+                pub fn gl_window_mut(&mut self) -> &mut ContextHolder {
+                    &mut self.common_mut().gl_window
+                }
+
+                /// Build the window of a new container. This does not involve opengl, so several windows can be built at the same time.
+                pub async fn build_window(
+                    window_builder: egui_multiwin::async_winit::window::WindowBuilder,
+                    options: &TrackedWindowOptions,
+                ) -> Result<Arc<egui_multiwin::async_winit::window::Window<ThreadSafety>>, DisplayCreationError> {
+                    let window_builder = if options.transparent {
+                        window_builder.with_transparent(true)
+                    }
+                    else {
+                        window_builder
+                    };
+                    Ok(Arc::new(
+                        window_builder
+                            .build()
+                            .await
+                            .map_err(|e| egui_multiwin::Error::WindowBuild(e.to_string()))?,
+                    ))
+                }
+
+                /// Create a new window container for a window built with [Self::build_window]
+                pub async fn create(
+                    window: Option<Arc<Mutex<$window>>>,
+                    viewportset: Arc<Mutex<ViewportIdSet>>,
+                    viewportid: &ViewportId,
+                    viewportcb: Option<std::sync::Arc<DeferredViewportUiCallback>>,
+                    winitwindow: Arc<egui_multiwin::async_winit::window::Window<ThreadSafety>>,
+                    event_loop: &egui_multiwin::async_winit::event_loop::EventLoopWindowTarget<ThreadSafety>,
+                    options: &TrackedWindowOptions,
+                    vb: Option<ViewportBuilder>,
+                    gl_share: &egui_multiwin::tracked_window::SharedGlContext,
+                    id: egui_multiwin::registry::WindowId,
+                ) -> Result<TrackedWindowContainer, DisplayCreationError> {
+                    let (gl_window, srgb, shared_context) = match ContextHolder::create(&winitwindow, event_loop, options, gl_share).await {
+                        Some(gl) => gl,
+                        None => return Err(egui_multiwin::Error::Display),
+                    };
+                    let wcommon = CommonWindowData {
+                        viewportid: viewportid.to_owned(),
+                        viewportset: viewportset.clone(),
+                        gl_window,
+                        vb,
+                        viewportcb,
+                        egui: None,
+                        shader: options.shader,
+                        id,
+                        zoom_factor: 1.0,
+                        file_drops: Arc::new(Mutex::new(FileDropQueue::default())),
+                        ime: Arc::new(Mutex::new(ImeState::default())),
+                        touch: Arc::new(Mutex::new(TouchState::default())),
+                        focused: false,
+                        system_theme: None,
+                        style_generation: None,
+                        font_generation: None,
+                        font_overrides: None,
+                        style_override: None,
+                        viewport_state: None,
+                        options: *options,
+                        viewport_options: None,
+                        srgb,
+                        last_frame: LastFrame::default(),
+                        shared_context,
+                        gl_info: None,
+                        persist_key: None,
+                        windowed_geometry: None,
+                        cursor_grab: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+                        mouse_passthrough: false,
+                        input_enabled: true,
+                        capture: Default::default(),
+                        paste: Default::default(),
+                        show_after_draw: false,
+                        focus_when_shown: false,
+                    };
+                    if let Some(window) = window {
+                        let w = PlainWindowContainer {
+                            window,
+                            common: wcommon,
+                        };
+                        Ok(TrackedWindowContainer::PlainWindow(w))
+                    }
+                    else {
+                        let w = ViewportWindowContainer {
+                            common: wcommon,
+                        };
+                        Ok(TrackedWindowContainer::Viewport(w))
+                    }
+                }
+
+                /// Recreate the opengl display, surface and context of the window after the context was lost, such as after a reset of the
+                /// graphics driver. The egui painter is recreated, the font texture is uploaded again, and images are reloaded. The window is
+                /// notified with [TrackedWindow::context_recreated] so that it can recreate its own opengl resources.
+                pub async fn recreate_context(&mut self,
+                    settings: &SharedSettings,
+                    event_loop: &egui_multiwin::async_winit::event_loop::EventLoopWindowTarget<ThreadSafety>,
+                ) -> Result<(), ContextError> {
+                    let draw_lock = settings.draw_lock.lock().await;
+                    let id = self.common().id();
+                    let window = self.gl_window().window();
+                    let options = self.common().options;
+                    let (gl_window, srgb, shared_context) = ContextHolder::create(&window, event_loop, &options, &settings.gl_share)
+                        .await
+                        .ok_or(ContextError::Creation)?;
+                    settings.contexts.remove_window(id);
+                    settings.contexts.add_window();
+                    {
+                        let common = self.common_mut();
+                        common.gl_window = gl_window;
+                        common.srgb = srgb;
+                        common.shared_context = shared_context;
+                        common.last_frame = LastFrame::default();
+                    }
+                    settings.contexts.make_current(id, self.gl_window_mut())?;
+                    let gl_window = self.gl_window();
+                    let gl = Arc::new(unsafe {
+                        glow::Context::from_loader_function(|s| {
+                            gl_window.get_proc_address(s)
+                        })
+                    });
+                    let info = GlInfo::new(&gl, gl_window);
+                    info.log();
+                    if srgb {
+                        unsafe {
+                            use glow::HasContext as _;
+                            gl.enable(glow::FRAMEBUFFER_SRGB);
                         }
                     }
+                    let shader = self.common().shader;
+                    let painter = egui_glow_async::Painter::new(gl, "", shader)
+                        .map_err(|e| ContextError::Painter(e.to_string()))?;
+                    if let Some(egui) = &mut self.common_mut().egui {
+                        // The old painter can not be destroyed, its resources were lost with the context
+                        egui.painter = painter;
+                        settings.textures.attach(egui, id, shared_context);
+                        let font_image = egui.egui_ctx.fonts(|f| f.image());
+                        egui.painter.set_texture(
+                            egui::TextureId::default(),
+                            &egui::epaint::ImageDelta::full(
+                                egui::ImageData::Font(font_image),
+                                egui::TextureOptions::LINEAR,
+                            ),
+                        );
+                        egui.egui_ctx.forget_all_images();
+                    }
+                    if let Some(w) = self.get_window_data() {
+                        w.lock_recover().context_recreated(&info);
+                    }
+                    self.common_mut().gl_info = Some(info);
+                    settings.contexts.make_not_current(id, self.gl_window_mut())?;
+                    drop(draw_lock);
+                    window.request_redraw();
+                    Ok(())
+                }
+
+                /// Build an instance that can have events dispatched to it
+                fn prepare_for_events(&mut self) -> Option<TrackedWindowContainerInstance> {
+                    let (window, common) = match self {
+                        Self::PlainWindow(w) => (WindowInstanceThings::PlainWindow { window: w.window.clone(), }, &mut w.common),
+                        Self::Viewport(w) => (WindowInstanceThings::Viewport { b: 42, }, &mut w.common),
+                    };
+                    let egui = common.egui.as_mut()?;
+                    Some(TrackedWindowContainerInstance { egui,
+                        window,
+                        viewportset: &common.viewportset,
+                        viewportid: &common.viewportid,
+                        viewport_callback: &common.viewportcb,
+                        id: common.id,
+                        zoom_factor: common.zoom_factor,
+                        file_drops: &common.file_drops,
+                        ime: &common.ime,
+                        touch: &common.touch,
+                        focused: common.focused,
+                        mouse_passthrough: common.mouse_passthrough,
+                        input_enabled: common.input_enabled,
+                        capture: &mut common.capture,
+                        paste: &mut common.paste,
+                        system_theme: common.system_theme,
+                        style_generation: &mut common.style_generation,
+                        font_generation: &mut common.font_generation,
+                        font_overrides: &common.font_overrides,
+                        style_override: &common.style_override,
+                        viewport_state: &common.viewport_state,
+                        viewport_options: common.viewport_options.unwrap_or(common.options),
+                        options: common.options,
+                        srgb: common.srgb,
+                        last_frame: &mut common.last_frame,
+                    })
                 }
 
-                /// Get a possibly current context
-                pub fn context(&self) -> Option<&ContextHolder<PossiblyCurrentContext>> {
+                /// Returns true when the window is allowed to close. Viewports close whenever their parent stops showing them.
+                fn can_quit(&mut self, c: &mut $common) -> bool {
                     match self {
-                        IndeterminateWindowedContext::PossiblyCurrent(pc) => Some(pc),
-                        IndeterminateWindowedContext::NotCurrent(nc) => None,
-                        IndeterminateWindowedContext::None => None,
+                        Self::PlainWindow(w) => w.window.lock_recover().can_quit(c),
+                        Self::Viewport(_) => true,
                     }
                 }
             }
@@ -649,18 +1839,21 @@ macro_rules! tracked_window {
                 pub windows_to_create: Vec<NewWindowRequest>,
             }
 
-            #[derive(egui_multiwin::thiserror::Error, Debug)]
-            /// Enumerates the kinds of errors that display creation can have.
-            pub enum DisplayCreationError {}
+            /// The errors that can occur when creating a window
+            pub type DisplayCreationError = egui_multiwin::Error;
         }
     };
 }
 
 /// This macro creates a dynamic definition of the multi_window module. It has the same arguments as the [`tracked_window`](macro.tracked_window.html) macro.
+/// The visibility and names of the modules are given the same way as for the tracked_window macro.
 #[macro_export]
 macro_rules! multi_window {
     ($common:ty, $window:ty) => {
-        pub mod multi_window {
+        egui_multiwin::multi_window!($common, $window; pub tracked_window, multi_window);
+    };
+    ($common:ty, $window:ty; $vis:vis $tw:ident, $mw:ident) => {
+        $vis mod $mw {
             //! This defines the MultiWindow struct. This is the main struct used in the main function of a user application.
 
             use std::collections::HashMap;
@@ -668,7 +1861,8 @@ macro_rules! multi_window {
 
             use egui_multiwin::egui_glow_async::{self, glow};
             use egui_multiwin::{
-                tracked_window::TrackedWindowOptions,
+                multi_window::LoopEvent,
+                tracked_window::{DrawWake, RepaintThrottle, ThemePreference, TrackedWindowOptions, WindowEventDescriptor},
                 async_winit::{
                     self,
                     error::EventLoopError,
@@ -678,47 +1872,636 @@ macro_rules! multi_window {
 
             use egui::viewport::{DeferredViewportUiCallback, ViewportId, ViewportIdSet};
             use egui_multiwin::egui;
+            use egui_multiwin::sync::LockRecover;
 
-            use super::tracked_window::{
-                CommonWindowData, DisplayCreationError, IndeterminateWindowedContext,
-                TrackedWindow, TrackedWindowContainer,
+            use super::$tw::{
+                CommonWindowData, DisplayCreationError, ThreadSafety, TrackedWindow, TrackedWindowContainer,
             };
 
+            /// An action for an application wide keyboard shortcut. Returning None declines the shortcut, allowing egui to receive the key press.
+            pub type ShortcutAction = Box<dyn Fn(&mut $common) -> Option<Vec<NewWindowRequest>> + Send + Sync>;
+
+            /// The decision of the application when every root window has closed
+            pub enum ExitDecision {
+                /// Exit the application
+                Allow,
+                /// Keep the application running without any root windows
+                Deny,
+                /// Keep the application running and create a window, such as a dialog asking to confirm the exit. The window should
+                /// be a root window, so that the decision is asked for again when it closes, and not while it is open.
+                ShowWindow(NewWindowRequest),
+            }
+
+            /// The action run when every root window has closed, deciding if the application exits
+            pub type ExitAction = Box<dyn Fn(&mut $common) -> ExitDecision>;
+
+            /// Saves the common data, returning a description of the error when saving fails
+            pub type AutosaveAction = std::rc::Rc<dyn Fn(&$common) -> Result<(), String>>;
+
+            /// Creates a window again from the key and payload given to [NewWindowRequest::with_session]. Returns None for windows that should
+            /// not be opened again.
+            pub type SessionFactory = Box<dyn Fn(&str, &[u8]) -> Option<NewWindowRequest>>;
+
+            /// The action run for each event of the tray icon
+            pub type TrayAction = Box<dyn Fn(&mut $common, egui_multiwin::tray::TrayEvent) -> AppEventResponse>;
+
+            /// The action run when a global hotkey is pressed, given the id of the hotkey
+            pub type HotkeyAction = Box<dyn Fn(&mut $common, u32) -> AppEventResponse>;
+
+            /// The action run when the power source of the computer changes
+            pub type PowerAction = Box<dyn Fn(&mut $common, egui_multiwin::power::PowerSource) -> AppEventResponse>;
+
+            /// The action run with the common data when a timer fires
+            pub type TimerAction = std::rc::Rc<dyn Fn(&mut $common) -> AppEventResponse>;
+
+            /// What a timer does when it fires, see [MultiWindowProxy::after] and [MultiWindowProxy::every]
+            pub enum TimerTarget {
+                /// Repaint a window. The timer is cancelled when the window closes. A repeating timer skips a repaint when the window drew a
+                /// frame since the last time it fired, such as for a repaint egui asked for, and waits a whole period from that frame
+                /// instead. A window that repaints every second on its own and has a timer every second is drawn once per second.
+                Repaint(egui_multiwin::registry::WindowId),
+                /// Run an action with the common data. The response is applied like the response to a tray event.
+                Common(TimerAction),
+                /// Call a function, such as one that sends an event into a channel of the application
+                Call(Box<dyn Fn()>),
+            }
+
+            impl TimerTarget {
+                /// Run an action with the common data when the timer fires
+                pub fn common(action: impl Fn(&mut $common) -> AppEventResponse + 'static) -> Self {
+                    Self::Common(std::rc::Rc::new(action))
+                }
+
+                /// Call a function when the timer fires
+                pub fn call(f: impl Fn() + 'static) -> Self {
+                    Self::Call(Box::new(f))
+                }
+            }
+
+            pub use egui_multiwin::multi_window::{ContextHook, InputFilter, PanicAction};
+
+            /// The response to an event of the tray icon, a global hotkey, a timer, or a change of the power source
+            #[derive(Default)]
+            pub struct AppEventResponse {
+                /// Windows to create
+                pub new_windows: Vec<NewWindowRequest>,
+                /// The ids of windows to show and focus, such as windows that were hidden with [WindowCommand::SetVisible]
+                pub show: Vec<egui_multiwin::registry::WindowId>,
+                /// The ids of windows to hide
+                pub hide: Vec<egui_multiwin::registry::WindowId>,
+            }
+
+            /// The list of application wide keyboard shortcuts, shared with every window
+            pub type ShortcutList = Arc<Mutex<Vec<(egui::KeyboardShortcut, ShortcutAction)>>>;
+
+            /// The settings of the `MultiWindow` that are shared with every window
+            pub type SharedSettings = egui_multiwin::multi_window::SharedSettings<ShortcutList>;
+
+            /// A cloneable handle for modifying the `MultiWindow` from other places, including after [run](MultiWindow::run) has been called.
+            #[derive(Clone)]
+            pub struct MultiWindowProxy {
+                /// Sender for windows to be created
+                pending_windows: egui_multiwin::async_channel::Sender<Vec<NewWindowRequest>>,
+                /// The registry of open windows
+                registry: egui_multiwin::registry::WindowRegistry,
+                /// The settings shared with every window
+                settings: SharedSettings,
+                /// Sender for windows to be cancelled
+                cancel_windows: egui_multiwin::async_channel::Sender<egui_multiwin::registry::WindowId>,
+                /// Sender for the actions of timers, which run with the common data
+                timer_actions: egui_multiwin::async_channel::Sender<TimerAction>,
+                /// Background futures that do not keep the application alive
+                daemons: egui_multiwin::future_set::FuturesHashSet<()>,
+            }
+
+            impl MultiWindowProxy {
+                /// Request a redraw of every open window
+                fn redraw_all(&self) {
+                    for id in self.registry.ids() {
+                        if let Some(w) = self.registry.get(id) {
+                            w.window.request_redraw();
+                        }
+                    }
+                }
+
+                /// Get a handle to the registry of open windows
+                pub fn registry(&self) -> egui_multiwin::registry::WindowRegistry {
+                    self.registry.clone()
+                }
+
+                /// Get the egui context of a window, such as for a crate showing notifications from a background task. Returns None
+                /// until egui is running in the window, see [egui_multiwin::registry::WindowInfo::context].
+                pub fn context(&self, id: egui_multiwin::registry::WindowId) -> Option<egui::Context> {
+                    self.registry.context(id)
+                }
+
+                /// Get the textures shared by all windows, so that an image is uploaded once and shown in any window
+                pub fn textures(&self) -> egui_multiwin::textures::AppTextureManager {
+                    self.settings.textures.clone()
+                }
+
+                /// Request that a new window be created, returning the id the window will have. Fails when the event loop has exited.
+                pub async fn add(&self, window: NewWindowRequest) -> Result<egui_multiwin::registry::WindowId, egui_multiwin::Error> {
+                    let id = window.id;
+                    self.pending_windows.send(vec![window]).await?;
+                    Ok(id)
+                }
+
+                /// Request that several windows be created together, returning the ids the windows will have. The windows are built at the
+                /// same time, which is faster than adding them one by one, and the last window of the list is focused. Fails when the event
+                /// loop has exited.
+                pub async fn add_all(&self, windows: Vec<NewWindowRequest>) -> Result<Vec<egui_multiwin::registry::WindowId>, egui_multiwin::Error> {
+                    let ids = windows.iter().map(|w| w.id).collect();
+                    if !windows.is_empty() {
+                        self.pending_windows.send(windows).await?;
+                    }
+                    Ok(ids)
+                }
+
+                /// Force a window to close by dropping the future that runs it, such as when the window is stuck. The window is not asked
+                /// if it can close and its geometry is not saved, but its opengl resources are released and it is removed from the registry.
+                pub fn cancel_window(&self, id: egui_multiwin::registry::WindowId) {
+                    let _ = self.cancel_windows.try_send(id);
+                }
+
+                /// Run a background future, such as an autosave timer, alongside the windows. Background futures do not keep the application
+                /// alive, they are cancelled when the application exits. Returns an id that can be used to cancel the future.
+                pub fn add_daemon(&self, f: impl std::future::Future<Output = ()> + 'static) -> u32 {
+                    self.daemons.get().add_future(f)
+                }
+
+                /// Cancel a background future added with [Self::add_daemon]
+                pub fn cancel_daemon(&self, id: u32) {
+                    self.daemons.get().cancel(id);
+                }
+
+                /// Fire a timer once after a delay. The timer runs as a background future, see [Self::add_daemon], and the returned id can
+                /// cancel it with [Self::cancel_daemon].
+                pub fn after(&self, delay: std::time::Duration, target: TimerTarget) -> u32 {
+                    self.add_timer(delay, false, target)
+                }
+
+                /// Fire a timer repeatedly, with a period between firings. The timer runs as a background future, see [Self::add_daemon],
+                /// and the returned id can cancel it with [Self::cancel_daemon].
+                pub fn every(&self, period: std::time::Duration, target: TimerTarget) -> u32 {
+                    self.add_timer(period, true, target)
+                }
+
+                /// Add the background future of a timer
+                fn add_timer(&self, period: std::time::Duration, repeat: bool, target: TimerTarget) -> u32 {
+                    use egui_multiwin::futures_lite::FutureExt;
+                    let window = match &target {
+                        TimerTarget::Repaint(id) => self.registry.get(*id),
+                        _ => None,
+                    };
+                    let actions = self.timer_actions.clone();
+                    let registry = self.registry.clone();
+                    let ticks = async move {
+                        let mut deadline = std::time::Instant::now() + period;
+                        loop {
+                            async_winit::Timer::after(deadline.saturating_duration_since(std::time::Instant::now())).await;
+                            match &target {
+                                TimerTarget::Repaint(id) => {
+                                    let Some(w) = registry.get(*id) else {
+                                        return;
+                                    };
+                                    // A frame drawn since the last firing already shows the window no more than a period ago
+                                    match w.last_frame() {
+                                        Some(last) if repeat && last + period > deadline => {
+                                            deadline = last + period;
+                                            continue;
+                                        }
+                                        _ => w.window.request_redraw(),
+                                    }
+                                }
+                                TimerTarget::Common(action) => {
+                                    if actions.send(action.clone()).await.is_err() {
+                                        return;
+                                    }
+                                }
+                                TimerTarget::Call(f) => f(),
+                            }
+                            if !repeat {
+                                return;
+                            }
+                            deadline = (deadline + period).max(std::time::Instant::now());
+                        }
+                    };
+                    match window {
+                        Some(window) => self.add_daemon(ticks.or(async move { window.closed().await })),
+                        // The timer of a window that is already closed ends when it first fires
+                        None => self.add_daemon(ticks),
+                    }
+                }
+
+                /// Set the style used by every window, including viewport windows. Windows that are already open use the new style on their next frame.
+                pub fn set_style(&self, style: egui::Style) {
+                    self.settings.style.set(Some(style));
+                    self.redraw_all();
+                }
+
+                /// Modify the style used by every window, starting from the default style if no style has been set.
+                pub fn modify_style(&self, f: impl FnOnce(&mut egui::Style)) {
+                    self.settings.style.modify(|s| f(s.get_or_insert_with(egui::Style::default)));
+                    self.redraw_all();
+                }
+
+                /// Set the theme for all current and future windows. This overrides the theme of the operating system, unless
+                /// [ThemePreference::System] is specified.
+                pub fn set_theme(&self, theme: ThemePreference) {
+                    *self.settings.theme.lock_recover() = theme;
+                    self.redraw_all();
+                }
+
+                /// Limit how often windows repaint while they do not have focus and the mouse is not over them. Windows can override this
+                /// with [TrackedWindowOptions::repaint_throttle].
+                pub fn set_repaint_throttle(&self, throttle: RepaintThrottle) {
+                    *self.settings.repaint_throttle.lock_recover() = throttle;
+                    self.redraw_all();
+                }
+
+                /// Set the repaint limits of every window from a power profile. This replaces the limit set with
+                /// [set_repaint_throttle](Self::set_repaint_throttle). An [automatic](egui_multiwin::power::PowerProfile::Automatic) profile
+                /// changes the limits again whenever the power source changes.
+                pub fn set_power_profile(&self, profile: egui_multiwin::power::PowerProfile) {
+                    *self.settings.power_profile.lock_recover() = profile;
+                    self.apply_power_source(egui_multiwin::power::power_source());
+                }
+
+                /// Set the filter that changes the input of every window before each frame, replacing the last filter. The filter runs after
+                /// the input of a window is collected and before egui sees it, including for windows that ignore input. Removing all
+                /// events of a window, such as all windows except a modal window, makes it ignore the keyboard and pointer while it keeps
+                /// drawing. None removes the filter.
+                pub fn set_input_filter(&self, filter: Option<InputFilter>) {
+                    *self.settings.input_filter.lock_recover() = filter;
+                    self.redraw_all();
+                }
+
+                /// Apply the power profile for a power source
+                fn apply_power_source(&self, source: egui_multiwin::power::PowerSource) {
+                    let profile = self.settings.power_profile.lock_recover().resolve(source);
+                    *self.settings.repaint_throttle.lock_recover() = profile.background_throttle();
+                    *self.settings.frame_limit.lock_recover() = profile.frame_limit();
+                    self.redraw_all();
+                }
+
+                /// Set the icon for windows that do not specify their own icon. Only windows created after this call use the icon.
+                pub fn set_default_icon(&self, icon: Option<egui_multiwin::icon::WindowIcon>) {
+                    *self.settings.icon.lock_recover() = icon;
+                }
+
+                /// Add or replace a font in every window. Windows that are already open install the font before their next frame.
+                pub fn add_font(&self, name: String, fd: egui_multiwin::egui::FontData) {
+                    self.add_font_to_families(name, fd, Vec::new());
+                }
+
+                /// Add or replace a font in every window, also adding it to the specified built in families. Use [egui::FontFamily::Proportional]
+                /// and [egui::FontFamily::Monospace] with [egui_multiwin::fonts::FontPlacement::Prepend] to make the font the default font of the application.
+                /// Fonts are placed into families in the order they are added.
+                pub fn add_font_to_families(
+                    &self,
+                    name: String,
+                    fd: egui_multiwin::egui::FontData,
+                    families: Vec<(egui::FontFamily, egui_multiwin::fonts::FontPlacement)>,
+                ) {
+                    self.settings.fonts.modify(|f| f.insert(egui_multiwin::fonts::FontEntry {
+                        name,
+                        data: fd,
+                        families,
+                    }));
+                    self.redraw_all();
+                }
+
+                /// Add or replace a font in every window from the raw bytes of a font, such as a font downloaded at runtime.
+                /// The data is checked before it is installed.
+                pub fn add_font_bytes(&self, name: String, data: Vec<u8>) -> Result<(), egui_multiwin::fonts::FontLoadError> {
+                    let fd = egui_multiwin::fonts::font_from_bytes(data)?;
+                    self.add_font(name, fd);
+                    Ok(())
+                }
+
+                /// Add or replace a font in every window by loading it from a file without blocking. The data is checked before it is installed.
+                pub async fn add_font_file(
+                    &self,
+                    name: String,
+                    path: impl AsRef<std::path::Path>,
+                ) -> Result<(), egui_multiwin::fonts::FontLoadError> {
+                    let fd = egui_multiwin::fonts::font_from_file_async(path).await?;
+                    self.add_font(name, fd);
+                    Ok(())
+                }
+
+                /// Remove a font from every window. Text using the family named after the font falls back to the proportional family.
+                pub fn remove_font(&self, name: &str) {
+                    self.settings.fonts.modify(|f| {
+                        f.remove(name);
+                    });
+                    self.redraw_all();
+                }
+            }
+
             /// The main struct of the crate. Manages multiple `TrackedWindow`s by forwarding events to them.
             /// `T` represents the common data struct for the user program. `U` is the type representing custom events.
             pub struct MultiWindow {
                 /// The event loop for the application
-                event_loop: Option<egui_multiwin::async_winit::event_loop::EventLoop<async_winit::ThreadSafe>>,
-                /// List of windows to be created
-                pending_windows: egui_multiwin::async_channel::Sender<NewWindowRequest>,
+                event_loop: Option<egui_multiwin::async_winit::event_loop::EventLoop<ThreadSafety>>,
                 /// Processor for making new windows
-                window_receiver: Option<egui_multiwin::async_channel::Receiver<NewWindowRequest>>,
-                /// A list of fonts to install on every egui instance
-                fonts: HashMap<String, egui_multiwin::egui::FontData>,
+                window_receiver: egui_multiwin::async_channel::Receiver<Vec<NewWindowRequest>>,
+                /// Receives the ids of windows to be cancelled
+                cancel_receiver: egui_multiwin::async_channel::Receiver<egui_multiwin::registry::WindowId>,
+                /// Receives the actions of timers
+                timer_receiver: egui_multiwin::async_channel::Receiver<TimerAction>,
                 /// The clipboard
-                clipboard: Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
+                clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+                /// The proxy, holding the things shared with every window
+                proxy: MultiWindowProxy,
+                /// The tray icon of the application and the action for its events
+                tray: Option<(egui_multiwin::tray::Tray, TrayAction)>,
+                /// The global hotkeys of the application and the action for them
+                hotkeys: Option<(egui_multiwin::hotkeys::GlobalHotkeys, HotkeyAction)>,
+                /// The action run when the power source changes
+                power_action: Option<PowerAction>,
+                /// Decides if the application exits when every root window has closed
+                can_exit: Option<ExitAction>,
+                /// How often the common data is saved, and the action that saves it
+                autosave: Option<(std::time::Duration, AutosaveAction)>,
+                /// Creates the windows of a saved session
+                session_factory: Option<SessionFactory>,
+                /// How long the non-root windows have to close when the application exits
+                shutdown_timeout: std::time::Duration,
             }
 
             impl Default for MultiWindow {
                 fn default() -> Self {
-                    Self::new()
+                    Self::new_or_panic()
                 }
             }
 
             impl MultiWindow {
-                /// Creates a new `MultiWindow`.
-                pub fn new() -> Self {
+                /// Creates a new `MultiWindow`. The system clipboard is opened when it is first used, a clipboard that is not available is not
+                /// an error.
+                pub fn new() -> Result<Self, egui_multiwin::multi_window::MultiWindowError> {
+                    // The event loop panics when the platform fails to create it
+                    let event_loop = std::panic::catch_unwind(egui_multiwin::async_winit::event_loop::EventLoop::new).map_err(|e| {
+                        egui_multiwin::multi_window::MultiWindowError::EventLoop(egui_multiwin::error::panic_message(&*e))
+                    })?;
                     let (t, r) = egui_multiwin::async_channel::bounded(10);
-                    MultiWindow {
-                        event_loop: Some(egui_multiwin::async_winit::event_loop::EventLoop::new()),
-                        pending_windows: t,
-                        window_receiver: Some(r),
-                        fonts: HashMap::new(),
-                        clipboard: Arc::new(Mutex::new(egui_multiwin::arboard::Clipboard::new().unwrap())),
+                    let (cancel_t, cancel_r) = egui_multiwin::async_channel::unbounded();
+                    let (timer_t, timer_r) = egui_multiwin::async_channel::unbounded();
+                    Ok(MultiWindow {
+                        event_loop: Some(event_loop),
+                        window_receiver: r,
+                        cancel_receiver: cancel_r,
+                        timer_receiver: timer_r,
+                        clipboard: Arc::new(Mutex::new(egui_multiwin::clipboard::SystemClipboard::new())),
+                        proxy: MultiWindowProxy {
+                            pending_windows: t,
+                            registry: egui_multiwin::registry::WindowRegistry::new(),
+                            settings: SharedSettings::default(),
+                            cancel_windows: cancel_t,
+                            timer_actions: timer_t,
+                            daemons: egui_multiwin::future_set::FuturesHashSet::new(),
+                        },
+                        tray: None,
+                        hotkeys: None,
+                        power_action: None,
+                        can_exit: None,
+                        autosave: None,
+                        session_factory: None,
+                        shutdown_timeout: std::time::Duration::from_secs(5),
+                    })
+                }
+
+                /// Creates a new `MultiWindow`, panicking if the event loop cannot be created
+                pub fn new_or_panic() -> Self {
+                    match Self::new() {
+                        Ok(m) => m,
+                        Err(e) => panic!("{}", e),
+                    }
+                }
+
+                /// Get a proxy that can be used to modify the application after [run](Self::run) has been called
+                pub fn proxy(&self) -> MultiWindowProxy {
+                    self.proxy.clone()
+                }
+
+                /// Set the style used by every window, including viewport windows. Windows that are already open use the new style on their next frame.
+                pub fn set_style(&mut self, style: egui::Style) {
+                    self.proxy.set_style(style);
+                }
+
+                /// Modify the style used by every window, starting from the default style if no style has been set.
+                pub fn modify_style(&mut self, f: impl FnOnce(&mut egui::Style)) {
+                    self.proxy.modify_style(f);
+                }
+
+                /// Set the theme for all current and future windows. This overrides the theme of the operating system, unless
+                /// [ThemePreference::System] is specified.
+                pub fn set_theme(&mut self, theme: ThemePreference) {
+                    self.proxy.set_theme(theme);
+                }
+
+                /// Limit how often windows repaint while they do not have focus and the mouse is not over them, 10 times per second by
+                /// default. Windows can override this with [TrackedWindowOptions::repaint_throttle].
+                pub fn set_repaint_throttle(&mut self, throttle: RepaintThrottle) {
+                    self.proxy.set_repaint_throttle(throttle);
+                }
+
+                /// Set the repaint limits of every window from a power profile, such as
+                /// [PowerSaver](egui_multiwin::power::PowerProfile::PowerSaver) to save power on a laptop.
+                pub fn set_power_profile(&mut self, profile: egui_multiwin::power::PowerProfile) {
+                    self.proxy.set_power_profile(profile);
+                }
+
+                /// Set the filter that changes the input of every window before each frame, given the id of the window, such as to lock
+                /// out the input of other windows during a modal operation. Use [MultiWindowProxy::set_input_filter] to change or remove
+                /// the filter later. [WindowCommand::SetInputEnabled] blanks the input of one window instead.
+                pub fn set_input_filter(&mut self, filter: impl Fn(egui_multiwin::registry::WindowId, &mut egui::RawInput) + Send + Sync + 'static) {
+                    self.proxy.set_input_filter(Some(Arc::new(filter)));
+                }
+
+                /// Get a handle to the registry of open windows
+                pub fn registry(&self) -> egui_multiwin::registry::WindowRegistry {
+                    self.proxy.registry()
+                }
+
+                /// Get the egui context of a window. Returns None until egui is running in the window, so a window added before
+                /// [Self::run] has no context yet. Use the proxy or the registry from another task instead.
+                pub fn context(&self, id: egui_multiwin::registry::WindowId) -> Option<egui::Context> {
+                    self.proxy.context(id)
+                }
+
+                /// Get the textures shared by all windows. Enable [Self::share_contexts] so that windows copy the uploaded textures
+                /// instead of uploading each image again.
+                pub fn textures(&self) -> egui_multiwin::textures::AppTextureManager {
+                    self.proxy.textures()
+                }
+
+                /// Get the monitors that are available. Returns an empty list once the event loop is running, use
+                /// [egui_multiwin::registry::WindowRegistry::window_monitors] from a window instead.
+                pub async fn monitors(&self) -> Vec<egui_multiwin::monitor::MonitorInfo> {
+                    match &self.event_loop {
+                        Some(e) => egui_multiwin::monitor::monitors(e.window_target()).await,
+                        None => Vec::new(),
+                    }
+                }
+
+                /// Set the action run when a window panics while drawing a frame, such as to show an error to the user. The window that
+                /// panicked is closed and the other windows keep running. Use [egui_multiwin::error::panic_message] to get the message of the panic.
+                /// Only windows created after this call run the action.
+                pub fn on_window_panic(&mut self, action: impl Fn(egui_multiwin::registry::WindowId, &(dyn std::any::Any + Send)) + Send + Sync + 'static) {
+                    self.proxy.settings.window_panic = Some(Arc::new(action));
+                }
+
+                /// Run a hook on the egui context of every window, including viewport windows and windows created later by the proxy or a
+                /// `RedrawResponse`. The hook runs once per window after the fonts and style are set and before
+                /// the first frame, such as to install image loaders with `egui_extras::install_image_loaders`. Hooks run in the order
+                /// they were added. With the `image-loaders` feature, the image loaders of egui_extras are installed without a hook.
+                pub fn with_context_hook(mut self, hook: impl Fn(&egui::Context) + Send + Sync + 'static) -> Self {
+                    self.proxy.settings.context_hooks.push(Arc::new(hook));
+                    self
+                }
+
+                /// Save the position, size and maximized state of windows in a file when they close, and restore them when the windows are
+                /// created again. Only windows with [NewWindowRequest::with_persist_key] are saved.
+                pub fn with_persistence(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+                    self.proxy.settings.persistence = Some(egui_multiwin::persistence::GeometryStore::load(path));
+                    self
+                }
+
+                /// Use a different clipboard for all windows, such as a [egui_multiwin::clipboard::MemoryClipboard] for tests. Text copied
+                /// or cut in egui is placed on this clipboard. The windows share the provider, unless it makes a clipboard for each window
+                /// with [egui_multiwin::clipboard::ClipboardProvider::for_window].
+                pub fn with_clipboard(mut self, provider: impl egui_multiwin::clipboard::ClipboardProvider + 'static) -> Self {
+                    self.clipboard = Arc::new(Mutex::new(provider));
+                    self
+                }
+
+                /// Set how long the non-root windows have to close when the application exits, 5 seconds by default. Windows that have not
+                /// closed by then are dropped. See [Self::run].
+                pub fn set_shutdown_timeout(&mut self, timeout: std::time::Duration) {
+                    self.shutdown_timeout = timeout;
+                }
+
+                /// Set the function that creates windows of a saved session again. See [Self::restore_session].
+                pub fn set_session_factory(&mut self, factory: impl Fn(&str, &[u8]) -> Option<NewWindowRequest> + 'static) {
+                    self.session_factory = Some(Box::new(factory));
+                }
+
+                /// Open the windows saved in a session file, and save the windows that are open when the application exits to the same file.
+                /// Windows are part of the session when they are requested with [NewWindowRequest::with_session]. Call this after
+                /// [Self::set_session_factory] and before [Self::run]. Windows keep being a root window or not being a root window.
+                pub async fn restore_session(&mut self, path: impl Into<std::path::PathBuf>) -> Result<(), egui_multiwin::Error> {
+                    let factory = self.session_factory.as_ref();
+                    let requests = self.proxy.settings.session.restore(path, |entry| {
+                        let mut request = factory.and_then(|f| f(&entry.key, &entry.payload))?;
+                        if let Some(w) = &mut request.window_state {
+                            w.set_root(entry.root);
+                        }
+                        request.session = Some((entry.key, entry.payload));
+                        Some(request)
+                    });
+                    // One batch, the event loop does not read the pending windows before it runs
+                    self.proxy.add_all(requests).await?;
+                    Ok(())
+                }
+
+                /// Set the icon for windows that do not specify their own icon with [NewWindowRequest::with_icon]
+                pub fn set_default_icon(&mut self, icon: egui_multiwin::icon::WindowIcon) {
+                    self.proxy.set_default_icon(Some(icon));
+                }
+
+                /// Set the tray icon of the application. The action is run with the common data for every event of the tray icon, and can
+                /// create windows or show and hide existing windows. The tray icon is removed when [run](Self::run) returns. The application
+                /// exits when every root window is closed, so hide root windows with [WindowCommand::SetVisible] to keep the application in the tray.
+                pub fn set_tray(
+                    &mut self,
+                    tray: egui_multiwin::tray::Tray,
+                    action: impl Fn(&mut $common, egui_multiwin::tray::TrayEvent) -> AppEventResponse + 'static,
+                ) {
+                    self.tray = Some((tray, Box::new(action)));
+                }
+
+                /// Set the global hotkeys of the application. The action is run with the common data when a hotkey is pressed, even when no
+                /// window of the application has focus. The hotkeys are unregistered when [run](Self::run) returns.
+                pub fn set_hotkeys(
+                    &mut self,
+                    hotkeys: egui_multiwin::hotkeys::GlobalHotkeys,
+                    action: impl Fn(&mut $common, u32) -> AppEventResponse + 'static,
+                ) {
+                    self.hotkeys = Some((hotkeys, Box::new(action)));
+                }
+
+                /// Set the action run with the common data when the computer switches between its battery and being plugged in, such as to
+                /// reduce the work of the application on battery. Computers without a battery never run the action. The repaint limits of an
+                /// [automatic](egui_multiwin::power::PowerProfile::Automatic) power profile are changed before the action runs.
+                pub fn set_power_action(
+                    &mut self,
+                    action: impl Fn(&mut $common, egui_multiwin::power::PowerSource) -> AppEventResponse + 'static,
+                ) {
+                    self.power_action = Some(Box::new(action));
+                }
+
+                /// Set the action that decides if the application exits when every root window has closed, such as to confirm exiting
+                /// when there are unsaved changes. The application exits without asking when this is not set.
+                pub fn set_can_exit(&mut self, action: impl Fn(&mut $common) -> ExitDecision + 'static) {
+                    self.can_exit = Some(Box::new(action));
+                }
+
+                /// Save the common data periodically, and once more when the application exits, after the windows have closed and before the
+                /// event loop exits. The action runs with the common data locked on the thread of the event loop, so a save never overlaps
+                /// another save. The interval is counted from the end of the previous save. Errors are logged and the application keeps
+                /// running.
+                pub fn set_autosave<E: std::fmt::Display>(
+                    &mut self,
+                    interval: std::time::Duration,
+                    action: impl Fn(&$common) -> Result<(), E> + 'static,
+                ) {
+                    self.autosave = Some((interval, std::rc::Rc::new(move |c: &$common| action(c).map_err(|e| e.to_string()))));
+                }
+
+                /// Run the autosave action, logging a failure
+                fn autosave(action: &AutosaveAction, c: &Arc<Mutex<$common>>) {
+                    let started = std::time::Instant::now();
+                    match action(&c.lock_recover()) {
+                        Ok(()) => egui_multiwin::trace::debug(format_args!("Autosave took {:?}", started.elapsed())),
+                        Err(e) => egui_multiwin::trace::error(format_args!("Unable to autosave: {}", e)),
+                    }
+                }
+
+                /// Apply the response to an event of the tray icon, a global hotkey, a timer, or a change of the power source. The windows are
+                /// created right away instead of being sent to the pending windows, which only the event loop reads.
+                async fn process_app_event_response(&mut self,
+                    response: AppEventResponse,
+                    c: Arc<Mutex<$common>>,
+                    elwt: &async_winit::event_loop::EventLoopWindowTarget<ThreadSafety>,
+                    events: &mut egui_multiwin::Events,
+                ) {
+                    if !response.new_windows.is_empty() {
+                        self.create_windows(response.new_windows, c, elwt, events).await;
+                    }
+                    for id in response.hide {
+                        if let Some(w) = self.proxy.registry.get(id) {
+                            w.window.set_visible(false).await;
+                        }
+                    }
+                    for id in response.show {
+                        if let Some(w) = self.proxy.registry.get(id) {
+                            w.window.set_visible(true).await;
+                            w.window.focus_window().await;
+                        }
                     }
                 }
 
-                /// Add a font that is applied to every window. Be sure to call this before calling [add](crate::multi_window::MultiWindow::add)
+                /// Create the opengl contexts of windows so that they share objects such as textures, allowing a texture uploaded in one window
+                /// to be displayed in another window. See [egui_multiwin::tracked_window::SharedGlContext] for the objects that are shared.
+                /// Only windows created after this call share objects. Windows that cannot share, such as windows on another display, get an
+                /// isolated context, which is reported by [CommonWindowData::shared_context].
+                pub fn share_contexts(&mut self, share: bool) {
+                    self.proxy.settings.gl_share.set_enabled(share);
+                }
+
+                /// Add a font that is applied to every window. Fonts can also be added after windows are open with [MultiWindowProxy::add_font].
                 /// multi_window is an instance of [MultiWindow](crate::multi_window::MultiWindow), DATA is a static `&[u8]` - most like defined with a `include_bytes!()` macro
                 /// ```
                 /// use egui_multiwin::multi_window::NewWindowRequest;
@@ -730,194 +2513,561 @@ macro_rules! multi_window {
                 ///     }
                 /// }
                 ///
-                /// let mut multi_window: egui_multiwin::multi_window::MultiWindow = egui_multiwin::multi_window::MultiWindow::new();
+                /// let mut multi_window: egui_multiwin::multi_window::MultiWindow = egui_multiwin::multi_window::MultiWindow::new_or_panic();
                 /// let DATA = include_bytes!("cmunbtl.ttf");
                 /// multi_window.add_font("my_font".to_string(), egui_multiwin::egui::FontData::from_static(DATA));
                 /// ```
                 pub fn add_font(&mut self, name: String, fd: egui_multiwin::egui::FontData) {
-                    self.fonts.insert(name, fd);
+                    self.proxy.add_font(name, fd);
                 }
 
-                /// Adds a new `TrackedWindow` to the `MultiWindow`. If custom fonts are desired, call [add_font](crate::multi_window::MultiWindow::add_font) first.
+                /// Add a font that is applied to every window by loading it from a file. Errors are returned for missing files or data that is not a valid font.
+                pub fn add_font_file(
+                    &mut self,
+                    name: String,
+                    path: impl AsRef<std::path::Path>,
+                ) -> Result<(), egui_multiwin::fonts::FontLoadError> {
+                    let fd = egui_multiwin::fonts::font_from_file(path)?;
+                    self.proxy.add_font(name, fd);
+                    Ok(())
+                }
+
+                /// Add a font that is applied to every window, also adding it to the specified built in families. Use [egui::FontFamily::Proportional]
+                /// and [egui::FontFamily::Monospace] with [egui_multiwin::fonts::FontPlacement::Prepend] to make the font the default font of the application.
+                /// Fonts are placed into families in the order they are added.
+                pub fn add_font_to_families(
+                    &mut self,
+                    name: String,
+                    fd: egui_multiwin::egui::FontData,
+                    families: Vec<(egui::FontFamily, egui_multiwin::fonts::FontPlacement)>,
+                ) {
+                    self.proxy.add_font_to_families(name, fd, families);
+                }
+
+                /// Register a keyboard shortcut that works in every window of the application. The shortcut is checked before egui receives
+                /// the key press. When the action returns None, the shortcut is declined and egui receives the key press as normal.
+                pub fn register_shortcut(
+                    &mut self,
+                    shortcut: egui::KeyboardShortcut,
+                    action: impl Fn(&mut $common) -> Option<Vec<NewWindowRequest>> + Send + Sync + 'static,
+                ) -> Result<(), egui_multiwin::multi_window::ShortcutError> {
+                    let mut shortcuts = self.proxy.settings.shortcuts.lock_recover();
+                    if shortcuts.iter().any(|(s, _)| *s == shortcut) {
+                        return Err(egui_multiwin::multi_window::ShortcutError::Conflict(shortcut));
+                    }
+                    shortcuts.push((shortcut, Box::new(action)));
+                    Ok(())
+                }
+
+                /// Run a background future alongside the windows, see [MultiWindowProxy::add_daemon]
+                pub fn add_daemon(&mut self, f: impl std::future::Future<Output = ()> + 'static) -> u32 {
+                    self.proxy.add_daemon(f)
+                }
+
+                /// Fire a timer once after a delay, see [MultiWindowProxy::after]
+                pub fn after(&mut self, delay: std::time::Duration, target: TimerTarget) -> u32 {
+                    self.proxy.after(delay, target)
+                }
+
+                /// Fire a timer repeatedly, see [MultiWindowProxy::every]
+                pub fn every(&mut self, period: std::time::Duration, target: TimerTarget) -> u32 {
+                    self.proxy.every(period, target)
+                }
+
+                /// Adds a new `TrackedWindow` to the `MultiWindow`, returning the id the window will have.
                 pub async fn add(
                     &mut self,
                     window: NewWindowRequest,
-                ) {
-                    self.pending_windows.send(window).await.unwrap();
+                ) -> Result<egui_multiwin::registry::WindowId, egui_multiwin::Error> {
+                    self.proxy.add(window).await
+                }
+
+                /// Adds several new `TrackedWindow`s to the `MultiWindow`, see [MultiWindowProxy::add_all]
+                pub async fn add_all(
+                    &mut self,
+                    windows: Vec<NewWindowRequest>,
+                ) -> Result<Vec<egui_multiwin::registry::WindowId>, egui_multiwin::Error> {
+                    self.proxy.add_all(windows).await
                 }
 
                 async fn init_egui(
-                    fontmap: &HashMap<String, egui_multiwin::egui::FontData>,
+                    settings: &SharedSettings,
                     twc: &mut TrackedWindowContainer,
-                    elwt: &async_winit::event_loop::EventLoopWindowTarget<async_winit::ThreadSafe>,
-                    window: &Arc<egui_multiwin::async_winit::window::Window<async_winit::ThreadSafe>>,
-                ) {
-                    let gl_window = twc.gl_window_option().take().unwrap().make_current();
+                    elwt: &async_winit::event_loop::EventLoopWindowTarget<ThreadSafety>,
+                    window: &Arc<egui_multiwin::async_winit::window::Window<ThreadSafety>>,
+                ) -> Result<(), ContextError> {
+                    let draw_lock = settings.draw_lock.lock().await;
+                    let id = twc.common().id();
+                    settings.contexts.make_current(id, twc.gl_window_mut())?;
+                    let gl_window = twc.gl_window();
                     let gl = Arc::new(unsafe {
                         glow::Context::from_loader_function(|s| {
                             gl_window.get_proc_address(s)
                         })
                     });
 
-                    unsafe {
-                        use glow::HasContext as _;
-                        gl.enable(glow::FRAMEBUFFER_SRGB);
+                    let info = GlInfo::new(&gl, gl_window);
+                    info.log();
+                    if let Some(w) = twc.get_window_data() {
+                        w.lock_recover().opengl_init(&info);
+                    }
+                    twc.common_mut().gl_info = Some(info);
+                    if twc.common().srgb {
+                        unsafe {
+                            use glow::HasContext as _;
+                            gl.enable(glow::FRAMEBUFFER_SRGB);
+                        }
                     }
                     let mut egui = {
                         let common = twc.common_mut();
                         let egui = egui_glow_async::EguiGlow::new(elwt, gl, common.shader, None);
-                        let mut fonts = egui::FontDefinitions::default();
-                        for (name, font) in fontmap.iter() {
-                            fonts.font_data.insert(name.clone(), font.clone());
-                            fonts.families.insert(
-                                egui::FontFamily::Name(name.to_owned().into()),
-                                vec![name.to_owned()],
-                            );
-                        }
-                        egui.egui_ctx.set_fonts(fonts);
                         egui
                     };
-                    twc.gl_window_option().replace(gl_window);
+                    settings.textures.attach(&egui, id, twc.common().shared_context);
+                    settings.contexts.make_not_current(id, twc.gl_window_mut())?;
+                    drop(draw_lock);
                     egui.egui_ctx.set_embed_viewports(false);
+                    if let (Some(key), Some(store)) = (&twc.common().persist_key, &settings.persistence) {
+                        store.restore_memory(key, &egui.egui_ctx);
+                    }
+                    twc.common_mut().system_theme = window.theme().await;
                     egui_multiwin::egui_glow_async::egui_async_winit::State::register_event_handlers(&egui.egui_winit, window);
+                    // The hooks see the fonts and style of the window, and run before its first frame
+                    let ctx = egui.egui_ctx.clone();
+                    id.attach(&ctx);
                     twc.common_mut().egui = Some(egui);
+                    twc.apply_shared_settings(settings);
+                    egui_multiwin::tracked_window::install_loaders(&ctx);
+                    for hook in &settings.context_hooks {
+                        hook(&ctx);
+                    }
                     twc.check_viewport_builder().await;
+                    Ok(())
+                }
+
+                /// Build the window for a request, applying the icon, saved geometry and other options of the request to its builder. The
+                /// builder of the request is used up.
+                async fn build_window(&self,
+                    window: &mut NewWindowRequest,
+                    elwt: &async_winit::event_loop::EventLoopWindowTarget<ThreadSafety>,
+                ) -> Result<Arc<egui_multiwin::async_winit::window::Window<ThreadSafety>>, egui_multiwin::Error> {
+                    let builder = std::mem::replace(&mut window.builder, egui_multiwin::async_winit::window::WindowBuilder::new());
+                    let icon = window.icon.take().or_else(|| self.proxy.settings.icon.lock_recover().clone());
+                    let mut builder = match icon {
+                        Some(icon) => builder.with_window_icon(Some(icon.icon())),
+                        None => builder,
+                    };
+                    if let (Some(key), Some(store)) = (&window.persist_key, &self.proxy.settings.persistence) {
+                        builder = store.place(key, builder, elwt).await;
+                    }
+                    if let Some(decorations) = window.decorations {
+                        builder = builder.with_decorations(decorations);
+                    }
+                    if let Some(resizable) = window.resizable {
+                        builder = builder.with_resizable(resizable);
+                    }
+                    if !window.visible || window.hide_until_drawn {
+                        builder = builder.with_visible(false);
+                    }
+                    if let Some(selector) = &window.monitor {
+                        let monitors = egui_multiwin::monitor::monitors(elwt).await;
+                        if let Some(m) = selector.select(&monitors, None) {
+                            let (x, y) = m.place(None, m.position);
+                            builder = builder.with_position(async_winit::dpi::PhysicalPosition::new(x, y));
+                        }
+                    }
+                    if let Some(position) = window.outer_position {
+                        builder = builder.with_position(position);
+                    }
+                    let built = TrackedWindowContainer::build_window(builder, &window.options).await?;
+                    // Some window managers place new windows themselves, moving the window afterwards still works with them
+                    if let Some(position) = window.outer_position {
+                        built.set_outer_position(position).await;
+                    }
+                    Ok(built)
                 }
 
+                /// Create the opengl context and egui of a window that was built with [Self::build_window], and start running the window.
+                /// The window is focused when `focus` is set, once it is visible.
                 async fn process_pending_window(&mut self,
                     window: NewWindowRequest,
+                    winitwindow: Arc<egui_multiwin::async_winit::window::Window<ThreadSafety>>,
+                    focus: bool,
                     c: Arc<Mutex<$common>>,
-                    elwt: &async_winit::event_loop::EventLoopWindowTarget<async_winit::ThreadSafe>,
+                    elwt: &async_winit::event_loop::EventLoopWindowTarget<ThreadSafety>,
                     events: &mut egui_multiwin::Events,
-                ) -> Result<(), DisplayCreationError> {
-                    let twc = TrackedWindowContainer::create(
+                ) -> Result<egui_multiwin::registry::WindowId, egui_multiwin::Error> {
+                    let mut twc = TrackedWindowContainer::create(
                         window.window_state.map(|a| Arc::new(Mutex::new(a))),
                         window.viewportset,
                         &window
                             .viewport_id
                             .unwrap_or(egui::viewport::ViewportId::ROOT),
                         window.viewport_callback,
-                        window.builder,
+                        winitwindow,
                         elwt,
                         &window.options,
                         window.viewport,
+                        &self.proxy.settings.gl_share,
+                        window.id,
                     ).await?;
+                    twc.common_mut().font_overrides = window.fonts;
+                    twc.common_mut().style_override = window.style;
+                    twc.common_mut().viewport_state = window.viewport_state;
+                    twc.common_mut().viewport_options = window.viewport_options;
+                    twc.common_mut().persist_key = window.persist_key.clone();
+                    if let Some(mode) = &window.fullscreen {
+                        let w = twc.gl_window().window();
+                        twc.set_fullscreen(Some(mode), &w).await;
+                    }
+                    if let Some(opacity) = window.opacity {
+                        egui_multiwin::opacity::set_opacity(&twc.gl_window().window(), opacity);
+                    }
+                    if !window.hit_test {
+                        let w = twc.gl_window().window();
+                        twc.set_mouse_passthrough(true, &w).await;
+                    }
+                    self.proxy.settings.attach_window(&self.proxy.registry,
+                        window.id,
+                        &twc.gl_window().window(),
+                        window.modal_for,
+                        window.owned_by,
+                        window.modal_scrim,
+                    );
+                    let show_after_draw = window.visible && window.hide_until_drawn;
+                    twc.common_mut().show_after_draw = show_after_draw;
+                    twc.common_mut().focus_when_shown = focus && show_after_draw;
+                    if focus && window.visible && !window.hide_until_drawn {
+                        twc.gl_window().window().focus_window().await;
+                    }
+                    self.proxy.settings.contexts.add_window();
+                    let window_id = twc.common().id();
                     let twc = Arc::new(Mutex::new(twc));
+                    let span = egui_multiwin::trace::WindowSpan::new(window_id);
+                    // Asks the window to close, from the close button or when the application exits
+                    let close_handle = egui_multiwin::sync::Notify::new();
+                    events.add_close_handle(window_id, close_handle.clone());
+                    // Held by the window future, so that the window is cleaned up even when the future is cancelled
+                    let cleanup = {
+                        let twc = twc.clone();
+                        let close_handle = close_handle.clone();
+                        let teardown = span.teardown();
+                        let registry = self.proxy.registry.clone();
+                        let settings = self.proxy.settings.clone();
+                        let daemons = self.proxy.daemons.clone();
+                        egui_multiwin::future_set::DropGuard::new(move || teardown.in_scope(|| {
+                            close_handle.close();
+                            settings.window_closed(&registry, window_id);
+                            if let Ok(mut t) = twc.try_lock() {
+                                // Viewports can not outlive the window that created them
+                                let viewports = t.get_common().egui.as_ref().map(|e| egui_multiwin::viewport::viewports(&e.egui_ctx));
+                                if let Some(viewports) = viewports {
+                                    egui_multiwin::viewport::close_all(&viewports);
+                                }
+                                // The opengl resources of egui can only be released while the context is current, which needs the draw lock.
+                                // When another window is drawing, the resources are released once it is done.
+                                if let Some(_draw) = settings.draw_lock.try_lock() {
+                                    Self::release_window(window_id, &mut t, &settings.contexts);
+                                    return;
+                                }
+                                drop(t);
+                                let twc = twc.clone();
+                                daemons.get().add_future(async move {
+                                    let _draw = settings.draw_lock.lock().await;
+                                    Self::release_window(window_id, &mut twc.lock_recover(), &settings.contexts);
+                                });
+                                return;
+                            }
+                            settings.contexts.remove_window(window_id);
+                        }))
+                    };
                     let twc2 = twc.clone();
-                    let clipboard = self.clipboard.to_owned();
-                    let fonts = self.fonts.clone();
+                    let window_clipboard = self.clipboard.lock_recover().for_window();
+                    let clipboard = window_clipboard.unwrap_or_else(|| self.clipboard.to_owned());
+                    let settings = self.proxy.settings.clone();
+                    let registry = self.proxy.registry.clone();
                     let c2 = c.to_owned();
                     let elwt2 = elwt.clone();
-                    let nwr = self.pending_windows.clone();
-                    let window_process = async move {
-                        let (quit_t, mut quit_r) = egui_multiwin::async_channel::bounded(2);
-                        let id : usize = egui_multiwin::rand::Rng::gen(&mut egui_multiwin::rand::thread_rng());
-                        let glw = {
-                            let twc3 = twc2.lock().unwrap();
-                            twc3.get_common().gl_window.as_ref().unwrap().window()
+                    let nwr = self.proxy.pending_windows.clone();
+                    let persist = window.persist_key.zip(self.proxy.settings.persistence.clone());
+                    let session = self.proxy.settings.session.clone();
+                    let session_entry = window.session.map(|(key, payload)| egui_multiwin::session::SessionEntry {
+                        key,
+                        payload,
+                        root: twc.lock_recover().get_window_data().map(|w| w.lock_recover().is_root()).unwrap_or(false),
+                    });
+                    let span2 = span.clone();
+                    let window_process = span.instrument(async move {
+                        let (glw, file_drops, ime, touch, viewport_state, cursor_grab) = {
+                            let twc3 = twc2.lock_recover();
+                            let common = twc3.get_common();
+                            (common.gl_window.window(), common.file_drops.clone(), common.ime.clone(), common.touch.clone(), common.viewport_state.clone(), common.cursor_grab.clone())
                         };
+                        let info = egui_multiwin::registry::WindowInfo::new(glw.clone()).with_close_request(close_handle.clone());
+                        registry.insert(window_id, info.clone());
+                        if let Some(entry) = session_entry {
+                            session.window_opened(window_id, entry);
+                        }
+                        let registry2 = registry.clone();
                         let glw3 = glw.clone();
-                        let quit = async move {
-                            quit_r.recv().await.unwrap();
-                        };
-                        let close = glw3.close_requested().wait();
-                        let (t, mut r) = egui_multiwin::async_channel::bounded(10);
-                        let (t2, mut r2) = egui_multiwin::async_channel::bounded(10);
-                        let ta = t.clone();
-                        glw3.close_requested().wait_direct_async(move |a| {
-                            let t = ta.clone();
-                            async move {
-                                t.send(true).await.unwrap();
-                                println!("Close window {}", id);
-                                false
-                            }
-                        });
-                        // This runs the drawing on the proper thread, preventing async-winit from trying to run two draw events at the same time
-                        glw3.redraw_requested().wait_direct_async(move |c| {
-                            let t = t.clone();
-                            let r2 = r2.clone();
-                            async move {
-                                t.send(true).await.unwrap();
-                                let a: bool = r2.recv().await.unwrap();
-                                true
-                            }
-                        });
+                        egui_multiwin::tracked_window::FileDropQueue::register(&file_drops, &glw3);
+                        egui_multiwin::tracked_window::ImeState::register(&ime, &glw3);
+                        egui_multiwin::tracked_window::TouchState::register(&touch, &glw3);
+                        // The draw loop asks the window with can_quit before closing it
+                        let signals = egui_multiwin::tracked_window::DrawSignals::register(window_id,
+                            &glw3,
+                            &elwt2,
+                            close_handle,
+                            cursor_grab,
+                            viewport_state,
+                        );
+                        let draw_signals = &signals;
                         let twc4 = twc2.clone();
+                        let span3 = span2.clone();
+                        use egui_multiwin::futures_lite::FutureExt;
+                        // An error in a frame closes the window
                         let draw = async move {
-                            let mut glw2 = glw.clone();
-                            {
-                                let mut twc5 = twc4.lock().unwrap();
-                                Self::init_egui(&fonts, &mut *twc5, &elwt2, &mut glw2).await;
-                            };
-                            loop {
-                                let a = r.recv().await.unwrap();
-                                let mut t = twc4.lock().unwrap();
-                                if let Some(rr) = t.redraw(&c2, &clipboard, &elwt2).await {
-                                    if rr.quit {
-                                        println!("Need to quit a window");
-                                        quit_t.send(()).await.unwrap();
+                            let frames = async {
+                                let mut glw2 = glw.clone();
+                                {
+                                    let mut twc5 = twc4.lock_recover();
+                                    let init = Self::init_egui(&settings, &mut *twc5, &elwt2, &mut glw2);
+                                    span3.creation().instrument(init).await.map_err(egui_multiwin::Error::EguiInit)?;
+                                    if let Some(egui) = &twc5.get_common().egui {
+                                        egui_multiwin::drag::attach(&egui.egui_ctx, &registry, window_id);
+                                        // Repaints requested outside of a frame, such as by a background task, wake the window
+                                        egui.egui_ctx.set_request_repaint_callback(draw_signals.repaint_callback());
+                                        info.set_context(&egui.egui_ctx);
                                     }
-                                    if !rr.new_windows.is_empty() {
-                                        for w in rr.new_windows {
-                                            nwr.send(w).await.unwrap();
+                                };
+                                let mut throttle = egui_multiwin::tracked_window::ThrottleState::default();
+                                // The first frame is drawn without waiting for the platform to ask for it, so that a new window shows its
+                                // contents as soon as possible, such as a torn off tab under the mouse
+                                let mut first_frame = true;
+                                loop {
+                                    let policy = twc4.lock_recover().get_common().options.repaint_throttle
+                                        .unwrap_or_else(|| *settings.repaint_throttle.lock_recover());
+                                    throttle.set_frame_limit(*settings.frame_limit.lock_recover());
+                                    let wake = draw_signals.next(&throttle, first_frame).await?;
+                                    if let DrawWake::RepaintRequested(delay) = wake {
+                                        throttle.request_repaint(std::time::Instant::now(), delay);
+                                        continue;
+                                    }
+                                    if let DrawWake::Repaint = wake {
+                                        throttle.take_repaint();
+                                        glw2.request_redraw();
+                                        continue;
+                                    }
+                                    if let DrawWake::CloseRequested = wake {
+                                        let mut t = twc4.lock_recover();
+                                        if t.can_quit(&mut c2.lock_recover()) {
+                                            egui_multiwin::trace::info(format_args!("Close window {}", window_id));
+                                            return Ok(());
                                         }
+                                        // The window refused to close, draw it so that it can show why
+                                        glw2.request_redraw();
+                                        continue;
                                     }
-                                    if let Some(redraw) = rr.redraw {
-                                        println!("Need to trigger a redraw after {:?}", redraw);
-                                        if redraw.is_zero() {
+                                    if let DrawWake::Event((event, consumed)) = wake {
+                                        let mut t = twc4.lock_recover();
+                                        if event.damages_pixels() {
+                                            t.damaged();
+                                        }
+                                        let c = t.handle_event(&c2, &event).await;
+                                        drop(t);
+                                        match event {
+                                            WindowEventDescriptor::Focused(f) => {
+                                                settings.focus_changed(&registry2, window_id, f).await;
+                                                // Repaint once without focus so that hover states are cleared
+                                                if throttle.set_focused(f) || !f {
+                                                    glw2.request_redraw();
+                                                }
+                                            }
+                                            WindowEventDescriptor::CursorEntered | WindowEventDescriptor::CursorLeft => {
+                                                // A delayed repaint happens immediately once the window is no longer throttled
+                                                if throttle.set_hovered(matches!(event, WindowEventDescriptor::CursorEntered)) {
+                                                    glw2.request_redraw();
+                                                }
+                                            }
+                                            WindowEventDescriptor::ThemeChanged(_) => {
+                                                glw2.request_redraw();
+                                            }
+                                            WindowEventDescriptor::Suspended => {
+                                                let mut t = twc4.lock_recover();
+                                                if let Err(e) = t.suspend(&c2, &settings).await {
+                                                    egui_multiwin::trace::error(format_args!("Unable to suspend a window: {}", e));
+                                                }
+                                            }
+                                            WindowEventDescriptor::Resumed => {
+                                                let mut t = twc4.lock_recover();
+                                                t.resume(&c2, &settings).await?;
+                                            }
+                                            _ => {}
                                         }
+                                        let _ = consumed.send(c).await;
+                                        continue;
+                                    }
+                                    // A throttled window that drew recently draws again once the interval has passed
+                                    if !throttle.allow_redraw(std::time::Instant::now(), policy) {
+                                        draw_signals.frame_done();
+                                        continue;
+                                    }
+                                    let mut t = twc4.lock_recover();
+                                    if let Some(egui) = &t.get_common().egui {
+                                        RepaintThrottle::set_throttled(&egui.egui_ctx, throttle.throttled(policy));
+                                    }
+                                    // A panic while drawing closes only this window
+                                    let redraw = std::panic::AssertUnwindSafe(t.redraw(&c2, &clipboard, &elwt2, &settings)).catch_unwind();
+                                    let rr = match span3.redraw().instrument(redraw).await {
+                                        Ok(rr) => rr,
+                                        Err(payload) => {
+                                            // The common data was locked during the frame, the other windows keep using it
+                                            c2.clear_poison();
+                                            if let Some(action) = &settings.window_panic {
+                                                action(window_id, &*payload);
+                                            }
+                                            return Err(egui_multiwin::Error::Panic(egui_multiwin::error::panic_message(&*payload)));
+                                        }
+                                    };
+                                    let rr = match rr {
+                                        Ok(rr) => rr,
+                                        Err(e) if e.is_context_lost() => {
+                                            egui_multiwin::trace::warn(format_args!("The opengl context of a window was lost, recreating it"));
+                                            t.recreate_context(&settings, &elwt2).await?;
+                                            None
+                                        }
+                                        // Windows are not drawn while the application is suspended
+                                        Err(ContextError::Suspended) => None,
+                                        Err(e) => return Err(e.into()),
+                                    };
+                                    if let Some(rr) = rr {
+                                        // Windows requested in the same frame as quitting are still created
+                                        if !rr.new_windows.is_empty() {
+                                            nwr.send(rr.new_windows).await?;
+                                        }
+                                        // Ending the draw loop resolves the future of the window, the cleanup destroys egui and the context
+                                        if rr.quit && t.can_quit(&mut c2.lock_recover()) {
+                                            egui_multiwin::trace::debug(format_args!("The window requested to quit"));
+                                            return Ok::<(), egui_multiwin::Error>(());
+                                        }
+                                        if let Some(redraw) = rr.redraw {
+                                            egui_multiwin::trace::debug(format_args!("The window requested a redraw after {:?}", redraw));
+                                        }
+                                        let now = std::time::Instant::now();
+                                        throttle.frame_drawn(now, rr.redraw, policy);
+                                        info.frame_drawn(now);
+                                    }
+                                    let ctx = t.get_common().egui.as_ref().map(|e| e.egui_ctx.clone());
+                                    drop(t);
+                                    // A drag between windows started by this window ends when the mouse button is released
+                                    if let Some(ctx) = ctx {
+                                        egui_multiwin::drag::finish_drag(&ctx).await;
+                                    }
+                                    // Nothing waits for the first frame
+                                    if !std::mem::take(&mut first_frame) {
+                                        draw_signals.frame_done();
                                     }
                                 }
-                                drop(t);
-                                t2.send(true).await.unwrap();
+                            };
+                            if let Err(e) = frames.await {
+                                egui_multiwin::trace::error(format_args!("Closing a window after an error: {}", e));
                             }
                         };
-                        use egui_multiwin::futures_lite::FutureExt;
-                        close.or(draw).or(quit).await;
+                        draw.await;
+                        signals.close();
+                        let save = async move {
+                            let Some((key, store)) = persist else {
+                                return;
+                            };
+                            let ctx = twc2.lock_recover().get_common().egui.as_ref().map(|e| e.egui_ctx.clone());
+                            store.save_window(&key, ctx.as_ref(), &glw3).await;
+                        };
+                        span2.teardown().instrument(save).await;
+                        drop(cleanup);
+                    });
+                    let root = match twc.clone().lock_recover().get_window_data() {
+                        Some(s) => s.lock_recover().is_root(),
+                        None => false,
                     };
-                    if let Some(s) = twc.clone().lock().unwrap().get_window_data() {
-                        if s.lock().unwrap().is_root() {
-                            events.window_close.get().add_future(window_process);
-                        }
-                        else {
-                            events.non_root_windows.get().add_future(window_process);
-                        }
+                    let id = if root {
+                        events.window_close.get().add_future(window_process)
                     }
                     else {
-                        events.non_root_windows.get().add_future(window_process);
+                        events.non_root_windows.get().add_future(window_process)
+                    };
+                    self.proxy.registry.set_task(window_id, egui_multiwin::registry::WindowTask { id, root });
+                    Ok(window_id)
+                }
+
+                /// Release the opengl resources of a window that closed, while holding the draw lock
+                fn release_window(
+                    window_id: egui_multiwin::registry::WindowId,
+                    t: &mut TrackedWindowContainer,
+                    contexts: &egui_multiwin::tracked_window::ContextTracker,
+                ) {
+                    if contexts.make_current(window_id, t.gl_window_mut()).is_ok() {
+                        if let Some(egui) = &mut t.common_mut().egui {
+                            egui.destroy();
+                        }
                     }
-                    Ok(())
+                    let _e = contexts.release(window_id, t.gl_window_mut());
+                    contexts.remove_window(window_id);
                 }
 
-                async fn get_pending_window(&mut self) -> Result<NewWindowRequest, egui_multiwin::async_channel::RecvError> {
-                    self.window_receiver.as_ref().unwrap().recv().await
+                /// Create a requested window. A window that cannot be created is logged and skipped, the application keeps running.
+                async fn create_window(&mut self,
+                    window: NewWindowRequest,
+                    c: Arc<Mutex<$common>>,
+                    elwt: &async_winit::event_loop::EventLoopWindowTarget<ThreadSafety>,
+                    events: &mut egui_multiwin::Events,
+                ) {
+                    self.create_windows(vec![window], c, elwt, events).await;
                 }
 
-                async fn process_pending_windows(&mut self,
+                /// Create requested windows. The windows are built at the same time, then their opengl contexts are created one at a time in
+                /// the order of the requests, because drivers may not allow creating contexts in parallel. When several windows are created,
+                /// the last one is focused, no matter which window finished building last. A window that cannot be created is logged and
+                /// skipped, the other windows are still created.
+                async fn create_windows(&mut self,
+                    mut windows: Vec<NewWindowRequest>,
                     c: Arc<Mutex<$common>>,
-                    elwt: &async_winit::event_loop::EventLoopWindowTarget<async_winit::ThreadSafe>,
+                    elwt: &async_winit::event_loop::EventLoopWindowTarget<ThreadSafety>,
                     events: &mut egui_multiwin::Events,
-                ) -> Result<(), DisplayCreationError> {
-                    loop {
-                        let window = {
-                            self.window_receiver.as_ref().unwrap().try_recv()
+                ) {
+                    let start = std::time::Instant::now();
+                    let count = windows.len();
+                    let built = {
+                        let this = &*self;
+                        let builds: Vec<_> = windows.iter_mut().map(|w| this.build_window(w, elwt)).collect();
+                        egui_multiwin::future_set::join_all(builds).await
+                    };
+                    for (i, (window, built)) in windows.into_iter().zip(built).enumerate() {
+                        let id = window.id;
+                        let focus = count > 1 && i + 1 == count;
+                        let result = match built {
+                            Ok(w) => self.process_pending_window(window, w, focus, c.to_owned(), elwt, events).await,
+                            Err(e) => Err(e),
                         };
-                        if let Ok(window) = window {
-                            self.process_pending_window(window, c.to_owned(), elwt, events).await?;
-                        }
-                        else {
-                            break;
+                        if let Err(e) = result {
+                            egui_multiwin::trace::error(format_args!("Unable to create window {}: {}", id, e));
                         }
                     }
-                    Ok(())
+                    if count > 1 {
+                        egui_multiwin::trace::debug(format_args!("Created {} windows in {:?}", count, start.elapsed()));
+                    }
                 }
 
                 /// Runs the event loop until all `TrackedWindow`s are closed.
+                ///
+                /// The application exits once every root window has closed. The non-root windows that are still open are then asked to close,
+                /// the same as when their close button is clicked, so they can save their work in `TrackedWindow::can_quit`. Windows that have
+                /// not closed within the shutdown timeout are dropped, see [Self::set_shutdown_timeout]. Then the common data is saved, if
+                /// [autosave](Self::set_autosave) is set, the clipboard is closed and the event loop exits.
                 pub fn run(
                     mut self,
                     c: $common,
                 ) -> Result<(), EventLoopError> {
-                    let event_loop_window_target: async_winit::event_loop::EventLoopWindowTarget<async_winit::ThreadSafe> =
+                    let event_loop_window_target: async_winit::event_loop::EventLoopWindowTarget<ThreadSafety> =
                         self.event_loop
                             .as_ref()
                             .unwrap()
@@ -928,32 +3078,122 @@ macro_rules! multi_window {
                         async move {
                             event_loop_window_target.resumed().await;
                             let e = event_loop_window_target.exit();
-                            let mut events = egui_multiwin::Events::new();
-                            self.process_pending_windows(c.to_owned(), &event_loop_window_target, &mut events).await.unwrap();
-                            println!("Done processing initial windows");
-                            let mut wc = events.window_close.clone();
-                            let mut oc = events.non_root_windows.clone();
-                            let pend = Self::get_pending_window;
+                            let mut events = egui_multiwin::Events::with_daemons(self.proxy.daemons.clone());
+                            let mut sources = egui_multiwin::multi_window::LoopSources::new(&events,
+                                self.window_receiver.clone(),
+                                self.cancel_receiver.clone(),
+                                self.timer_receiver.clone(),
+                            );
+                            self.create_windows(sources.pending_windows(), c.to_owned(), &event_loop_window_target, &mut events).await;
+                            egui_multiwin::trace::debug(format_args!("Done processing initial windows"));
+                            let tray = self.tray.take();
+                            let hotkeys = self.hotkeys.take();
+                            let power_action = self.power_action.take();
+                            let autosave = self.autosave.take();
+                            if let Some((interval, action)) = &autosave {
+                                let (interval, action, c) = (*interval, action.clone(), c.clone());
+                                self.proxy.add_daemon(async move {
+                                    loop {
+                                        async_winit::Timer::after(interval).await;
+                                        Self::autosave(&action, &c);
+                                    }
+                                });
+                            }
+                            // Cleared when exiting is denied, until another root window is opened
+                            let mut exit_check = true;
                             loop {
-                                tokio::select! {
-                                    _ = &mut wc => { println!("All the root windows closed"); break; }
-                                    _ = egui_multiwin::futures_lite::stream::StreamExt::next(&mut oc) => { }
-                                    pw = pend(&mut self) => {
-                                        if let Ok(w) = pw {
-                                            self.process_pending_window(w,
-                                                c.to_owned(),
-                                                &event_loop_window_target,
-                                                &mut events,
-                                            ).await.unwrap();
+                                let event = sources.next(exit_check, tray.as_ref().map(|t| &t.0), hotkeys.as_ref().map(|h| &h.0)).await;
+                                let mut response = None;
+                                match event {
+                                    LoopEvent::RootsClosed => {
+                                        egui_multiwin::trace::info(format_args!("All the root windows closed"));
+                                        let decision = match &self.can_exit {
+                                            Some(can_exit) => can_exit(&mut c.lock_recover()),
+                                            None => ExitDecision::Allow,
+                                        };
+                                        if !matches!(decision, ExitDecision::Allow) {
+                                            self.proxy.settings.session.keep_running();
+                                        }
+                                        match decision {
+                                            ExitDecision::Allow => break,
+                                            ExitDecision::Deny => {}
+                                            ExitDecision::ShowWindow(w) => {
+                                                self.create_window(w,
+                                                    c.to_owned(),
+                                                    &event_loop_window_target,
+                                                    &mut events,
+                                                ).await;
+                                            }
+                                        }
+                                        exit_check = !events.window_close.get().is_empty();
+                                    }
+                                    LoopEvent::WindowClosed | LoopEvent::DaemonDone => {}
+                                    LoopEvent::Cancel(id) => {
+                                        if let Some(task) = self.proxy.registry.task(id) {
+                                            if task.root {
+                                                events.window_close.get().cancel(task.id);
+                                            }
+                                            else {
+                                                events.non_root_windows.get().cancel(task.id);
+                                            }
+                                        }
+                                    }
+                                    LoopEvent::Tray(event) => {
+                                        if let Some((_, action)) = &tray {
+                                            response = Some(action(&mut c.lock_recover(), event));
+                                        }
+                                    }
+                                    LoopEvent::Hotkey(id) => {
+                                        if let Some((_, action)) = &hotkeys {
+                                            response = Some(action(&mut c.lock_recover(), id));
+                                        }
+                                    }
+                                    LoopEvent::Timer(action) => {
+                                        response = Some(action(&mut c.lock_recover()));
+                                    }
+                                    LoopEvent::Power(source) => {
+                                        egui_multiwin::trace::info(format_args!("The power source changed to {:?}", source));
+                                        let profile = *self.proxy.settings.power_profile.lock_recover();
+                                        if profile == egui_multiwin::power::PowerProfile::Automatic {
+                                            self.proxy.apply_power_source(source);
                                         }
+                                        if let Some(action) = &power_action {
+                                            response = Some(action(&mut c.lock_recover(), source));
+                                        }
+                                    }
+                                    LoopEvent::NewWindows(w) => {
+                                        self.create_windows(w,
+                                            c.to_owned(),
+                                            &event_loop_window_target,
+                                            &mut events,
+                                        ).await;
+                                        exit_check |= !events.window_close.get().is_empty();
                                     }
                                 }
+                                if let Some(response) = response {
+                                    self.process_app_event_response(response,
+                                        c.to_owned(),
+                                        &event_loop_window_target,
+                                        &mut events,
+                                    ).await;
+                                    exit_check |= !events.window_close.get().is_empty();
+                                }
                             }
-                            println!("Waiting for program to exit");
-                            drop(oc);
+                            egui_multiwin::trace::info(format_args!("Waiting for program to exit"));
+                            // The session is the windows open now, before the remaining windows are asked to close
+                            self.proxy.settings.session.save();
+                            sources.shutdown(&events, self.shutdown_timeout).await;
+                            drop(tray);
+                            drop(hotkeys);
+                            // The autosave daemon was cancelled with the other daemons, so this is the only save running
+                            if let Some((_, action)) = &autosave {
+                                Self::autosave(action, &c);
+                            }
+                            // On wayland the clipboard has to be torn down while the display connection is still open
+                            self.clipboard.lock_recover().close();
                             event_loop_window_target.set_exit();
                             let w = e.await;
-                            println!("Program exiting now");
+                            egui_multiwin::trace::info(format_args!("Program exiting now"));
                             w
                         })
                 }
@@ -975,6 +3215,47 @@ macro_rules! multi_window {
                 viewportset: Arc<Mutex<ViewportIdSet>>,
                 /// The viewport callback
                 viewport_callback: Option<std::sync::Arc<DeferredViewportUiCallback>>,
+                /// Fonts for this window only, merged over the fonts of the `MultiWindow`
+                pub fonts: Option<egui_multiwin::fonts::FontSet>,
+                /// A style for this window only, used instead of the style of the `MultiWindow`
+                pub style: Option<egui::Style>,
+                /// The state shared with the parent window, when the window displays a viewport
+                viewport_state: Option<Arc<Mutex<egui_multiwin::viewport::ViewportState>>>,
+                /// Options for the viewport windows created by this window. The options of this window are used when not set.
+                pub viewport_options: Option<TrackedWindowOptions>,
+                /// The icon for this window, used instead of the default icon of the `MultiWindow`
+                pub icon: Option<egui_multiwin::icon::WindowIcon>,
+                /// The key the geometry of the window is saved under, when persistence is enabled with [MultiWindow::with_persistence]
+                pub persist_key: Option<String>,
+                /// The key and payload used to open the window again in the next session, see [MultiWindow::restore_session]
+                pub session: Option<(String, Vec<u8>)>,
+                /// The monitor the window is opened on
+                pub monitor: Option<egui_multiwin::monitor::MonitorSelector>,
+                /// The position of the top left corner of the window on the screen, overriding the monitor and the saved geometry
+                pub outer_position: Option<async_winit::dpi::PhysicalPosition<i32>>,
+                /// Show the window fullscreen when it opens
+                pub fullscreen: Option<egui_multiwin::monitor::FullscreenMode>,
+                /// Show or hide the title bar and borders of the window, overriding the builder
+                pub decorations: Option<bool>,
+                /// Allow or prevent resizing the window, overriding the builder
+                pub resizable: Option<bool>,
+                /// The opacity of the whole window when it opens, from 0.0 (invisible) to 1.0 (opaque)
+                pub opacity: Option<f32>,
+                /// True when the window receives the mouse when it opens, false when the mouse passes through to the windows underneath
+                pub hit_test: bool,
+                /// Show the window when it opens. A window that should open hidden is requested with [Self::with_visible] instead of a
+                /// hidden builder, because the builder is made hidden until the first frame is drawn.
+                pub visible: bool,
+                /// Keep the window hidden until its first frame is drawn, so that it does not show a blank frame while opening
+                pub hide_until_drawn: bool,
+                /// The window blocked by this window while it is open, see [Self::modal_for]
+                pub modal_for: Option<egui_multiwin::registry::WindowId>,
+                /// The color drawn over the window blocked by this window, None to not dim it
+                pub modal_scrim: Option<egui::Color32>,
+                /// The window that owns this window, see [Self::owned_by]
+                pub owned_by: Option<egui_multiwin::registry::WindowId>,
+                /// The id the window will have, allocated with the request
+                id: egui_multiwin::registry::WindowId,
             }
 
             impl NewWindowRequest {
@@ -992,9 +3273,171 @@ macro_rules! multi_window {
                         viewport_id: None,
                         viewportset: Arc::new(Mutex::new(egui::viewport::ViewportIdSet::default())),
                         viewport_callback: None,
+                        fonts: None,
+                        style: None,
+                        viewport_state: None,
+                        viewport_options: None,
+                        icon: None,
+                        persist_key: None,
+                        session: None,
+                        monitor: None,
+                        outer_position: None,
+                        fullscreen: None,
+                        decorations: None,
+                        resizable: None,
+                        opacity: None,
+                        hit_test: true,
+                        visible: true,
+                        hide_until_drawn: true,
+                        modal_for: None,
+                        modal_scrim: Some(egui_multiwin::modal::DEFAULT_SCRIM),
+                        owned_by: None,
+                        id: egui_multiwin::registry::WindowId::allocate(),
                     }
                 }
 
+                /// Get the id the window will have once it is created. The id is the same in the registry, in log messages, and in the result
+                /// of `add`.
+                pub fn id(&self) -> egui_multiwin::registry::WindowId {
+                    self.id
+                }
+
+                /// Use fonts specific to this window, merged over the fonts of the `MultiWindow`. Viewports of this window use the same fonts.
+                pub fn with_fonts(mut self, fonts: egui_multiwin::fonts::FontSet) -> Self {
+                    self.fonts = Some(fonts);
+                    self
+                }
+
+                /// Use a style specific to this window instead of the style of the `MultiWindow`. Viewports of this window use the same style.
+                pub fn with_style(mut self, style: egui::Style) -> Self {
+                    self.style = Some(style);
+                    self
+                }
+
+                /// Use an icon for this window instead of the default icon of the `MultiWindow`. The icon is created from rgba pixel data,
+                /// 4 bytes per pixel, and is checked before the window is requested.
+                pub fn with_icon(mut self, rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, egui_multiwin::icon::IconError> {
+                    self.icon = Some(egui_multiwin::icon::WindowIcon::from_rgba(rgba, width, height)?);
+                    Ok(self)
+                }
+
+                /// Use an icon that was already created for this window, such as an icon loaded with
+                /// [egui_multiwin::icon::WindowIcon::from_png] when the `image` feature is enabled
+                pub fn with_window_icon(mut self, icon: egui_multiwin::icon::WindowIcon) -> Self {
+                    self.icon = Some(icon);
+                    self
+                }
+
+                /// Save the geometry of the window under a key when it closes, and restore it when a window with the same key is created.
+                /// Requires [MultiWindow::with_persistence].
+                pub fn with_persist_key(mut self, key: impl Into<String>) -> Self {
+                    self.persist_key = Some(key.into());
+                    self
+                }
+
+                /// Make the window part of the session, so that it is opened again on the next launch. The key and payload are given to the
+                /// session factory of the `MultiWindow` to create the window again.
+                pub fn with_session(mut self, key: impl Into<String>, payload: Vec<u8>) -> Self {
+                    self.session = Some((key.into(), payload));
+                    self
+                }
+
+                /// Open the window on a monitor, near its top left corner. A monitor that is not available selects the primary monitor.
+                pub fn on_monitor(mut self, selector: egui_multiwin::monitor::MonitorSelector) -> Self {
+                    self.monitor = Some(selector);
+                    self
+                }
+
+                /// Open the window with its top left corner at a position on the screen in physical pixels, such as at the mouse with
+                /// [egui_multiwin::tracked_window::screen_pointer_position] when a tab is dragged out of another window. Platforms that do
+                /// not let applications place their windows, such as wayland, ignore the position.
+                pub fn with_outer_position(mut self, position: async_winit::dpi::PhysicalPosition<i32>) -> Self {
+                    self.outer_position = Some(position);
+                    self
+                }
+
+                /// Make the window modal for another window, such as a dialog for the window that opened it. While the window is open,
+                /// the parent ignores the keyboard and pointer and is dimmed, and focusing the parent focuses this window instead. A modal
+                /// window can have a modal window of its own. The window is owned by its parent unless [Self::owned_by] chooses another
+                /// owner. A parent that is already closed when the window is created is ignored with a warning, and the window opens as an
+                /// ordinary window.
+                pub fn modal_for(mut self, parent: egui_multiwin::registry::WindowId) -> Self {
+                    self.modal_for = Some(parent);
+                    self
+                }
+
+                /// Make the window owned by another window, such as a tool window of the main window. The platform keeps the window above
+                /// its owner and minimizes it with the owner, see [egui_multiwin::owner]. The window is asked to close when its owner
+                /// closes, on every platform. An owner that is already closed when the window is created is ignored with a warning, and the
+                /// window opens as an ordinary window.
+                pub fn owned_by(mut self, owner: egui_multiwin::registry::WindowId) -> Self {
+                    self.owned_by = Some(owner);
+                    self
+                }
+
+                /// Choose the color drawn over the parent of a modal window, [egui_multiwin::modal::DEFAULT_SCRIM] by default. None
+                /// leaves the parent undimmed while it is blocked.
+                pub fn with_modal_scrim(mut self, scrim: Option<egui::Color32>) -> Self {
+                    self.modal_scrim = scrim;
+                    self
+                }
+
+                /// Show the window fullscreen when it opens. The window returns to the size given by the builder when it leaves fullscreen.
+                pub fn with_fullscreen(mut self, mode: egui_multiwin::monitor::FullscreenMode) -> Self {
+                    self.fullscreen = Some(mode);
+                    self
+                }
+
+                /// Show or hide the window when it opens. A hidden window can be shown later with
+                /// [egui_multiwin::tracked_window::WindowCommand::SetVisible] or [AppEventResponse::show].
+                pub fn with_visible(mut self, visible: bool) -> Self {
+                    self.visible = visible;
+                    self
+                }
+
+                /// Keep the window hidden until its first frame is drawn (the default), or show it as soon as it is built. Showing the
+                /// window early makes it show a blank or garbage frame for a moment, but leaves the visibility given by the builder alone.
+                pub fn with_hide_until_drawn(mut self, hide: bool) -> Self {
+                    self.hide_until_drawn = hide;
+                    self
+                }
+
+                /// Show or hide the title bar and borders of the window, overriding the builder. See
+                /// [egui_multiwin::tracked_window::WindowCommand::SetDecorations] for
+                /// changing it while the window is open.
+                pub fn with_decorations(mut self, decorations: bool) -> Self {
+                    self.decorations = Some(decorations);
+                    self
+                }
+
+                /// Allow or prevent resizing the window, overriding the builder. See
+                /// [egui_multiwin::tracked_window::WindowCommand::SetResizable] for changing it while the window is open.
+                pub fn with_resizable(mut self, resizable: bool) -> Self {
+                    self.resizable = Some(resizable);
+                    self
+                }
+
+                /// Set the opacity of the whole window when it opens, from 0.0 (invisible) to 1.0 (opaque). See
+                /// [egui_multiwin::tracked_window::WindowCommand::SetOpacity] for changing it while the window is open.
+                pub fn with_opacity(mut self, opacity: f32) -> Self {
+                    self.opacity = Some(opacity);
+                    self
+                }
+
+                /// Choose whether the window receives the mouse when it opens (true, the default), or lets the mouse pass through to the
+                /// windows underneath (false). See [egui_multiwin::tracked_window::WindowCommand::SetMousePassthrough] for changing it while
+                /// the window is open.
+                pub fn with_hit_test(mut self, hit_test: bool) -> Self {
+                    self.hit_test = hit_test;
+                    self
+                }
+
+                /// Use different options for the viewport windows created by this window
+                pub fn with_viewport_options(mut self, options: TrackedWindowOptions) -> Self {
+                    self.viewport_options = Some(options);
+                    self
+                }
+
                 /// Construct a new viewport window
                 pub fn new_viewport(
                     builder: egui_multiwin::async_winit::window::WindowBuilder,
@@ -1004,6 +3447,7 @@ macro_rules! multi_window {
                     viewportset: Arc<Mutex<ViewportIdSet>>,
                     vpcb: Option<std::sync::Arc<DeferredViewportUiCallback>>,
                 ) -> Self {
+                    let visible = vp_builder.visible.unwrap_or(true);
                     Self {
                         window_state: None,
                         builder,
@@ -1012,6 +3456,26 @@ macro_rules! multi_window {
                         viewport_id: Some(vp_id),
                         viewport_callback: vpcb,
                         viewportset,
+                        fonts: None,
+                        style: None,
+                        viewport_state: None,
+                        viewport_options: None,
+                        icon: None,
+                        persist_key: None,
+                        session: None,
+                        monitor: None,
+                        outer_position: None,
+                        fullscreen: None,
+                        decorations: None,
+                        resizable: None,
+                        opacity: None,
+                        hit_test: true,
+                        visible,
+                        hide_until_drawn: true,
+                        modal_for: None,
+                        modal_scrim: Some(egui_multiwin::modal::DEFAULT_SCRIM),
+                        owned_by: None,
+                        id: egui_multiwin::registry::WindowId::allocate(),
                     }
                 }
             }
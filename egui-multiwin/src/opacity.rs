@@ -0,0 +1,161 @@
+//! Contains the opacity of whole windows. Unlike per pixel transparency, this does not need a transparent opengl configuration, the
+//! platform blends the finished window with whatever is behind it. Supported on windows (layered windows), macos, and x11. Other
+//! platforms, such as wayland, ignore the opacity.
+
+use raw_window_handle_6::{HasDisplayHandle, HasWindowHandle};
+
+/// Limit an opacity to the range from 0.0 (invisible) to 1.0 (opaque). An opacity that is not a number is treated as opaque.
+pub fn clamp_opacity(opacity: f32) -> f32 {
+    if opacity.is_nan() {
+        1.0
+    } else {
+        opacity.clamp(0.0, 1.0)
+    }
+}
+
+/// Returns true when the platform can change the opacity of the window
+pub fn supported(window: &async_winit::window::Window<async_winit::ThreadSafe>) -> bool {
+    let (Ok(w), Ok(d)) = (window.window_handle(), window.display_handle()) else {
+        return false;
+    };
+    match (w.as_raw(), d.as_raw()) {
+        #[cfg(windows)]
+        (raw_window_handle_6::RawWindowHandle::Win32(_), _) => true,
+        #[cfg(target_os = "macos")]
+        (raw_window_handle_6::RawWindowHandle::AppKit(_), _) => true,
+        #[cfg(all(unix, not(target_os = "macos"), not(target_os = "android")))]
+        (
+            raw_window_handle_6::RawWindowHandle::Xlib(_),
+            raw_window_handle_6::RawDisplayHandle::Xlib(d),
+        ) => d.display.is_some(),
+        _ => false,
+    }
+}
+
+/// Set the opacity of a window, clamped with [clamp_opacity]. Returns false when the platform cannot change the opacity of the window,
+/// see [supported].
+pub fn set_opacity(
+    window: &async_winit::window::Window<async_winit::ThreadSafe>,
+    opacity: f32,
+) -> bool {
+    let opacity = clamp_opacity(opacity);
+    let (Ok(w), Ok(d)) = (window.window_handle(), window.display_handle()) else {
+        return false;
+    };
+    match (w.as_raw(), d.as_raw()) {
+        #[cfg(windows)]
+        (raw_window_handle_6::RawWindowHandle::Win32(h), _) => {
+            win32::set_opacity(h.hwnd.get(), opacity)
+        }
+        #[cfg(target_os = "macos")]
+        (raw_window_handle_6::RawWindowHandle::AppKit(h), _) => {
+            appkit::set_opacity(h.ns_view.as_ptr(), opacity)
+        }
+        #[cfg(all(unix, not(target_os = "macos"), not(target_os = "android")))]
+        (
+            raw_window_handle_6::RawWindowHandle::Xlib(h),
+            raw_window_handle_6::RawDisplayHandle::Xlib(d),
+        ) => match d.display {
+            Some(display) => xlib::set_opacity(display.as_ptr(), h.window, opacity),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Opacity with layered windows
+#[cfg(windows)]
+mod win32 {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongW, SetLayeredWindowAttributes, SetWindowLongW, GWL_EXSTYLE, LWA_ALPHA,
+        WS_EX_LAYERED,
+    };
+
+    /// Make the window a layered window and set its opacity
+    pub fn set_opacity(hwnd: isize, opacity: f32) -> bool {
+        unsafe {
+            let style = GetWindowLongW(hwnd, GWL_EXSTYLE);
+            if style & WS_EX_LAYERED as i32 == 0 {
+                SetWindowLongW(hwnd, GWL_EXSTYLE, style | WS_EX_LAYERED as i32);
+            }
+            SetLayeredWindowAttributes(hwnd, 0, (opacity * 255.0).round() as u8, LWA_ALPHA) != 0
+        }
+    }
+}
+
+/// Opacity with the alpha value of the NSWindow
+#[cfg(target_os = "macos")]
+mod appkit {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+
+    /// Set the alpha value of the window containing a view
+    pub fn set_opacity(ns_view: *mut std::ffi::c_void, opacity: f32) -> bool {
+        unsafe {
+            let view = &*(ns_view as *const AnyObject);
+            let window: *const AnyObject = msg_send![view, window];
+            if window.is_null() {
+                return false;
+            }
+            let _: () = msg_send![&*window, setAlphaValue: opacity as f64];
+        }
+        true
+    }
+}
+
+/// Opacity with the _NET_WM_WINDOW_OPACITY property, which compositing window managers use
+#[cfg(all(unix, not(target_os = "macos"), not(target_os = "android")))]
+mod xlib {
+    use x11_dl::xlib::{Display, PropModeReplace, Xlib, XA_CARDINAL};
+
+    lazy_static::lazy_static! {
+        /// The xlib functions, None when xlib cannot be loaded
+        static ref XLIB: Option<Xlib> = Xlib::open().ok();
+    }
+
+    /// Set the opacity property of a window
+    pub fn set_opacity(
+        display: *mut std::ffi::c_void,
+        window: std::os::raw::c_ulong,
+        opacity: f32,
+    ) -> bool {
+        let Some(xlib) = XLIB.as_ref() else {
+            return false;
+        };
+        let display = display as *mut Display;
+        // The property is a 32 bit cardinal, where the largest value is opaque
+        let value = (opacity as f64 * u32::MAX as f64) as std::os::raw::c_ulong;
+        unsafe {
+            let atom = (xlib.XInternAtom)(
+                display,
+                b"_NET_WM_WINDOW_OPACITY\0".as_ptr() as *const std::os::raw::c_char,
+                0,
+            );
+            (xlib.XChangeProperty)(
+                display,
+                window,
+                atom,
+                XA_CARDINAL,
+                32,
+                PropModeReplace,
+                &value as *const std::os::raw::c_ulong as *const u8,
+                1,
+            );
+            (xlib.XFlush)(display);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opacity_is_clamped() {
+        assert_eq!(clamp_opacity(0.7), 0.7);
+        assert_eq!(clamp_opacity(1.5), 1.0);
+        assert_eq!(clamp_opacity(-0.5), 0.0);
+        assert_eq!(clamp_opacity(f32::NAN), 1.0);
+    }
+}
@@ -0,0 +1,159 @@
+//! Contains the owner of windows, such as the main window that owns a tool window. The platform keeps an owned window above its owner
+//! and minimizes it along with the owner. The owner is set with the owner window on windows, with the transient hint on x11, and by
+//! making the window a child window of the owner on macos. Wayland and other platforms ignore the owner.
+//!
+//! On every platform, the `MultiWindow` asks the owned windows of a window to close when the window closes, see
+//! `NewWindowRequest::owned_by`.
+
+use raw_window_handle_6::{HasDisplayHandle, HasWindowHandle};
+
+/// Set the owner of a window, or remove its owner with None. Returns false when the platform cannot set the owner of the window, the
+/// window then behaves as an ordinary window.
+pub fn set_owner(
+    window: &async_winit::window::Window<async_winit::ThreadSafe>,
+    owner: Option<&async_winit::window::Window<async_winit::ThreadSafe>>,
+) -> bool {
+    let (Ok(w), Ok(d)) = (window.window_handle(), window.display_handle()) else {
+        return false;
+    };
+    let owner = match owner.map(|o| o.window_handle()) {
+        Some(Ok(o)) => Some(o.as_raw()),
+        Some(Err(_)) => return false,
+        None => None,
+    };
+    match (w.as_raw(), d.as_raw(), owner) {
+        #[cfg(windows)]
+        (raw_window_handle_6::RawWindowHandle::Win32(h), _, None) => {
+            win32::set_owner(h.hwnd.get(), 0)
+        }
+        #[cfg(windows)]
+        (
+            raw_window_handle_6::RawWindowHandle::Win32(h),
+            _,
+            Some(raw_window_handle_6::RawWindowHandle::Win32(o)),
+        ) => win32::set_owner(h.hwnd.get(), o.hwnd.get()),
+        #[cfg(target_os = "macos")]
+        (raw_window_handle_6::RawWindowHandle::AppKit(h), _, None) => {
+            appkit::set_owner(h.ns_view.as_ptr(), None)
+        }
+        #[cfg(target_os = "macos")]
+        (
+            raw_window_handle_6::RawWindowHandle::AppKit(h),
+            _,
+            Some(raw_window_handle_6::RawWindowHandle::AppKit(o)),
+        ) => appkit::set_owner(h.ns_view.as_ptr(), Some(o.ns_view.as_ptr())),
+        #[cfg(all(unix, not(target_os = "macos"), not(target_os = "android")))]
+        (
+            raw_window_handle_6::RawWindowHandle::Xlib(h),
+            raw_window_handle_6::RawDisplayHandle::Xlib(d),
+            None,
+        ) => match d.display {
+            Some(display) => xlib::set_owner(display.as_ptr(), h.window, None),
+            None => false,
+        },
+        #[cfg(all(unix, not(target_os = "macos"), not(target_os = "android")))]
+        (
+            raw_window_handle_6::RawWindowHandle::Xlib(h),
+            raw_window_handle_6::RawDisplayHandle::Xlib(d),
+            Some(raw_window_handle_6::RawWindowHandle::Xlib(o)),
+        ) => match d.display {
+            Some(display) => xlib::set_owner(display.as_ptr(), h.window, Some(o.window)),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Owners with the owner window handle
+#[cfg(windows)]
+mod win32 {
+    use windows_sys::Win32::UI::WindowsAndMessaging::GWLP_HWNDPARENT;
+
+    /// Set the owner of a window, 0 for no owner. For a top level window the parent index sets the owner, not the parent.
+    pub fn set_owner(hwnd: isize, owner: isize) -> bool {
+        unsafe {
+            #[cfg(target_pointer_width = "64")]
+            windows_sys::Win32::UI::WindowsAndMessaging::SetWindowLongPtrW(
+                hwnd,
+                GWLP_HWNDPARENT,
+                owner,
+            );
+            #[cfg(not(target_pointer_width = "64"))]
+            windows_sys::Win32::UI::WindowsAndMessaging::SetWindowLongW(
+                hwnd,
+                GWLP_HWNDPARENT,
+                owner as i32,
+            );
+        }
+        true
+    }
+}
+
+/// Owners with child windows of the NSWindow
+#[cfg(target_os = "macos")]
+mod appkit {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+
+    /// The ordering that keeps a child window above its parent
+    const NS_WINDOW_ABOVE: isize = 1;
+
+    /// Get the window containing a view, null when the view is not in a window
+    unsafe fn window_of(ns_view: *mut std::ffi::c_void) -> *const AnyObject {
+        let view = &*(ns_view as *const AnyObject);
+        msg_send![view, window]
+    }
+
+    /// Make the window containing a view a child window of the window containing another view, or of no window
+    pub fn set_owner(ns_view: *mut std::ffi::c_void, owner: Option<*mut std::ffi::c_void>) -> bool {
+        unsafe {
+            let window = window_of(ns_view);
+            if window.is_null() {
+                return false;
+            }
+            let parent: *const AnyObject = msg_send![&*window, parentWindow];
+            if !parent.is_null() {
+                let _: () = msg_send![&*parent, removeChildWindow: &*window];
+            }
+            if let Some(owner) = owner {
+                let owner = window_of(owner);
+                if owner.is_null() {
+                    return false;
+                }
+                let _: () = msg_send![&*owner, addChildWindow: &*window, ordered: NS_WINDOW_ABOVE];
+            }
+        }
+        true
+    }
+}
+
+/// Owners with the WM_TRANSIENT_FOR property, which window managers use to keep dialogs and tool windows with their main window
+#[cfg(all(unix, not(target_os = "macos"), not(target_os = "android")))]
+mod xlib {
+    use x11_dl::xlib::{Display, Xlib, XA_WM_TRANSIENT_FOR};
+
+    lazy_static::lazy_static! {
+        /// The xlib functions, None when xlib cannot be loaded
+        static ref XLIB: Option<Xlib> = Xlib::open().ok();
+    }
+
+    /// Set or remove the transient hint of a window
+    pub fn set_owner(
+        display: *mut std::ffi::c_void,
+        window: std::os::raw::c_ulong,
+        owner: Option<std::os::raw::c_ulong>,
+    ) -> bool {
+        let Some(xlib) = XLIB.as_ref() else {
+            return false;
+        };
+        let display = display as *mut Display;
+        unsafe {
+            match owner {
+                Some(owner) => (xlib.XSetTransientForHint)(display, window, owner),
+                None => (xlib.XDeleteProperty)(display, window, XA_WM_TRANSIENT_FOR),
+            };
+            (xlib.XFlush)(display);
+        }
+        true
+    }
+}
@@ -0,0 +1,342 @@
+//! Contains the storage of window geometry, used to reopen windows where they were when the application last closed them.
+//! The egui memory of windows is also stored, so that ui state such as collapsed headers survives a restart. The file is only read and
+//! written with the `persistence` feature, without it the geometry is remembered until the application exits.
+
+use std::collections::HashMap;
+#[cfg(feature = "persistence")]
+use std::convert::TryFrom;
+#[cfg(feature = "persistence")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::sync::LockRecover;
+
+/// The geometry of a window, in physical pixels
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowGeometry {
+    /// The position of the top left corner of the window, including decorations, if it was known
+    pub position: Option<(i32, i32)>,
+    /// The size of the window, not including decorations
+    pub size: (u32, u32),
+    /// True when the window was maximized
+    pub maximized: bool,
+    /// The name of the monitor containing the window, if it was known
+    pub monitor: Option<String>,
+}
+
+/// The area of a monitor, in physical pixels
+#[derive(Clone, Debug)]
+pub struct MonitorArea {
+    /// The position of the top left corner of the monitor
+    pub position: (i32, i32),
+    /// The size of the monitor
+    pub size: (u32, u32),
+    /// The name of the monitor, if it is known
+    pub name: Option<String>,
+}
+
+impl MonitorArea {
+    /// Returns true when a rectangle is at least partially on the monitor
+    fn overlaps(&self, position: (i32, i32), size: (u32, u32)) -> bool {
+        let (x, y) = (self.position.0 as i64, self.position.1 as i64);
+        let (px, py) = (position.0 as i64, position.1 as i64);
+        px < x + self.size.0 as i64
+            && px + size.0 as i64 > x
+            && py < y + self.size.1 as i64
+            && py + size.1 as i64 > y
+    }
+}
+
+impl WindowGeometry {
+    /// Adjust the geometry so that the window fits on the monitors that are available. A window that is not on any monitor is moved to the
+    /// monitor it was last on, or to the first monitor when that monitor is gone. A window larger than the monitor it is on is shrunk.
+    /// Nothing is changed when no monitors are known.
+    pub fn clamp(&mut self, monitors: &[MonitorArea]) {
+        let Some(home) = monitors
+            .iter()
+            .find(|m| m.name.is_some() && m.name == self.monitor)
+            .or_else(|| monitors.first())
+        else {
+            return;
+        };
+        let monitor = match self.position {
+            Some(p) => match monitors.iter().find(|m| m.overlaps(p, self.size)) {
+                Some(m) => m,
+                None => {
+                    self.position = Some(home.position);
+                    home
+                }
+            },
+            None => home,
+        };
+        self.size.0 = self.size.0.min(monitor.size.0);
+        self.size.1 = self.size.1.min(monitor.size.1);
+    }
+
+    /// Convert the geometry to json
+    #[cfg(feature = "persistence")]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "position": self.position.map(|(x, y)| [x, y]),
+            "size": [self.size.0, self.size.1],
+            "maximized": self.maximized,
+            "monitor": self.monitor,
+        })
+    }
+
+    /// Read the geometry from json, returning None if it is not valid
+    #[cfg(feature = "persistence")]
+    fn from_json(v: &serde_json::Value) -> Option<Self> {
+        /// Read a pair of numbers
+        fn pair(v: &serde_json::Value) -> Option<(i64, i64)> {
+            let a = v.as_array()?;
+            Some((a.first()?.as_i64()?, a.get(1)?.as_i64()?))
+        }
+        let size = pair(v.get("size")?)?;
+        Some(Self {
+            position: v
+                .get("position")
+                .and_then(pair)
+                .and_then(|(x, y)| Some((i32::try_from(x).ok()?, i32::try_from(y).ok()?))),
+            size: (u32::try_from(size.0).ok()?, u32::try_from(size.1).ok()?),
+            maximized: v.get("maximized")?.as_bool()?,
+            monitor: v
+                .get("monitor")
+                .and_then(|m| m.as_str())
+                .map(|m| m.to_string()),
+        })
+    }
+}
+
+/// The internals of the geometry store
+struct GeometryStoreInternal {
+    /// The file the geometry is saved to
+    #[cfg_attr(not(feature = "persistence"), allow(dead_code))]
+    path: PathBuf,
+    /// The geometry of windows, by persistence key
+    windows: HashMap<String, WindowGeometry>,
+    /// The egui memory of windows in ron format, by persistence key
+    #[cfg_attr(not(feature = "persistence"), allow(dead_code))]
+    memory: HashMap<String, String>,
+}
+
+/// Stores the geometry of windows in a json file. Cloning the store gives another handle to the same store.
+#[derive(Clone)]
+pub struct GeometryStore {
+    /// The shared internals
+    i: Arc<Mutex<GeometryStoreInternal>>,
+}
+
+impl GeometryStore {
+    /// Load the geometry of windows from a file. A missing file gives an empty store, an invalid file is logged and ignored. Without
+    /// the `persistence` feature the file is not read and the store starts empty.
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        #[cfg(feature = "persistence")]
+        let (windows, memory) = Self::read(&path);
+        #[cfg(not(feature = "persistence"))]
+        let (windows, memory) = {
+            crate::trace::warn(format_args!(
+                "Window geometry is not saved to {} without the persistence feature",
+                path.display()
+            ));
+            (HashMap::new(), HashMap::new())
+        };
+        Self {
+            i: Arc::new(Mutex::new(GeometryStoreInternal {
+                path,
+                windows,
+                memory,
+            })),
+        }
+    }
+
+    /// Read the geometry and egui memory of windows from a file
+    #[cfg(feature = "persistence")]
+    fn read(path: &Path) -> (HashMap<String, WindowGeometry>, HashMap<String, String>) {
+        let saved = match std::fs::read(path) {
+            Ok(data) => match serde_json::from_slice::<serde_json::Value>(&data) {
+                Ok(serde_json::Value::Object(o)) => o,
+                _ => {
+                    crate::trace::warn(format_args!(
+                        "Ignoring invalid window geometry in {}",
+                        path.display()
+                    ));
+                    serde_json::Map::new()
+                }
+            },
+            Err(_) => serde_json::Map::new(),
+        };
+        let windows = saved
+            .iter()
+            .filter_map(|(k, v)| Some((k.clone(), WindowGeometry::from_json(v)?)))
+            .collect();
+        let memory = saved
+            .iter()
+            .filter_map(|(k, v)| Some((k.clone(), v.get("memory")?.as_str()?.to_string())))
+            .collect();
+        (windows, memory)
+    }
+
+    /// Get the saved geometry of a window
+    pub fn get(&self, key: &str) -> Option<WindowGeometry> {
+        self.i.lock_recover().windows.get(key).cloned()
+    }
+
+    /// Save the geometry of a window, writing the file. Errors writing the file are logged. Without the `persistence` feature the
+    /// file is not written.
+    pub fn set(&self, key: &str, geometry: WindowGeometry) {
+        let mut i = self.i.lock_recover();
+        i.windows.insert(key.to_string(), geometry);
+        #[cfg(feature = "persistence")]
+        i.write();
+    }
+
+    /// Remember the egui memory of a window. The memory is written to the file with the next call to [Self::set].
+    /// Does nothing without the `persistence` feature.
+    pub fn set_memory(&self, key: &str, ctx: &egui::Context) {
+        #[cfg(feature = "persistence")]
+        match ctx.memory(ron::to_string) {
+            Ok(m) => {
+                self.i.lock_recover().memory.insert(key.to_string(), m);
+            }
+            Err(e) => crate::trace::error(format_args!(
+                "Unable to save the egui memory of a window: {}",
+                e
+            )),
+        }
+        #[cfg(not(feature = "persistence"))]
+        let _ = (key, ctx);
+    }
+
+    /// Place a window at its saved geometry, moved onto a monitor when the monitor it was on is no longer connected. The builder is
+    /// returned unchanged when no geometry is saved for the window.
+    pub async fn place(
+        &self,
+        key: &str,
+        builder: async_winit::window::WindowBuilder,
+        event_loop: &async_winit::event_loop::EventLoopWindowTarget<async_winit::ThreadSafe>,
+    ) -> async_winit::window::WindowBuilder {
+        let Some(mut geometry) = self.get(key) else {
+            return builder;
+        };
+        let monitors: Vec<MonitorArea> = event_loop
+            .available_monitors()
+            .await
+            .map(|m| MonitorArea {
+                position: (m.position().x, m.position().y),
+                size: (m.size().width, m.size().height),
+                name: m.name(),
+            })
+            .collect();
+        geometry.clamp(&monitors);
+        let builder = builder
+            .with_inner_size(async_winit::dpi::PhysicalSize::new(
+                geometry.size.0,
+                geometry.size.1,
+            ))
+            .with_maximized(geometry.maximized);
+        match geometry.position {
+            Some((x, y)) => builder.with_position(async_winit::dpi::PhysicalPosition::new(x, y)),
+            None => builder,
+        }
+    }
+
+    /// Save the geometry and the egui memory of a window that is closing, writing the file
+    pub async fn save_window(
+        &self,
+        key: &str,
+        ctx: Option<&egui::Context>,
+        window: &async_winit::window::Window<async_winit::ThreadSafe>,
+    ) {
+        if let Some(ctx) = ctx {
+            self.set_memory(key, ctx);
+        }
+        let geometry = WindowGeometry {
+            position: window.outer_position().await.ok().map(|p| (p.x, p.y)),
+            size: {
+                let s = window.inner_size().await;
+                (s.width, s.height)
+            },
+            maximized: window.is_maximized().await,
+            monitor: window.current_monitor().await.and_then(|m| m.name()),
+        };
+        self.set(key, geometry);
+    }
+
+    /// Restore the saved egui memory of a window. Memory that cannot be read, such as memory saved by a different version of egui,
+    /// is discarded. Does nothing without the `persistence` feature.
+    pub fn restore_memory(&self, key: &str, ctx: &egui::Context) {
+        #[cfg(feature = "persistence")]
+        {
+            let saved = self.i.lock_recover().memory.get(key).cloned();
+            if let Some(m) = saved.and_then(|m| ron::from_str::<egui::Memory>(&m).ok()) {
+                ctx.memory_mut(|mem| *mem = m);
+            }
+        }
+        #[cfg(not(feature = "persistence"))]
+        let _ = (key, ctx);
+    }
+}
+
+#[cfg(feature = "persistence")]
+impl GeometryStoreInternal {
+    /// Write the geometry and egui memory of windows to the file. Errors are logged.
+    fn write(&self) {
+        let json = serde_json::Value::Object(
+            self.windows
+                .iter()
+                .map(|(k, g)| {
+                    let mut v = g.to_json();
+                    if let Some(m) = self.memory.get(k) {
+                        v["memory"] = serde_json::Value::String(m.clone());
+                    }
+                    (k.clone(), v)
+                })
+                .collect(),
+        );
+        if let Err(e) = std::fs::write(&self.path, json.to_string()) {
+            crate::trace::error(format_args!(
+                "Unable to save window geometry to {}: {}",
+                self.path.display(),
+                e
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offscreen_window_is_moved_to_a_monitor() {
+        let monitors = [MonitorArea {
+            position: (0, 0),
+            size: (1920, 1080),
+            name: None,
+        }];
+        let mut g = WindowGeometry {
+            position: Some((3000, 200)),
+            size: (2500, 600),
+            maximized: false,
+            monitor: None,
+        };
+        g.clamp(&monitors);
+        assert_eq!(g.position, Some((0, 0)));
+        assert_eq!(g.size, (1920, 600));
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn geometry_survives_json() {
+        let g = WindowGeometry {
+            position: Some((-10, 20)),
+            size: (640, 480),
+            maximized: true,
+            monitor: Some("DP-1".to_string()),
+        };
+        assert_eq!(WindowGeometry::from_json(&g.to_json()), Some(g));
+    }
+}
@@ -0,0 +1,262 @@
+//! Contains the power source of the computer and the power profiles that limit how often windows repaint. The power source is read from
+//! sysfs on linux, from the system power status on windows, and from `pmset` on macos. Computers without a battery, such as desktops,
+//! and other platforms report [PowerSource::Unknown] and never report a change.
+
+use crate::tracked_window::RepaintThrottle;
+
+/// Where the computer gets its power from
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PowerSource {
+    /// The computer is plugged in
+    Ac,
+    /// The computer runs on its battery
+    Battery,
+    /// The computer has no battery, or the power source cannot be read on this platform
+    Unknown,
+}
+
+/// How much the repaints of windows are limited to save power
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PowerProfile {
+    /// Windows repaint as often as they ask, even in the background
+    Performance,
+    /// Windows in the background repaint at most 10 times per second. These are the limits a `MultiWindow` starts with.
+    #[default]
+    Balanced,
+    /// Windows in the background repaint at most twice per second, and every window at most 30 times per second, which also slows
+    /// down windows that repaint continuously
+    PowerSaver,
+    /// [Self::PowerSaver] while the computer runs on its battery, [Self::Balanced] otherwise. The profile changes along with the power
+    /// source.
+    Automatic,
+}
+
+impl PowerProfile {
+    /// The profile used on a power source, which is only different from this profile for [Self::Automatic]
+    pub fn resolve(self, source: PowerSource) -> Self {
+        match (self, source) {
+            (Self::Automatic, PowerSource::Battery) => Self::PowerSaver,
+            (Self::Automatic, _) => Self::Balanced,
+            (p, _) => p,
+        }
+    }
+
+    /// The repaint limit of windows in the background
+    pub fn background_throttle(self) -> RepaintThrottle {
+        match self {
+            Self::Performance => RepaintThrottle::Unlimited,
+            Self::Balanced | Self::Automatic => RepaintThrottle::default(),
+            Self::PowerSaver => RepaintThrottle::MaxFps(std::num::NonZeroU32::new(2).unwrap()),
+        }
+    }
+
+    /// The repaint limit of every window, including the window with focus
+    pub fn frame_limit(self) -> RepaintThrottle {
+        match self {
+            Self::PowerSaver => RepaintThrottle::MaxFps(std::num::NonZeroU32::new(30).unwrap()),
+            _ => RepaintThrottle::Unlimited,
+        }
+    }
+}
+
+/// Read the current power source of the computer. This can wait for a short time on macos, where another program is run.
+pub fn power_source() -> PowerSource {
+    #[cfg(target_os = "linux")]
+    {
+        sysfs_source(std::path::Path::new("/sys/class/power_supply"))
+    }
+    #[cfg(windows)]
+    {
+        win32::power_source()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        match std::process::Command::new("pmset")
+            .args(["-g", "batt"])
+            .output()
+        {
+            Ok(output) => pmset_source(&String::from_utf8_lossy(&output.stdout)),
+            Err(_) => PowerSource::Unknown,
+        }
+    }
+    #[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+    {
+        PowerSource::Unknown
+    }
+}
+
+/// Read the power source from the power supplies listed in sysfs. Batteries of devices, such as a wireless mouse, are ignored.
+#[cfg(target_os = "linux")]
+fn sysfs_source(dir: &std::path::Path) -> PowerSource {
+    let read = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path)
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default()
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return PowerSource::Unknown;
+    };
+    let (mut battery, mut online, mut discharging) = (false, false, false);
+    for entry in entries.flatten() {
+        let supply = entry.path();
+        if read(supply.join("type")) == "Battery" {
+            if read(supply.join("scope")) != "Device" {
+                battery = true;
+                discharging |= read(supply.join("status")) == "Discharging";
+            }
+        } else {
+            online |= read(supply.join("online")) == "1";
+        }
+    }
+    match (battery, online, discharging) {
+        (false, _, _) => PowerSource::Unknown,
+        (true, true, _) => PowerSource::Ac,
+        (true, false, true) => PowerSource::Battery,
+        (true, false, false) => PowerSource::Ac,
+    }
+}
+
+/// Read the power source from the output of `pmset -g batt`
+#[cfg(target_os = "macos")]
+fn pmset_source(output: &str) -> PowerSource {
+    if !output.contains("InternalBattery") {
+        PowerSource::Unknown
+    } else if output.contains("'Battery Power'") {
+        PowerSource::Battery
+    } else if output.contains("'AC Power'") {
+        PowerSource::Ac
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+/// The power source from the system power status
+#[cfg(windows)]
+mod win32 {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    /// The battery flag reported when the computer has no battery
+    const NO_BATTERY: u8 = 128;
+
+    /// Read the power source from the system power status
+    pub fn power_source() -> super::PowerSource {
+        let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+        if unsafe { GetSystemPowerStatus(&mut status) } == 0 || status.BatteryFlag & NO_BATTERY != 0
+        {
+            return super::PowerSource::Unknown;
+        }
+        match status.ACLineStatus {
+            0 => super::PowerSource::Battery,
+            1 => super::PowerSource::Ac,
+            _ => super::PowerSource::Unknown,
+        }
+    }
+}
+
+/// Watches the power source of the computer for changes
+pub struct PowerMonitor {
+    /// The last power source that was read
+    source: PowerSource,
+    /// When the power source is read next. This is kept between calls to [Self::changed], so that a call that is cancelled does not
+    /// delay the next read.
+    next_read: std::time::Instant,
+}
+
+impl PowerMonitor {
+    /// How often the power source is read
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+    /// Construct a monitor, reading the current power source
+    pub fn new() -> Self {
+        Self {
+            source: power_source(),
+            next_read: std::time::Instant::now() + Self::POLL_INTERVAL,
+        }
+    }
+
+    /// The last power source that was read
+    pub fn source(&self) -> PowerSource {
+        self.source
+    }
+
+    /// Wait until the power source changes, returning the new power source. A computer that reported [PowerSource::Unknown] when the
+    /// monitor was constructed is not read again, so this never returns.
+    pub async fn changed(&mut self) -> PowerSource {
+        if self.source == PowerSource::Unknown {
+            return std::future::pending().await;
+        }
+        loop {
+            async_winit::Timer::after(
+                self.next_read
+                    .saturating_duration_since(std::time::Instant::now()),
+            )
+            .await;
+            self.next_read = std::time::Instant::now() + Self::POLL_INTERVAL;
+            let source = blocking::unblock(power_source).await;
+            if source != self.source {
+                self.source = source;
+                return source;
+            }
+        }
+    }
+}
+
+impl Default for PowerMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn automatic_profile_follows_the_power_source() {
+        let auto = PowerProfile::Automatic;
+        assert_eq!(auto.resolve(PowerSource::Battery), PowerProfile::PowerSaver);
+        assert_eq!(auto.resolve(PowerSource::Ac), PowerProfile::Balanced);
+        assert_eq!(auto.resolve(PowerSource::Unknown), PowerProfile::Balanced);
+        assert_eq!(
+            PowerProfile::Performance.resolve(PowerSource::Battery),
+            PowerProfile::Performance
+        );
+        assert_eq!(
+            PowerProfile::Balanced.frame_limit(),
+            RepaintThrottle::Unlimited
+        );
+        assert_ne!(
+            PowerProfile::PowerSaver.frame_limit(),
+            RepaintThrottle::Unlimited
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn sysfs_supplies_give_the_power_source() {
+        let dir = std::env::temp_dir().join(format!("egui_multiwin_power_{}", std::process::id()));
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            let path = dir.join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            for (file, contents) in files {
+                std::fs::write(path.join(file), format!("{}\n", contents)).unwrap();
+            }
+        };
+        supply("AC", &[("type", "Mains"), ("online", "0")]);
+        supply(
+            "hidpp_battery_0",
+            &[
+                ("type", "Battery"),
+                ("scope", "Device"),
+                ("status", "Discharging"),
+            ],
+        );
+        assert_eq!(sysfs_source(&dir), PowerSource::Unknown);
+        supply("BAT0", &[("type", "Battery"), ("status", "Discharging")]);
+        assert_eq!(sysfs_source(&dir), PowerSource::Battery);
+        supply("AC", &[("type", "Mains"), ("online", "1")]);
+        assert_eq!(sysfs_source(&dir), PowerSource::Ac);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(sysfs_source(&dir), PowerSource::Unknown);
+    }
+}
@@ -0,0 +1,32 @@
+//! Contains the profiling of windows with puffin. With the `profiling` feature, the frame of every window is recorded as a puffin frame
+//! with scopes for the steps of drawing it, which show up in puffin_viewer next to the scopes of the application. The application turns
+//! the scopes on with `puffin::set_scopes_on`. Without the feature, the scopes compile to nothing.
+
+#[cfg(feature = "profiling")]
+pub use puffin;
+
+/// Record a profiling scope until the end of the enclosing block, with an optional string of data such as the id of a window
+#[cfg(feature = "profiling")]
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        $crate::profile::puffin::profile_scope!($name);
+    };
+    ($name:expr, $data:expr) => {
+        $crate::profile::puffin::profile_scope!($name, $data);
+    };
+}
+
+/// Record a profiling scope until the end of the enclosing block, with an optional string of data such as the id of a window
+#[cfg(not(feature = "profiling"))]
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {};
+    ($name:expr, $data:expr) => {};
+}
+
+/// Start a new profiling frame, before a window draws its frame
+pub fn new_frame() {
+    #[cfg(feature = "profiling")]
+    puffin::GlobalProfiler::lock().new_frame();
+}
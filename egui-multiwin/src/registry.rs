@@ -0,0 +1,433 @@
+//! Contains the registry of the windows that are currently open in the application
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use raw_window_handle_5::{HasRawDisplayHandle, HasRawWindowHandle};
+use raw_window_handle_6::{HasDisplayHandle, HasWindowHandle};
+
+use crate::sync::{LockRecover, Notify};
+
+/// The id of a window. Ids are given out in increasing order starting at 1 and are never reused while the program runs, so an id of a
+/// closed window never refers to a window opened later.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WindowId(u64);
+
+/// The id given to the next window
+static NEXT_WINDOW_ID: AtomicU64 = AtomicU64::new(1);
+
+impl WindowId {
+    /// Allocate the id for a new window
+    pub fn allocate() -> Self {
+        Self(NEXT_WINDOW_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Get the number of the id
+    pub fn get(self) -> u64 {
+        self.0
+    }
+
+    /// The id used to store the window id in the egui context of the window
+    fn context_id() -> egui::Id {
+        egui::Id::new("egui_multiwin_window_id")
+    }
+
+    /// Get the id of the window that owns an egui context, such as to open a modal window for the window from its redraw. Returns None
+    /// for a context not owned by a window of the application.
+    pub fn of(ctx: &egui::Context) -> Option<Self> {
+        ctx.data(|d| d.get_temp(Self::context_id()))
+    }
+
+    /// Store the id in the egui context of its window, for [Self::of]. Called by the `MultiWindow` for every window.
+    pub fn attach(self, ctx: &egui::Context) {
+        ctx.data_mut(|d| d.insert_temp(Self::context_id(), self));
+    }
+}
+
+impl std::fmt::Display for WindowId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Information about an open window
+#[derive(Clone)]
+pub struct WindowInfo {
+    /// The window handle
+    pub window: Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
+    /// When the window last drew a frame
+    last_frame: Arc<Mutex<Option<std::time::Instant>>>,
+    /// Closed when the window is removed from the registry
+    open: Notify,
+    /// The egui context of the window, once egui is running in the window
+    context: Arc<Mutex<Option<egui::Context>>>,
+    /// Asks the window to close
+    close_request: Notify,
+}
+
+impl WindowInfo {
+    /// Construct the information for a window
+    pub fn new(window: Arc<async_winit::window::Window<async_winit::ThreadSafe>>) -> Self {
+        Self {
+            window,
+            last_frame: Arc::new(Mutex::new(None)),
+            open: Notify::new(),
+            context: Arc::new(Mutex::new(None)),
+            close_request: Notify::new(),
+        }
+    }
+
+    /// Use a notification to ask the window to close, see [Self::request_close]
+    pub fn with_close_request(mut self, close_request: Notify) -> Self {
+        self.close_request = close_request;
+        self
+    }
+
+    /// Ask the window to close, the same as when its close button is clicked. The window can refuse with `can_quit`.
+    pub fn request_close(&self) {
+        self.close_request.notify();
+    }
+
+    /// Get the time when the window last drew a frame, None before the first frame
+    pub fn last_frame(&self) -> Option<std::time::Instant> {
+        *self.last_frame.lock_recover()
+    }
+
+    /// Record that the window drew a frame
+    pub fn frame_drawn(&self, at: std::time::Instant) {
+        *self.last_frame.lock_recover() = Some(at);
+    }
+
+    /// Get the egui context of the window, such as to show notifications in the window from a background task. This is None until egui
+    /// is running in the window, and after the window closes. Requesting a repaint of the context wakes the window, even from another
+    /// thread. A context kept after the window closes is inert, nothing shows what is added to it.
+    pub fn context(&self) -> Option<egui::Context> {
+        self.context.lock_recover().clone()
+    }
+
+    /// Publish the egui context of the window
+    pub fn set_context(&self, ctx: &egui::Context) {
+        *self.context.lock_recover() = Some(ctx.clone());
+    }
+
+    /// Wait until the window closes
+    pub async fn closed(&self) {
+        // Nothing notifies, the wait only ends when the window is removed from the registry
+        while self.open.wait().await {}
+    }
+
+    /// Get the raw window handle and raw display handle of the window, using version 0.5 of raw-window-handle.
+    /// The handles are only valid while the window is open. Do not keep them after the window has closed.
+    pub fn raw_handles(
+        &self,
+    ) -> (
+        raw_window_handle_5::RawWindowHandle,
+        raw_window_handle_5::RawDisplayHandle,
+    ) {
+        (
+            self.window.raw_window_handle(),
+            self.window.raw_display_handle(),
+        )
+    }
+
+    /// Get the raw window handle and raw display handle of the window, using version 0.6 of raw-window-handle.
+    /// Returns None if the platform cannot currently provide the handles, such as on android while the application is suspended.
+    /// The handles are only valid while the window is open. Do not keep them after the window has closed.
+    pub fn raw_handles_6(
+        &self,
+    ) -> Option<(
+        raw_window_handle_6::RawWindowHandle,
+        raw_window_handle_6::RawDisplayHandle,
+    )> {
+        let w = self.window.window_handle().ok()?;
+        let d = self.window.display_handle().ok()?;
+        Some((w.as_raw(), d.as_raw()))
+    }
+
+    /// Returns true when the platform can change the opacity of the whole window, see
+    /// [crate::tracked_window::WindowCommand::SetOpacity]. An application can use this to hide its opacity setting.
+    pub fn opacity_supported(&self) -> bool {
+        crate::opacity::supported(&self.window)
+    }
+}
+
+/// The future that runs a window, in the future sets of the event loop
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WindowTask {
+    /// The id of the future in its set
+    pub id: u32,
+    /// True when the future is in the set of root windows
+    pub root: bool,
+}
+
+/// The internals of the window registry
+#[derive(Default)]
+struct WindowRegistryInternal {
+    /// The open windows, by window id
+    windows: HashMap<WindowId, WindowInfo>,
+    /// The id of the window that currently has focus
+    focused: Option<WindowId>,
+    /// The futures running the windows, by window id
+    tasks: HashMap<WindowId, WindowTask>,
+    /// The window that started the current drag between windows, and its payload
+    drag: Option<(WindowId, crate::drag::DragPayload)>,
+    /// The payloads dropped onto windows, waiting for the next frame of the window
+    drops: HashMap<WindowId, crate::drag::DroppedPayload>,
+    /// The owner of each owned window
+    owners: HashMap<WindowId, WindowId>,
+}
+
+/// The registry of open windows, shared among all windows of the application. Cloning the registry gives another handle to the same registry.
+#[derive(Clone, Default)]
+pub struct WindowRegistry {
+    /// The shared internals
+    i: Arc<Mutex<WindowRegistryInternal>>,
+}
+
+impl WindowRegistry {
+    /// Construct a new, empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a window to the registry
+    pub fn insert(&self, id: WindowId, info: WindowInfo) {
+        self.i.lock_recover().windows.insert(id, info);
+    }
+
+    /// Remove a window from the registry
+    pub fn remove(&self, id: WindowId) {
+        let mut i = self.i.lock_recover();
+        if let Some(info) = i.windows.remove(&id) {
+            info.open.close();
+            info.context.lock_recover().take();
+        }
+        i.tasks.remove(&id);
+        i.drops.remove(&id);
+        if i.focused == Some(id) {
+            i.focused = None;
+        }
+        if i.drag.as_ref().map(|(source, _)| *source) == Some(id) {
+            i.drag = None;
+        }
+        i.owners.retain(|owned, owner| *owned != id && *owner != id);
+    }
+
+    /// Get information about a window, if it is still open
+    pub fn get(&self, id: WindowId) -> Option<WindowInfo> {
+        self.i.lock_recover().windows.get(&id).cloned()
+    }
+
+    /// Get the egui context of a window, see [WindowInfo::context]. Returns None if the window is not open, or egui is not running in it yet.
+    pub fn context(&self, id: WindowId) -> Option<egui::Context> {
+        self.get(id).and_then(|w| w.context())
+    }
+
+    /// Get the raw window and display handles (raw-window-handle 0.5) of a window. Returns None if the window is not open.
+    pub fn raw_handles(
+        &self,
+        id: WindowId,
+    ) -> Option<(
+        raw_window_handle_5::RawWindowHandle,
+        raw_window_handle_5::RawDisplayHandle,
+    )> {
+        self.get(id).map(|w| w.raw_handles())
+    }
+
+    /// Get the raw window and display handles (raw-window-handle 0.6) of a window. Returns None if the window is not open
+    /// or if the platform cannot currently provide the handles.
+    pub fn raw_handles_6(
+        &self,
+        id: WindowId,
+    ) -> Option<(
+        raw_window_handle_6::RawWindowHandle,
+        raw_window_handle_6::RawDisplayHandle,
+    )> {
+        self.get(id).and_then(|w| w.raw_handles_6())
+    }
+
+    /// Record the future that runs a window
+    pub fn set_task(&self, id: WindowId, task: WindowTask) {
+        self.i.lock_recover().tasks.insert(id, task);
+    }
+
+    /// Get the future that runs a window, if the window is still running
+    pub fn task(&self, id: WindowId) -> Option<WindowTask> {
+        self.i.lock_recover().tasks.get(&id).copied()
+    }
+
+    /// Get the number of running root windows
+    pub fn roots(&self) -> usize {
+        self.i
+            .lock_recover()
+            .tasks
+            .values()
+            .filter(|t| t.root)
+            .count()
+    }
+
+    /// Get the monitors that are available and the index of the monitor a window is on. Returns None if the window is not open.
+    pub async fn window_monitors(
+        &self,
+        id: WindowId,
+    ) -> Option<(Vec<crate::monitor::MonitorInfo>, Option<usize>)> {
+        let w = self.get(id)?;
+        Some(crate::monitor::window_monitors(&w.window).await)
+    }
+
+    /// Returns true when the platform can change the opacity of a window. Returns false if the window is not open.
+    pub fn opacity_supported(&self, id: WindowId) -> bool {
+        self.get(id).map(|w| w.opacity_supported()).unwrap_or(false)
+    }
+
+    /// Record that a window is owned by another window, see [crate::owner]
+    pub fn set_owner(&self, id: WindowId, owner: WindowId) {
+        self.i.lock_recover().owners.insert(id, owner);
+    }
+
+    /// Get the owner of a window, None for a window without an owner
+    pub fn owner(&self, id: WindowId) -> Option<WindowId> {
+        self.i.lock_recover().owners.get(&id).copied()
+    }
+
+    /// Get the windows owned by a window
+    pub fn owned(&self, owner: WindowId) -> Vec<WindowId> {
+        let i = self.i.lock_recover();
+        let mut owned: Vec<WindowId> = i
+            .owners
+            .iter()
+            .filter(|(_, o)| **o == owner)
+            .map(|(id, _)| *id)
+            .collect();
+        owned.sort();
+        owned
+    }
+
+    /// Get the ids of all open windows
+    pub fn ids(&self) -> Vec<WindowId> {
+        self.i.lock_recover().windows.keys().copied().collect()
+    }
+
+    /// Get the number of open windows
+    pub fn len(&self) -> usize {
+        self.i.lock_recover().windows.len()
+    }
+
+    /// Returns true when no windows are open
+    pub fn is_empty(&self) -> bool {
+        self.i.lock_recover().windows.is_empty()
+    }
+
+    /// Get the id of the window that currently has focus, if any
+    pub fn focused(&self) -> Option<WindowId> {
+        self.i.lock_recover().focused
+    }
+
+    /// Record a focus change for a window
+    pub fn set_focused(&self, id: WindowId, focused: bool) {
+        let mut i = self.i.lock_recover();
+        if focused {
+            i.focused = Some(id);
+        } else if i.focused == Some(id) {
+            i.focused = None;
+        }
+    }
+
+    /// Start dragging a payload out of a window, replacing any other drag. See [crate::drag].
+    pub fn start_drag(&self, source: WindowId, payload: crate::drag::DragPayload) {
+        self.i.lock_recover().drag = Some((source, payload));
+    }
+
+    /// Get the window that started the current drag and its payload
+    pub fn drag(&self) -> Option<(WindowId, crate::drag::DragPayload)> {
+        self.i.lock_recover().drag.clone()
+    }
+
+    /// Stop the current drag without dropping its payload
+    pub fn cancel_drag(&self) {
+        self.i.lock_recover().drag = None;
+    }
+
+    /// End the current drag by dropping its payload onto the window at a position on the screen in physical pixels, which receives it
+    /// in its next frame. Returns the id of the window, or None when there is no drag or no window at the position. The z order of the
+    /// windows is not known, so when windows overlap, the window opened last is chosen.
+    pub async fn drop_at(
+        &self,
+        position: async_winit::dpi::PhysicalPosition<i32>,
+    ) -> Option<WindowId> {
+        let (source, payload) = self.i.lock_recover().drag.take()?;
+        let mut windows: Vec<_> = self
+            .i
+            .lock_recover()
+            .windows
+            .iter()
+            .map(|(id, w)| (*id, w.window.clone()))
+            .collect();
+        windows.sort_by_key(|(id, _)| std::cmp::Reverse(*id));
+        for (id, window) in windows {
+            if window.is_minimized().await == Some(true) {
+                continue;
+            }
+            let Ok(pos) = window.inner_position().await else {
+                continue;
+            };
+            let size = window.inner_size().await;
+            let inside = (pos.x..pos.x + size.width as i32).contains(&position.x)
+                && (pos.y..pos.y + size.height as i32).contains(&position.y);
+            if inside {
+                let dropped = crate::drag::DroppedPayload {
+                    source,
+                    payload,
+                    position,
+                };
+                self.i.lock_recover().drops.insert(id, dropped);
+                window.request_redraw();
+                return Some(id);
+            }
+        }
+        None
+    }
+
+    /// Take the payload dropped onto a window
+    pub fn take_drop(&self, id: WindowId) -> Option<crate::drag::DroppedPayload> {
+        self.i.lock_recover().drops.remove(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_ids_increase() {
+        let a = WindowId::allocate();
+        let b = WindowId::allocate();
+        assert!(a.get() >= 1);
+        assert!(b > a);
+        assert_eq!(b.to_string(), b.get().to_string());
+        let ctx = egui::Context::default();
+        assert_eq!(WindowId::of(&ctx), None);
+        b.attach(&ctx);
+        assert_eq!(WindowId::of(&ctx), Some(b));
+    }
+
+    #[test]
+    fn owned_windows_are_forgotten_with_their_owner() {
+        let registry = WindowRegistry::new();
+        let (main, tools, palette) = (
+            WindowId::allocate(),
+            WindowId::allocate(),
+            WindowId::allocate(),
+        );
+        registry.set_owner(tools, main);
+        registry.set_owner(palette, main);
+        assert_eq!(registry.owner(tools), Some(main));
+        assert_eq!(registry.owned(main), [tools, palette]);
+        registry.remove(tools);
+        assert_eq!(registry.owner(tools), None);
+        assert_eq!(registry.owned(main), [palette]);
+        registry.remove(main);
+        assert_eq!(registry.owner(palette), None);
+    }
+}
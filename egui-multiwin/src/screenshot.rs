@@ -0,0 +1,161 @@
+//! Contains the capture of the rendered output of windows, for copying a window as an image or for visual tests. A capture is requested
+//! with [crate::tracked_window::WindowCommand::CaptureFrame], or from egui with [egui::ViewportCommand::Screenshot], and is taken from
+//! the next frame after it is drawn, including the custom opengl rendering, and before it is shown.
+
+use std::sync::Arc;
+
+use egui_glow_async::glow;
+
+/// Read the contents of the default framebuffer of a window. The opengl context of the window must be current, with the frame drawn
+/// but not yet swapped. The pixels are read as they are stored, which is what the window shows, so srgb conversion is disabled while
+/// reading. The platform ignores the alpha of an opaque window, so the image is opaque unless the window is transparent.
+pub fn read_framebuffer(
+    gl: &glow::Context,
+    size: (u32, u32),
+    transparent: bool,
+) -> egui::ColorImage {
+    use glow::HasContext;
+    let mut rgba = vec![0u8; size.0 as usize * size.1 as usize * 4];
+    unsafe {
+        gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        gl.disable(glow::FRAMEBUFFER_SRGB);
+        gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+        gl.read_pixels(
+            0,
+            0,
+            size.0 as i32,
+            size.1 as i32,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelPackData::Slice(&mut rgba),
+        );
+    }
+    image_from_rows(&rgba, size, transparent)
+}
+
+/// Convert premultiplied rgba pixels, stored bottom row first like opengl stores them, into an image with the top row first
+fn image_from_rows(rgba: &[u8], size: (u32, u32), transparent: bool) -> egui::ColorImage {
+    let row = size.0 as usize * 4;
+    let pixels = if row == 0 {
+        Vec::new()
+    } else {
+        rgba.chunks_exact(row)
+            .rev()
+            .flat_map(|r| r.chunks_exact(4))
+            .map(|p| {
+                let alpha = if transparent { p[3] } else { 255 };
+                egui::Color32::from_rgba_premultiplied(p[0], p[1], p[2], alpha)
+            })
+            .collect()
+    };
+    egui::ColorImage {
+        size: [size.0 as usize, size.1 as usize],
+        pixels,
+    }
+}
+
+/// Save a captured image as a png file
+#[cfg(feature = "image")]
+pub fn save_png(
+    image: &egui::ColorImage,
+    path: impl AsRef<std::path::Path>,
+) -> Result<(), image::ImageError> {
+    let rgba = image
+        .pixels
+        .iter()
+        .flat_map(|p| p.to_srgba_unmultiplied())
+        .collect();
+    let buffer = image::RgbaImage::from_raw(image.size[0] as u32, image.size[1] as u32, rgba)
+        .ok_or_else(|| {
+            image::ImageError::Parameter(image::error::ParameterError::from_kind(
+                image::error::ParameterErrorKind::DimensionMismatch,
+            ))
+        })?;
+    buffer.save_with_format(path, image::ImageFormat::Png)
+}
+
+/// The captures waiting for the next frame of a window
+#[derive(Default)]
+pub struct CaptureState {
+    /// The channels waiting for the next frame
+    senders: Vec<async_channel::Sender<Arc<egui::ColorImage>>>,
+    /// True when egui asked for a screenshot of the next frame
+    egui_requested: bool,
+    /// The screenshot to give to egui at the start of the next frame
+    egui_pending: Option<Arc<egui::ColorImage>>,
+}
+
+impl CaptureState {
+    /// Capture the next frame, sending it into a channel
+    pub fn request(&mut self, sender: async_channel::Sender<Arc<egui::ColorImage>>) {
+        self.senders.push(sender);
+    }
+
+    /// Capture the next frame for egui, which receives it as an [egui::Event::Screenshot]
+    pub fn request_egui(&mut self) {
+        self.egui_requested = true;
+    }
+
+    /// Returns true when the next frame is captured. A captured frame is always drawn, even when it looks the same as the last frame.
+    pub fn wanted(&self) -> bool {
+        !self.senders.is_empty() || self.egui_requested
+    }
+
+    /// Deliver a captured frame to everything that asked for it. This never waits, a channel that is full or closed misses the frame.
+    pub fn deliver(&mut self, image: egui::ColorImage) {
+        let image = Arc::new(image);
+        for s in self.senders.drain(..) {
+            let _ = s.try_send(image.clone());
+        }
+        if self.egui_requested {
+            self.egui_requested = false;
+            self.egui_pending = Some(image);
+        }
+    }
+
+    /// Returns true when a screenshot is waiting to be given to egui, so another frame is needed
+    pub fn egui_pending(&self) -> bool {
+        self.egui_pending.is_some()
+    }
+
+    /// Place the screenshot requested by egui into the input for egui
+    pub fn apply(&mut self, input: &mut egui::RawInput) {
+        if let Some(image) = self.egui_pending.take() {
+            input.events.push(egui::Event::Screenshot {
+                viewport_id: input.viewport_id,
+                image,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_flipped() {
+        let rgba = [1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0];
+        let image = image_from_rows(&rgba, (2, 2), false);
+        let reds: Vec<_> = image.pixels.iter().map(|p| p.r()).collect();
+        assert_eq!(reds, [3, 4, 1, 2]);
+        assert!(image.pixels.iter().all(|p| p.a() == 255));
+        assert!(image_from_rows(&[], (0, 4), true).pixels.is_empty());
+    }
+
+    #[test]
+    fn capture_is_delivered_once() {
+        let mut state = CaptureState::default();
+        let (s, r) = async_channel::bounded(1);
+        state.request(s);
+        state.request_egui();
+        assert!(state.wanted());
+        state.deliver(egui::ColorImage::new([1, 1], egui::Color32::RED));
+        assert!(!state.wanted());
+        assert_eq!(r.try_recv().map(|i| i.size), Ok([1, 1]));
+        let mut input = egui::RawInput::default();
+        state.apply(&mut input);
+        state.apply(&mut input);
+        assert_eq!(input.events.len(), 1);
+    }
+}
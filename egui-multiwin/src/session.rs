@@ -0,0 +1,244 @@
+//! Contains the session of the application, the set of open windows that is saved when the application exits so that the same windows
+//! can be opened on the next launch. The session file is only read and written with the `persistence` feature.
+
+use std::collections::HashMap;
+#[cfg(feature = "persistence")]
+use std::convert::TryFrom;
+#[cfg(feature = "persistence")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::registry::WindowId;
+use crate::sync::LockRecover;
+
+/// A window that is part of the session
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionEntry {
+    /// The key given by the application, identifying what kind of window to create
+    pub key: String,
+    /// The data given by the application for creating the window again
+    pub payload: Vec<u8>,
+    /// True when the window was a root window
+    pub root: bool,
+}
+
+impl SessionEntry {
+    /// Convert the entry to json
+    #[cfg(feature = "persistence")]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "key": self.key,
+            "payload": self.payload,
+            "root": self.root,
+        })
+    }
+
+    /// Read the entry from json, returning None if it is not valid
+    #[cfg(feature = "persistence")]
+    fn from_json(v: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            key: v.get("key")?.as_str()?.to_string(),
+            payload: v
+                .get("payload")?
+                .as_array()?
+                .iter()
+                .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                .collect::<Option<Vec<u8>>>()?,
+            root: v.get("root")?.as_bool()?,
+        })
+    }
+}
+
+/// The internals of the session
+#[derive(Default)]
+struct SessionInternal {
+    /// The file the session is saved to, None when the session is not saved
+    #[cfg_attr(not(feature = "persistence"), allow(dead_code))]
+    path: Option<PathBuf>,
+    /// The open windows that are part of the session, by window id
+    windows: HashMap<WindowId, SessionEntry>,
+    /// The order windows were opened in, so that they are opened in the same order next time
+    order: Vec<WindowId>,
+    /// The root window of the session that closed last, while no other root window was open. Closing it makes the application exit,
+    /// so it stays in the session until the application exits or keeps running.
+    closed_root: Option<WindowId>,
+}
+
+/// The set of open windows that is saved when the application exits. Cloning the session gives another handle to the same session.
+#[derive(Clone, Default)]
+pub struct Session {
+    /// The shared internals
+    i: Arc<Mutex<SessionInternal>>,
+}
+
+impl Session {
+    /// Start saving the session to a file, returning the windows saved in the file by the last session. A missing or invalid file gives
+    /// an empty list, entries that cannot be read are skipped. Without the `persistence` feature nothing is read or saved.
+    pub fn load(&self, path: impl Into<PathBuf>) -> Vec<SessionEntry> {
+        let path = path.into();
+        #[cfg(feature = "persistence")]
+        let entries = Self::read(&path);
+        #[cfg(not(feature = "persistence"))]
+        let entries = {
+            crate::trace::warn(format_args!(
+                "The session is not saved to {} without the persistence feature",
+                path.display()
+            ));
+            Vec::new()
+        };
+        self.i.lock_recover().path = Some(path);
+        entries
+    }
+
+    /// Start saving the session to a file like [Self::load], creating the windows saved in the file with a factory. Entries the factory
+    /// does not create a window for are skipped. The windows are returned together, so that they can be sent to the event loop as one
+    /// batch before it runs.
+    pub fn restore<R>(
+        &self,
+        path: impl Into<PathBuf>,
+        factory: impl FnMut(SessionEntry) -> Option<R>,
+    ) -> Vec<R> {
+        self.load(path).into_iter().filter_map(factory).collect()
+    }
+
+    /// Read the windows saved in a session file
+    #[cfg(feature = "persistence")]
+    fn read(path: &Path) -> Vec<SessionEntry> {
+        std::fs::read(path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<serde_json::Value>(&data).ok())
+            .and_then(|v| {
+                v.as_array()
+                    .map(|a| a.iter().filter_map(SessionEntry::from_json).collect())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Add a window to the session
+    pub fn window_opened(&self, id: WindowId, entry: SessionEntry) {
+        let mut i = self.i.lock_recover();
+        i.windows.insert(id, entry);
+        i.order.push(id);
+    }
+
+    /// Remove a window from the session. A root window that closes while no other root window of the application is open stays in the
+    /// session, because the application exits with it, until [Self::keep_running] is called.
+    pub fn window_closed(&self, id: WindowId, last_root: bool) {
+        let mut i = self.i.lock_recover();
+        if last_root && i.windows.get(&id).map(|e| e.root).unwrap_or(false) {
+            if let Some(previous) = i.closed_root.replace(id) {
+                i.remove(previous);
+            }
+            return;
+        }
+        i.remove(id);
+    }
+
+    /// The application did not exit after its last root window closed, so that window is no longer part of the session
+    pub fn keep_running(&self) {
+        let mut i = self.i.lock_recover();
+        if let Some(id) = i.closed_root.take() {
+            i.remove(id);
+        }
+    }
+
+    /// Get the windows of the session, in the order they were opened
+    pub fn entries(&self) -> Vec<SessionEntry> {
+        let i = self.i.lock_recover();
+        i.order
+            .iter()
+            .filter_map(|id| i.windows.get(id))
+            .cloned()
+            .collect()
+    }
+
+    /// Write the windows of the session to the session file, when the session is saved. Errors are logged. Does nothing without the
+    /// `persistence` feature.
+    pub fn save(&self) {
+        #[cfg(feature = "persistence")]
+        self.write();
+    }
+
+    /// Write the windows of the session to the session file
+    #[cfg(feature = "persistence")]
+    fn write(&self) {
+        let Some(path) = self.i.lock_recover().path.clone() else {
+            return;
+        };
+        let json = serde_json::Value::Array(self.entries().iter().map(|e| e.to_json()).collect());
+        if let Err(e) = std::fs::write(&path, json.to_string()) {
+            crate::trace::error(format_args!(
+                "Unable to save the session to {}: {}",
+                path.display(),
+                e
+            ));
+        }
+    }
+}
+
+impl SessionInternal {
+    /// Forget a window
+    fn remove(&mut self, id: WindowId) {
+        self.windows.remove(&id);
+        self.order.retain(|w| *w != id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn entry_survives_json() {
+        let e = SessionEntry {
+            key: "document".to_string(),
+            payload: vec![0, 1, 255],
+            root: true,
+        };
+        assert_eq!(SessionEntry::from_json(&e.to_json()), Some(e));
+    }
+
+    #[test]
+    fn last_root_stays_until_exit() {
+        let session = Session::default();
+        let entry = |key: &str| SessionEntry {
+            key: key.to_string(),
+            payload: Vec::new(),
+            root: true,
+        };
+        let (first, second) = (WindowId::allocate(), WindowId::allocate());
+        session.window_opened(first, entry("first"));
+        session.window_opened(second, entry("second"));
+        session.window_closed(first, false);
+        session.window_closed(second, true);
+        assert_eq!(session.entries(), vec![entry("second")]);
+        session.keep_running();
+        assert!(session.entries().is_empty());
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn restore_more_windows_than_the_channel_holds() {
+        let path =
+            std::env::temp_dir().join(format!("egui-multiwin-session-{}.json", std::process::id()));
+        let saved = Session::default();
+        saved.load(&path);
+        for i in 0..12 {
+            let entry = SessionEntry {
+                key: format!("window {}", i),
+                payload: vec![i],
+                root: i == 0,
+            };
+            saved.window_opened(WindowId::allocate(), entry);
+        }
+        saved.save();
+        let windows = Session::default().restore(&path, |e| Some(e.key));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(windows.len(), 12);
+        // The pending windows are not read until the event loop runs, so restoring must not wait on the channel
+        let (sender, _receiver) = async_channel::bounded(10);
+        assert!(futures_lite::future::block_on(sender.send(windows)).is_ok());
+    }
+}
@@ -0,0 +1,107 @@
+//! Contains helpers for the locks and notifications shared between windows. A panic while a lock is held poisons the lock. The data
+//! behind the lock is still used afterwards, so that a panic in one window does not cause panics in the other windows.
+
+use std::sync::{Mutex, MutexGuard};
+
+/// Locking that recovers from poisoning
+pub trait LockRecover<T> {
+    /// Lock the mutex. A poisoned mutex is logged once and recovered.
+    fn lock_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockRecover<T> for Mutex<T> {
+    fn lock_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(|e| {
+            crate::trace::warn(format_args!(
+                "Recovering a lock that was poisoned by a panic"
+            ));
+            self.clear_poison();
+            e.into_inner()
+        })
+    }
+}
+
+/// A notification that coalesces. Notifying while a notification is already pending does nothing, so a storm of notifications never
+/// blocks the notifier. Cloning gives another handle to the same notification.
+#[derive(Clone)]
+pub struct Notify {
+    /// Sends the notification
+    s: async_channel::Sender<()>,
+    /// Receives the notification
+    r: async_channel::Receiver<()>,
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        let (s, r) = async_channel::bounded(1);
+        Self { s, r }
+    }
+}
+
+impl Notify {
+    /// Construct a notification with nothing pending
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Notify the waiter without blocking. Returns false when the notification is closed.
+    pub fn notify(&self) -> bool {
+        !matches!(
+            self.s.try_send(()),
+            Err(async_channel::TrySendError::Closed(_))
+        )
+    }
+
+    /// Wait for a notification. Returns false when the notification is closed.
+    pub async fn wait(&self) -> bool {
+        self.r.recv().await.is_ok()
+    }
+
+    /// Close the notification, waking everything that waits for it
+    pub fn close(&self) {
+        self.s.close();
+    }
+
+    /// Returns true when the notification is closed
+    pub fn is_closed(&self) -> bool {
+        self.s.is_closed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn notifications_coalesce_and_close() {
+        let n = Notify::new();
+        for _ in 0..100 {
+            assert!(n.notify());
+        }
+        assert!(futures_lite::future::block_on(n.wait()));
+        assert!(
+            futures_lite::future::block_on(futures_lite::future::poll_once(n.wait())).is_none()
+        );
+        n.close();
+        assert!(!n.notify());
+        assert!(!futures_lite::future::block_on(n.wait()));
+    }
+
+    #[test]
+    fn poisoned_lock_is_recovered() {
+        let common = Arc::new(Mutex::new(1));
+        let c = common.clone();
+        let _ = std::thread::spawn(move || {
+            let mut g = c.lock().unwrap();
+            *g = 2;
+            panic!("poisoning the lock");
+        })
+        .join();
+        assert!(common.is_poisoned());
+        assert_eq!(*common.lock_recover(), 2);
+        assert!(!common.is_poisoned());
+        *common.lock_recover() += 1;
+        assert_eq!(*common.lock().unwrap(), 3);
+    }
+}
@@ -0,0 +1,135 @@
+//! Contains a harness for testing the user interface of windows without opening windows or using opengl, such as in continuous
+//! integration. The redraw function of a window needs a real window and opengl context, so the user interface of the window is written
+//! as a function taking the egui context, which the redraw function calls with the context of the window, and which tests call with the
+//! context of the harness. Painting is skipped, the output of egui and the value returned by the function are given to the test.
+//!
+//! Interaction in egui uses the position of widgets from the previous frame, so a test runs a frame to lay out the window, simulates
+//! input at the position of a widget, then runs another frame to see the effect of the input.
+
+/// Runs frames of an egui context with simulated input
+pub struct TestHarness {
+    /// The egui context
+    ctx: egui::Context,
+    /// The size of the simulated window in points
+    size: egui::Vec2,
+    /// The events for the next frame
+    events: Vec<egui::Event>,
+    /// The modifier keys held down
+    modifiers: egui::Modifiers,
+    /// The time of the next frame in seconds
+    time: f64,
+}
+
+impl TestHarness {
+    /// The time between simulated frames in seconds
+    const FRAME_TIME: f64 = 1.0 / 60.0;
+
+    /// Construct a harness simulating a window of a size in points
+    pub fn new(size: egui::Vec2) -> Self {
+        Self {
+            ctx: egui::Context::default(),
+            size,
+            events: Vec::new(),
+            modifiers: egui::Modifiers::NONE,
+            time: 0.0,
+        }
+    }
+
+    /// Get the egui context of the harness, to change its style or fonts before running frames
+    pub fn context(&self) -> &egui::Context {
+        &self.ctx
+    }
+
+    /// Run one frame with the input simulated since the last frame, returning the output of egui and the value returned by the user
+    /// interface
+    pub fn run<R>(&mut self, ui: impl FnOnce(&egui::Context) -> R) -> (egui::FullOutput, R) {
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, self.size)),
+            time: Some(self.time),
+            predicted_dt: Self::FRAME_TIME as f32,
+            modifiers: self.modifiers,
+            events: std::mem::take(&mut self.events),
+            focused: true,
+            ..Default::default()
+        };
+        self.time += Self::FRAME_TIME;
+        self.ctx.begin_frame(input);
+        let r = ui(&self.ctx);
+        (self.ctx.end_frame(), r)
+    }
+
+    /// Add a raw event to the next frame
+    pub fn event(&mut self, event: egui::Event) {
+        self.events.push(event);
+    }
+
+    /// Hold down modifier keys for the following input, until they are changed again
+    pub fn set_modifiers(&mut self, modifiers: egui::Modifiers) {
+        self.modifiers = modifiers;
+    }
+
+    /// Move the mouse to a position in points
+    pub fn move_pointer(&mut self, pos: egui::Pos2) {
+        self.event(egui::Event::PointerMoved(pos));
+    }
+
+    /// Press (true) or release (false) a mouse button at a position in points
+    pub fn pointer_button(&mut self, pos: egui::Pos2, button: egui::PointerButton, pressed: bool) {
+        self.move_pointer(pos);
+        self.event(egui::Event::PointerButton {
+            pos,
+            button,
+            pressed,
+            modifiers: self.modifiers,
+        });
+    }
+
+    /// Click a mouse button at a position in points
+    pub fn click_button(&mut self, pos: egui::Pos2, button: egui::PointerButton) {
+        self.pointer_button(pos, button, true);
+        self.pointer_button(pos, button, false);
+    }
+
+    /// Click the primary mouse button at a position in points
+    pub fn click(&mut self, pos: egui::Pos2) {
+        self.click_button(pos, egui::PointerButton::Primary);
+    }
+
+    /// Press and release a key
+    pub fn press_key(&mut self, key: egui::Key) {
+        for pressed in [true, false] {
+            self.event(egui::Event::Key {
+                key,
+                physical_key: None,
+                pressed,
+                repeat: false,
+                modifiers: self.modifiers,
+            });
+        }
+    }
+
+    /// Type text, as if it was typed on the keyboard
+    pub fn type_text(&mut self, text: &str) {
+        self.event(egui::Event::Text(text.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clicking_a_button_is_seen_in_the_next_frame() {
+        let mut harness = TestHarness::new(egui::vec2(200.0, 100.0));
+        let ui = |ctx: &egui::Context| {
+            egui::CentralPanel::default()
+                .show(ctx, |ui| ui.button("Press"))
+                .inner
+        };
+        let (_, button) = harness.run(ui);
+        assert!(!button.clicked());
+        harness.click(button.rect.center());
+        let (_, button) = harness.run(ui);
+        assert!(button.clicked());
+    }
+}
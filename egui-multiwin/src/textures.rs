@@ -0,0 +1,392 @@
+//! Contains the textures shared by all windows of the application. Each window has its own egui context, and a texture loaded into one
+//! context can not be shown by another, so an image shown in several windows is normally uploaded once per window. The
+//! [AppTextureManager] uploads an image once, and gives each window a [egui::TextureId] for it when the window first shows it.
+//!
+//! A texture registered with the painter of a window is deleted by the painter when the window closes, so windows never register the
+//! uploaded texture itself. When the opengl contexts of windows are shared, see `MultiWindow::share_contexts`, each window gets a copy
+//! made on the graphics card from the uploaded texture, which is much cheaper than an upload. Windows with a context that is not shared,
+//! or older than opengl 3 or opengl es 3, upload the image themselves, so the pixels are kept until the texture is evicted.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use egui_glow_async::glow::{self, HasContext};
+
+use crate::registry::WindowId;
+use crate::sync::LockRecover;
+
+/// Identifies a texture of the [AppTextureManager], redeemed for a [egui::TextureId] in each window with
+/// [AppTextureManager::texture_id]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextureToken(u64);
+
+/// A texture shared by the windows
+struct SharedTexture {
+    /// The name of the texture, for log messages
+    name: String,
+    /// The pixels of the texture
+    image: Arc<egui::ColorImage>,
+    /// The filtering and wrapping of the texture
+    options: egui::TextureOptions,
+    /// The uploaded texture, in the contexts shared by windows. Created by the first window with a shared context that shows it.
+    original: Option<glow::Texture>,
+    /// The texture id of the texture in each window that shows it
+    windows: HashMap<WindowId, egui::TextureId>,
+}
+
+/// The internals of the texture manager
+#[derive(Default)]
+struct TexturesInternal {
+    /// The number of the next token
+    next: u64,
+    /// The textures, by token
+    textures: HashMap<TextureToken, SharedTexture>,
+    /// Uploaded textures of evicted textures, deleted by the next window with a shared context that draws
+    to_delete: Vec<glow::Texture>,
+    /// The texture ids of evicted textures, freed by each window in its next frame
+    to_free: HashMap<WindowId, Vec<egui::TextureId>>,
+    /// The number of times an image was uploaded
+    uploads: u64,
+}
+
+/// The textures shared by all windows of the application. Cloning the manager gives another handle to the same textures.
+#[derive(Clone, Default)]
+pub struct AppTextureManager {
+    /// The shared internals
+    i: Arc<Mutex<TexturesInternal>>,
+}
+
+/// How a window uses the textures of the manager, stored in the egui context of the window
+#[derive(Copy, Clone)]
+struct WindowTextures {
+    /// The id of the window
+    id: WindowId,
+    /// True when windows get a copy of the uploaded texture, because the context of the window is shared and can copy textures
+    copy: bool,
+}
+
+impl WindowTextures {
+    /// The id used to store the state in the egui context of the window
+    fn id() -> egui::Id {
+        egui::Id::new("egui_multiwin_textures")
+    }
+}
+
+impl AppTextureManager {
+    /// Construct a manager without textures
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an image, returning the token for its texture. Nothing is uploaded until a window shows the texture.
+    pub fn add(
+        &self,
+        name: impl Into<String>,
+        image: egui::ColorImage,
+        options: egui::TextureOptions,
+    ) -> TextureToken {
+        let mut i = self.i.lock_recover();
+        let token = TextureToken(i.next);
+        i.next += 1;
+        i.textures.insert(
+            token,
+            SharedTexture {
+                name: name.into(),
+                image: Arc::new(image),
+                options,
+                original: None,
+                windows: HashMap::new(),
+            },
+        );
+        token
+    }
+
+    /// Get the size of a texture in pixels, None for an evicted texture
+    pub fn size(&self, token: TextureToken) -> Option<[usize; 2]> {
+        self.i
+            .lock_recover()
+            .textures
+            .get(&token)
+            .map(|t| t.image.size)
+    }
+
+    /// The number of times an image was uploaded, counting an upload for each window whose context is not shared
+    pub fn uploads(&self) -> u64 {
+        self.i.lock_recover().uploads
+    }
+
+    /// Remove a texture. Each window frees its texture id in its next frame, and the uploaded texture is deleted once no window uses
+    /// it anymore. The token is not valid afterwards.
+    pub fn evict(&self, token: TextureToken) {
+        let mut i = self.i.lock_recover();
+        let Some(texture) = i.textures.remove(&token) else {
+            return;
+        };
+        if let Some(original) = texture.original {
+            i.to_delete.push(original);
+        }
+        for (window, id) in texture.windows {
+            i.to_free.entry(window).or_default().push(id);
+        }
+    }
+
+    /// Get the texture id of a texture in the window that owns an egui context, copying or uploading the texture when the window shows
+    /// it for the first time. Must be called while the opengl context of the window is current, such as in `TrackedWindow::redraw`.
+    /// Returns None for an evicted texture, when opengl fails, or outside of the windows of a `MultiWindow`.
+    pub fn texture_id(
+        &self,
+        token: TextureToken,
+        egui: &mut egui_glow_async::EguiGlow,
+    ) -> Option<egui::TextureId> {
+        let window: WindowTextures = egui.egui_ctx.data(|d| d.get_temp(WindowTextures::id()))?;
+        let mut guard = self.i.lock_recover();
+        let i = &mut *guard;
+        let texture = i.textures.get_mut(&token)?;
+        if let Some(id) = texture.windows.get(&window.id) {
+            return Some(*id);
+        }
+        let gl = egui.painter.gl();
+        let created = unsafe {
+            if window.copy {
+                let original = match texture.original {
+                    Some(original) => Ok(original),
+                    None => {
+                        let original = upload(gl, &texture.image, texture.options);
+                        if let Ok(original) = original {
+                            // The other contexts copy the texture, so the upload must be complete
+                            gl.finish();
+                            crate::trace::info(format_args!(
+                                "Uploaded the texture {}",
+                                texture.name
+                            ));
+                            i.uploads += 1;
+                            texture.original = Some(original);
+                        }
+                        original
+                    }
+                };
+                original.and_then(|original| copy(gl, original, &texture.image, texture.options))
+            } else {
+                let created = upload(gl, &texture.image, texture.options);
+                if created.is_ok() {
+                    crate::trace::info(format_args!(
+                        "Uploaded the texture {} for window {}, which can not share it",
+                        texture.name, window.id
+                    ));
+                    i.uploads += 1;
+                }
+                created
+            }
+        };
+        match created {
+            Ok(created) => {
+                let id = egui.painter.register_native_texture(created);
+                texture.windows.insert(window.id, id);
+                Some(id)
+            }
+            Err(e) => {
+                crate::trace::error(format_args!(
+                    "Unable to create the texture {}: {}",
+                    texture.name, e
+                ));
+                None
+            }
+        }
+    }
+
+    /// Let the window that owns an egui context use the textures. `shared` is true when the opengl context of the window shares objects
+    /// with the other windows. Called by the `MultiWindow` when egui starts in a window, and again after its context is recreated.
+    pub fn attach(&self, egui: &egui_glow_async::EguiGlow, id: WindowId, shared: bool) {
+        // Textures are copied with a framebuffer blit, which needs opengl 3 or opengl es 3
+        let copy = shared && egui.painter.gl().version().major >= 3;
+        let window = WindowTextures { id, copy };
+        egui.egui_ctx
+            .data_mut(|d| d.insert_temp(WindowTextures::id(), window));
+        self.window_closed(id);
+    }
+
+    /// Free the texture ids of evicted textures in a window, and delete the uploaded textures that no window uses anymore. Called by the
+    /// `MultiWindow` in every frame of every window, while the opengl context of the window is current.
+    pub fn frame(&self, egui: &mut egui_glow_async::EguiGlow) {
+        let Some(window) = egui
+            .egui_ctx
+            .data(|d| d.get_temp::<WindowTextures>(WindowTextures::id()))
+        else {
+            return;
+        };
+        let mut i = self.i.lock_recover();
+        for id in i.to_free.remove(&window.id).unwrap_or_default() {
+            egui.painter.free_texture(id);
+        }
+        if window.copy && i.to_free.is_empty() {
+            for original in i.to_delete.drain(..) {
+                unsafe { egui.painter.gl().delete_texture(original) };
+            }
+        }
+    }
+
+    /// Forget the textures of a window, because the window closed or lost its opengl context. The textures of the window are deleted
+    /// along with its painter.
+    pub fn window_closed(&self, id: WindowId) {
+        let mut i = self.i.lock_recover();
+        i.to_free.remove(&id);
+        for texture in i.textures.values_mut() {
+            texture.windows.remove(&id);
+        }
+    }
+}
+
+/// Create a texture with the filtering and wrapping of the options, leaving it bound
+unsafe fn create_texture(
+    gl: &glow::Context,
+    options: egui::TextureOptions,
+) -> Result<glow::Texture, String> {
+    let filter = |f: egui::TextureFilter| match f {
+        egui::TextureFilter::Nearest => glow::NEAREST as i32,
+        egui::TextureFilter::Linear => glow::LINEAR as i32,
+    };
+    let wrap = match options.wrap_mode {
+        egui::TextureWrapMode::ClampToEdge => glow::CLAMP_TO_EDGE,
+        egui::TextureWrapMode::Repeat => glow::REPEAT,
+        egui::TextureWrapMode::MirroredRepeat => glow::MIRRORED_REPEAT,
+    } as i32;
+    let texture = gl.create_texture()?;
+    gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+    gl.tex_parameter_i32(
+        glow::TEXTURE_2D,
+        glow::TEXTURE_MAG_FILTER,
+        filter(options.magnification),
+    );
+    gl.tex_parameter_i32(
+        glow::TEXTURE_2D,
+        glow::TEXTURE_MIN_FILTER,
+        filter(options.minification),
+    );
+    gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, wrap);
+    gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, wrap);
+    Ok(texture)
+}
+
+/// The internal format of textures, srgb like the textures of egui, except on opengl es 2 which lacks it
+fn internal_format(gl: &glow::Context) -> i32 {
+    let version = gl.version();
+    if version.is_embedded && version.major < 3 {
+        glow::RGBA as i32
+    } else {
+        glow::SRGB8_ALPHA8 as i32
+    }
+}
+
+/// Upload an image into a new texture
+unsafe fn upload(
+    gl: &glow::Context,
+    image: &egui::ColorImage,
+    options: egui::TextureOptions,
+) -> Result<glow::Texture, String> {
+    let texture = create_texture(gl, options)?;
+    let pixels: Vec<u8> = image.pixels.iter().flat_map(|p| p.to_array()).collect();
+    gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+    gl.tex_image_2d(
+        glow::TEXTURE_2D,
+        0,
+        internal_format(gl),
+        image.size[0] as i32,
+        image.size[1] as i32,
+        0,
+        glow::RGBA,
+        glow::UNSIGNED_BYTE,
+        Some(&pixels),
+    );
+    Ok(texture)
+}
+
+/// Copy a texture in a shared context into a new texture of the current context, with a framebuffer blit
+unsafe fn copy(
+    gl: &glow::Context,
+    source: glow::Texture,
+    image: &egui::ColorImage,
+    options: egui::TextureOptions,
+) -> Result<glow::Texture, String> {
+    let (width, height) = (image.size[0] as i32, image.size[1] as i32);
+    let texture = create_texture(gl, options)?;
+    gl.tex_image_2d(
+        glow::TEXTURE_2D,
+        0,
+        internal_format(gl),
+        width,
+        height,
+        0,
+        glow::RGBA,
+        glow::UNSIGNED_BYTE,
+        None,
+    );
+    let read = gl.create_framebuffer()?;
+    let draw = gl.create_framebuffer()?;
+    gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(read));
+    gl.framebuffer_texture_2d(
+        glow::READ_FRAMEBUFFER,
+        glow::COLOR_ATTACHMENT0,
+        glow::TEXTURE_2D,
+        Some(source),
+        0,
+    );
+    gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, Some(draw));
+    gl.framebuffer_texture_2d(
+        glow::DRAW_FRAMEBUFFER,
+        glow::COLOR_ATTACHMENT0,
+        glow::TEXTURE_2D,
+        Some(texture),
+        0,
+    );
+    // The pixels are copied as they are stored, and the blit is limited by the scissor of the last frame
+    let srgb = !gl.version().is_embedded && gl.is_enabled(glow::FRAMEBUFFER_SRGB);
+    if srgb {
+        gl.disable(glow::FRAMEBUFFER_SRGB);
+    }
+    gl.disable(glow::SCISSOR_TEST);
+    gl.blit_framebuffer(
+        0,
+        0,
+        width,
+        height,
+        0,
+        0,
+        width,
+        height,
+        glow::COLOR_BUFFER_BIT,
+        glow::NEAREST,
+    );
+    if srgb {
+        gl.enable(glow::FRAMEBUFFER_SRGB);
+    }
+    gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+    gl.delete_framebuffer(read);
+    gl.delete_framebuffer(draw);
+    Ok(texture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicted_textures_are_freed_in_each_window() {
+        let manager = AppTextureManager::new();
+        let image = egui::ColorImage::new([4, 2], egui::Color32::RED);
+        let token = manager.add("red", image, egui::TextureOptions::LINEAR);
+        assert_eq!(manager.size(token), Some([4, 2]));
+        let (a, b) = (WindowId::allocate(), WindowId::allocate());
+        {
+            let mut i = manager.i.lock_recover();
+            let texture = i.textures.get_mut(&token).unwrap();
+            texture.windows.insert(a, egui::TextureId::User(1));
+            texture.windows.insert(b, egui::TextureId::User(7));
+        }
+        manager.evict(token);
+        assert_eq!(manager.size(token), None);
+        manager.window_closed(b);
+        let i = manager.i.lock_recover();
+        assert_eq!(i.to_free.get(&a), Some(&vec![egui::TextureId::User(1)]));
+        assert!(!i.to_free.contains_key(&b));
+    }
+}
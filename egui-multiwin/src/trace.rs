@@ -0,0 +1,96 @@
+//! Contains the tracing of the event loop and windows. With the `tracing` feature (enabled by default), events and per window spans
+//! are recorded with the tracing crate, including the warnings and errors of the crate. Without it, nothing is recorded or printed.
+
+use std::future::Future;
+
+/// Record an informational event
+pub fn info(message: std::fmt::Arguments<'_>) {
+    #[cfg(feature = "tracing")]
+    tracing::info!("{}", message);
+    #[cfg(not(feature = "tracing"))]
+    let _ = message;
+}
+
+/// Record a debugging event
+pub fn debug(message: std::fmt::Arguments<'_>) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!("{}", message);
+    #[cfg(not(feature = "tracing"))]
+    let _ = message;
+}
+
+/// Record a warning, for something that went wrong without stopping the application
+pub fn warn(message: std::fmt::Arguments<'_>) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!("{}", message);
+    #[cfg(not(feature = "tracing"))]
+    let _ = message;
+}
+
+/// Record an error, for something that failed
+pub fn error(message: std::fmt::Arguments<'_>) {
+    #[cfg(feature = "tracing")]
+    tracing::error!("{}", message);
+    #[cfg(not(feature = "tracing"))]
+    let _ = message;
+}
+
+/// A span covering the lifetime of one window, with child spans for the creation, each redraw, and the teardown of the window
+#[derive(Clone)]
+pub struct WindowSpan {
+    /// The span of the window
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl WindowSpan {
+    /// Construct the span of a window
+    pub fn new(id: crate::registry::WindowId) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = id;
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!("window", id = id.get()),
+        }
+    }
+
+    /// A child span covering the creation of the window
+    pub fn creation(&self) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(parent: &self.span, "creation"),
+        }
+    }
+
+    /// A child span covering one redraw of the window
+    pub fn redraw(&self) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(parent: &self.span, "redraw"),
+        }
+    }
+
+    /// A child span covering the teardown of the window
+    pub fn teardown(&self) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(parent: &self.span, "teardown"),
+        }
+    }
+
+    /// Run a future inside the span. The span is entered each time the future is polled.
+    pub fn instrument<F: Future>(&self, f: F) -> impl Future<Output = F::Output> {
+        #[cfg(feature = "tracing")]
+        return tracing::Instrument::instrument(f, self.span.clone());
+        #[cfg(not(feature = "tracing"))]
+        f
+    }
+
+    /// Run a function inside the span
+    pub fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+}
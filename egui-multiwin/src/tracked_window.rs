@@ -1,7 +1,8 @@
 //! This module covers definition and functionality for an individual window.
 
 use std::num::NonZeroU32;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 use egui::NumExt;
 use glutin::context::{NotCurrentContext, PossiblyCurrentContext};
@@ -9,40 +10,110 @@ use glutin::prelude::GlDisplay;
 use glutin::prelude::{NotCurrentGlContext, PossiblyCurrentGlContext};
 use glutin::surface::GlSurface;
 use glutin::surface::WindowSurface;
+use raw_window_handle_5::HasRawWindowHandle;
 use thiserror::Error;
 
-/// A holder of context and related items
-pub struct ContextHolder<T> {
+use crate::registry::WindowId;
+use crate::sync::LockRecover;
+
+/// The state of the opengl context of a window
+enum GlContext {
+    /// The context is possibly current
+    PossiblyCurrent(PossiblyCurrentContext),
+    /// The context is not current
+    NotCurrent(NotCurrentContext),
+    /// The context was destroyed by glutin while changing whether it is current
+    Lost,
+}
+
+/// The errors that can occur when using the opengl context of a window
+#[derive(Error, Debug)]
+pub enum ContextError {
+    /// glutin returned an error
+    #[error("Opengl context error: {0}")]
+    Glutin(#[from] glutin::error::Error),
+    /// The context was destroyed by a failure to change whether it is current
+    #[error("The opengl context was lost")]
+    Lost,
+    /// The operation requires the context to be current
+    #[error("The opengl context is not current")]
+    NotCurrent,
+    /// No opengl context could be created for the window
+    #[error("Unable to create an opengl context")]
+    Creation,
+    /// The egui painter could not be created
+    #[error("Unable to create the egui painter: {0}")]
+    Painter(String),
+    /// The window has no surface because the application is suspended
+    #[error("The window has no surface while the application is suspended")]
+    Suspended,
+}
+
+impl ContextError {
+    /// Returns true when the error means that the context was lost and must be recreated, such as after a reset of the graphics driver
+    pub fn is_context_lost(&self) -> bool {
+        match self {
+            ContextError::Glutin(e) => e.error_kind() == glutin::error::ErrorKind::ContextLost,
+            ContextError::Lost => true,
+            _ => false,
+        }
+    }
+}
+
+/// A holder of context and related items. The context is made current and not current in place.
+pub struct ContextHolder {
     /// The context being held
-    context: T,
+    context: GlContext,
     /// The window
     pub window: Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
-    /// The window surface
-    ws: glutin::surface::Surface<WindowSurface>,
+    /// The window surface, None while the application is suspended
+    ws: Option<glutin::surface::Surface<WindowSurface>>,
     /// The display
     display: glutin::display::Display,
+    /// The configuration the surface was created with, used to create the surface again when the application resumes
+    config: glutin::config::Config,
+    /// True when the surface was created with srgb conversion
+    srgb: bool,
     /// The options for the display
     options: TrackedWindowOptions,
+    /// The swap mode to use for the window
+    swap_mode: SwapMode,
+    /// The swap mode last given to the surface, the swap interval is only set when this differs from the requested swap mode
+    applied_swap_mode: std::cell::Cell<Option<SwapMode>>,
+    /// The size last given to the surface, the surface is only resized when the window size differs
+    surface_size: std::cell::Cell<Option<(u32, u32)>>,
 }
 
-impl<T> ContextHolder<T> {
+impl ContextHolder {
     /// Create a new context holder
     pub fn new(
-        context: T,
-        window: async_winit::window::Window<async_winit::ThreadSafe>,
+        context: NotCurrentContext,
+        window: Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
         ws: glutin::surface::Surface<WindowSurface>,
         display: glutin::display::Display,
+        config: glutin::config::Config,
+        srgb: bool,
         options: TrackedWindowOptions,
     ) -> Self {
         Self {
-            context,
-            window: Arc::new(window),
-            ws,
+            context: GlContext::NotCurrent(context),
+            window,
+            ws: Some(ws),
             display,
+            config,
+            srgb,
             options,
+            swap_mode: options.swap_mode(),
+            applied_swap_mode: std::cell::Cell::new(None),
+            surface_size: std::cell::Cell::new(None),
         }
     }
 
+    /// Change the swap mode of the window, taking effect on the next swap
+    pub fn set_swap_mode(&mut self, mode: SwapMode) {
+        self.swap_mode = mode;
+    }
+
     /// Get the window handle
     pub fn window(&self) -> Arc<async_winit::window::Window<async_winit::ThreadSafe>> {
         self.window.clone()
@@ -54,82 +125,1982 @@ impl<T> ContextHolder<T> {
         let cst = unsafe { std::ffi::CStr::from_ptr(cs) };
         self.display.get_proc_address(cst)
     }
-}
 
-impl ContextHolder<PossiblyCurrentContext> {
-    /// Call swap_buffers. linux targets have vsync specifically disabled because it causes problems with hidden windows.
-    pub fn swap_buffers(&self) -> glutin::error::Result<()> {
-        if self.options.vsync {
-            let _e = self.ws.set_swap_interval(
-                &self.context,
-                glutin::surface::SwapInterval::Wait(NonZeroU32::MIN),
+    /// Returns true when the configuration of the context is hardware accelerated
+    pub fn hardware_accelerated(&self) -> bool {
+        use glutin::prelude::GlConfig;
+        self.config.hardware_accelerated()
+    }
+
+    /// Returns true when the context has been made current and not made not current since
+    pub fn is_current(&self) -> bool {
+        matches!(self.context, GlContext::PossiblyCurrent(_))
+    }
+
+    /// Get the surface of the window, when the application is not suspended
+    fn surface(&self) -> Result<&glutin::surface::Surface<WindowSurface>, ContextError> {
+        self.ws.as_ref().ok_or(ContextError::Suspended)
+    }
+
+    /// Returns true when the surface of the window was destroyed because the application is suspended
+    pub fn is_suspended(&self) -> bool {
+        self.ws.is_none()
+    }
+
+    /// Make the context not current and destroy the surface of the window, because the application was suspended. The native window
+    /// may be destroyed by the operating system while the application is suspended.
+    pub fn suspend(&mut self) -> Result<(), ContextError> {
+        self.make_not_current()?;
+        self.ws = None;
+        Ok(())
+    }
+
+    /// Create the surface of the window again after the application resumed. Does nothing when the surface exists.
+    pub async fn resume(&mut self) -> Result<(), ContextError> {
+        if self.ws.is_some() {
+            return Ok(());
+        }
+        let size = self.window.inner_size().await;
+        let sa = glutin::surface::SurfaceAttributesBuilder::<WindowSurface>::new()
+            .with_srgb(Some(self.srgb))
+            .build(
+                self.window.raw_window_handle(),
+                NonZeroU32::new(size.width.at_least(1)).unwrap(),
+                NonZeroU32::new(size.height.at_least(1)).unwrap(),
             );
-        } else {
-            let _e = self
-                .ws
-                .set_swap_interval(&self.context, glutin::surface::SwapInterval::DontWait);
+        self.ws = Some(unsafe { self.display.create_window_surface(&self.config, &sa) }?);
+        // The swap interval belongs to the surface
+        self.applied_swap_mode.set(None);
+        self.surface_size.set(Some((size.width, size.height)));
+        Ok(())
+    }
+
+    /// Make the context current
+    pub fn make_current(&mut self) -> Result<(), ContextError> {
+        let ws = self.ws.as_ref().ok_or(ContextError::Suspended)?;
+        match std::mem::replace(&mut self.context, GlContext::Lost) {
+            GlContext::PossiblyCurrent(c) => {
+                let r = c.make_current(ws);
+                self.context = GlContext::PossiblyCurrent(c);
+                r?;
+            }
+            GlContext::NotCurrent(c) => {
+                self.context = GlContext::PossiblyCurrent(c.make_current(ws)?);
+            }
+            GlContext::Lost => return Err(ContextError::Lost),
+        }
+        Ok(())
+    }
+
+    /// Make the context not current
+    pub fn make_not_current(&mut self) -> Result<(), ContextError> {
+        match std::mem::replace(&mut self.context, GlContext::Lost) {
+            GlContext::PossiblyCurrent(c) => {
+                self.context = GlContext::NotCurrent(c.make_not_current()?);
+            }
+            GlContext::NotCurrent(c) => self.context = GlContext::NotCurrent(c),
+            GlContext::Lost => return Err(ContextError::Lost),
+        }
+        Ok(())
+    }
+
+    /// Get the context, when it is current
+    fn current(&self) -> Result<&PossiblyCurrentContext, ContextError> {
+        match &self.context {
+            GlContext::PossiblyCurrent(c) => Ok(c),
+            GlContext::NotCurrent(_) => Err(ContextError::NotCurrent),
+            GlContext::Lost => Err(ContextError::Lost),
         }
-        self.ws.swap_buffers(&self.context)
     }
 
-    /// Resize the window to the specified size. The size cannot be zero in either dimension.
-    pub fn resize(&self, size: async_winit::dpi::PhysicalSize<u32>) {
+    /// Call swap_buffers. The context must be current.
+    /// The swap interval is only set when the swap mode changes, setting it on every frame is slow on some drivers.
+    pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        let context = self.current()?;
+        let ws = self.surface()?;
+        if self.applied_swap_mode.get() != Some(self.swap_mode) {
+            let _e = ws.set_swap_interval(context, self.swap_mode.interval());
+            self.applied_swap_mode.set(Some(self.swap_mode));
+        }
+        Ok(ws.swap_buffers(context)?)
+    }
+
+    /// Resize the window to the specified size. The size cannot be zero in either dimension. The context must be current.
+    /// Nothing is done when the surface already has the size.
+    pub fn resize(&self, size: async_winit::dpi::PhysicalSize<u32>) -> Result<(), ContextError> {
         let w = size.width;
         let h = size.height;
-        self.ws.resize(
-            &self.context,
+        if self.surface_size.get() == Some((w, h)) {
+            return Ok(());
+        }
+        self.surface()?.resize(
+            self.current()?,
             NonZeroU32::new(w.at_least(1)).unwrap(),
             NonZeroU32::new(h.at_least(1)).unwrap(),
-        )
+        );
+        self.surface_size.set(Some((w, h)));
+        Ok(())
     }
 
-    /// Make the context current again
-    pub fn make_current(&mut self) {
-        self.context.make_current(&self.ws).unwrap();
+    /// Create the opengl display, surface and context for a window. Returns the context, whether the custom opengl rendering uses srgb
+    /// conversion, and whether the context shares objects with other windows. When no configuration matching the options works, relaxed
+    /// configurations are tried, which accept the configurations of a software rasterizer. Returns None when nothing works.
+    ///
+    /// Mesa only offers its software rasterizer when the `LIBGL_ALWAYS_SOFTWARE` environment variable is set before the driver is
+    /// loaded. Applications that must run on machines without working graphics hardware, such as headless test machines, set it before
+    /// starting, the environment can not be changed safely once windows exist.
+    pub async fn create(
+        winitwindow: &Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
+        event_loop: &async_winit::event_loop::EventLoopWindowTarget,
+        options: &TrackedWindowOptions,
+        gl_share: &SharedGlContext,
+    ) -> Option<(Self, bool, bool)> {
+        if let Some(gl) = Self::create_with(winitwindow, event_loop, options, gl_share, false).await
+        {
+            return Some(gl);
+        }
+        crate::trace::warn(format_args!("No opengl configuration matching the options works for the window, trying relaxed configurations"));
+        if let Some(gl) = Self::create_with(winitwindow, event_loop, options, gl_share, true).await
+        {
+            return Some(gl);
+        }
+        #[cfg(target_os = "linux")]
+        crate::trace::error(format_args!("No opengl configuration works for the window, set LIBGL_ALWAYS_SOFTWARE=1 before starting to use the software rasterizer"));
+        #[cfg(not(target_os = "linux"))]
+        crate::trace::error(format_args!("No opengl configuration works for the window"));
+        None
     }
 
-    /// Make a possibly current context not-current
-    pub fn make_not_current(
-        self,
-    ) -> Result<ContextHolder<NotCurrentContext>, glutin::error::Error> {
-        let c = self.context.make_not_current()?;
-        let s = ContextHolder::<NotCurrentContext> {
-            context: c,
-            window: self.window,
-            ws: self.ws,
-            display: self.display,
-            options: self.options,
-        };
-        Ok(s)
+    /// Try to create the opengl display, surface and context for a window, using the options of the window or a relaxed configuration
+    /// that accepts slow and small configurations
+    async fn create_with(
+        winitwindow: &Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
+        event_loop: &async_winit::event_loop::EventLoopWindowTarget,
+        options: &TrackedWindowOptions,
+        gl_share: &SharedGlContext,
+        relaxed: bool,
+    ) -> Option<(Self, bool, bool)> {
+        use glutin::prelude::GlConfig;
+        use raw_window_handle_5::HasRawDisplayHandle;
+        let rdh = event_loop.raw_display_handle();
+        let rwh = winitwindow.raw_window_handle();
+        #[cfg(target_os = "windows")]
+        let pref = glutin::display::DisplayApiPreference::Wgl(Some(rwh));
+        #[cfg(target_os = "linux")]
+        let pref = glutin::display::DisplayApiPreference::Egl;
+        #[cfg(target_os = "macos")]
+        let pref = glutin::display::DisplayApiPreference::Cgl;
+        let display = unsafe { glutin::display::Display::new(rdh, pref) };
+        if let Ok(display) = display {
+            let configt = if relaxed {
+                // Accept any configuration that can draw to a window, including slow and small ones
+                glutin::config::ConfigTemplateBuilder::default()
+                    .with_alpha_size(0)
+                    .with_buffer_type(glutin::config::ColorBufferType::Rgb {
+                        r_size: 5,
+                        g_size: 6,
+                        b_size: 5,
+                    })
+                    .prefer_hardware_accelerated(None)
+                    .build()
+            } else {
+                let mut configt = glutin::config::ConfigTemplateBuilder::default()
+                    .with_transparency(options.transparent);
+                if let Some(bits) = options.depth_bits {
+                    configt = configt.with_depth_size(bits);
+                }
+                if let Some(bits) = options.stencil_bits {
+                    configt = configt.with_stencil_size(bits);
+                }
+                configt.build()
+            };
+            let mut configs: Vec<glutin::config::Config> =
+                match unsafe { display.find_configs(configt) } {
+                    Ok(configs) => configs.collect(),
+                    Err(_) => Vec::new(),
+                };
+            configs.sort_by(|a, b| a.num_samples().cmp(&b.num_samples()));
+            if options.transparent {
+                // Prefer configurations with a full alpha channel that support transparency
+                configs.sort_by_key(|c| {
+                    (c.alpha_size() != 8, c.supports_transparency() != Some(true))
+                });
+            }
+            if options.srgb == SrgbMode::ForceEnabled {
+                configs.sort_by_key(|c| !c.srgb_capable());
+            }
+            if let Some(picker) = options.config_picker {
+                if !configs.is_empty() {
+                    let index = picker(&configs);
+                    if index < configs.len() {
+                        let config = configs.remove(index);
+                        configs.insert(0, config);
+                    }
+                }
+            }
+            // Try all configurations until one works
+            for config in configs {
+                let srgb = options.srgb.enabled(&config);
+                let sab: glutin::surface::SurfaceAttributesBuilder<WindowSurface> =
+                    glutin::surface::SurfaceAttributesBuilder::default();
+                let sa = sab.with_srgb(Some(srgb)).build(
+                    rwh,
+                    std::num::NonZeroU32::new(winitwindow.inner_size().await.width.max(1)).unwrap(),
+                    std::num::NonZeroU32::new(winitwindow.inner_size().await.height.max(1))
+                        .unwrap(),
+                );
+                let ws = unsafe { display.create_window_surface(&config, &sa) };
+                if let Ok(ws) = ws {
+                    // Use the first context in the list of preferences that can be created
+                    let (gl_window, shared_context) =
+                        match gl_share.create_context(&display, &config, options, Some(rwh)) {
+                            Some(c) => c,
+                            None => continue,
+                        };
+                    crate::trace::debug(format_args!(
+                        "Using gl config: samples {}, alpha {}, depth {}, stencil {}, srgb {}, float {}, hardware accelerated {}",
+                        config.num_samples(),
+                        config.alpha_size(),
+                        config.depth_size(),
+                        config.stencil_size(),
+                        config.srgb_capable(),
+                        config.float_pixels(),
+                        config.hardware_accelerated(),
+                    ));
+                    return Some((
+                        Self::new(
+                            gl_window,
+                            winitwindow.clone(),
+                            ws,
+                            display,
+                            config,
+                            srgb,
+                            *options,
+                        ),
+                        srgb,
+                        shared_context,
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Information about the opengl context of a window, given to the window when the context is created
+#[derive(Clone, Debug)]
+pub struct GlInfo {
+    /// The version of the context
+    pub version: egui_glow_async::glow::Version,
+    /// The renderer reported by opengl, naming the graphics hardware or the software rasterizer
+    pub renderer: String,
+    /// False when the window is drawn by a software rasterizer, which is slow
+    pub hardware: bool,
+}
+
+impl GlInfo {
+    /// Query the information from the current opengl context of a window
+    pub fn new(gl: &egui_glow_async::glow::Context, context: &ContextHolder) -> Self {
+        use egui_glow_async::glow::HasContext;
+        let renderer = unsafe { gl.get_parameter_string(egui_glow_async::glow::RENDERER) };
+        let lower = renderer.to_lowercase();
+        let software = ["llvmpipe", "softpipe", "swiftshader", "software", "gdi generic"]
+            .iter()
+            .any(|s| lower.contains(s));
+        Self {
+            version: gl.version().clone(),
+            hardware: context.hardware_accelerated() && !software,
+            renderer,
+        }
+    }
+
+    /// Log the opengl renderer of a window, warning when the window is drawn by a software rasterizer
+    pub fn log(&self) {
+        if self.hardware {
+            crate::trace::info(format_args!(
+                "Opengl {:?} renderer {}",
+                self.version, self.renderer
+            ));
+        } else {
+            crate::trace::warn(format_args!(
+                "Software rendering is in use for a window, opengl {:?} renderer {}",
+                self.version, self.renderer
+            ));
+        }
     }
 }
 
-impl ContextHolder<NotCurrentContext> {
-    /// Transforms a not current context into a possibly current context
+/// Clear a window by filling it with transparency, also clearing the depth and stencil buffers when the options of the window have them
+pub fn clear_window(gl: &egui_glow_async::glow::Context, options: &TrackedWindowOptions) {
+    use egui_glow_async::glow::{self, HasContext};
+    unsafe {
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        let mut mask = glow::COLOR_BUFFER_BIT;
+        if options.depth_bits.is_some() {
+            gl.clear_depth_f32(1.0);
+            mask |= glow::DEPTH_BUFFER_BIT;
+        }
+        if options.stencil_bits.is_some() {
+            gl.clear_stencil(0);
+            mask |= glow::STENCIL_BUFFER_BIT;
+        }
+        gl.clear(mask);
+    }
+}
+
+/// Grab the cursor of a window, or release it with [egui::viewport::CursorGrab::None]. Locking falls back to confining the cursor, on
+/// platforms that can not lock it. Returns true when the grab changed, a failure is logged.
+pub async fn grab_cursor(
+    window: &async_winit::window::Window<async_winit::ThreadSafe>,
+    grab: egui::viewport::CursorGrab,
+) -> bool {
+    use async_winit::window::CursorGrabMode;
+    use egui::viewport::CursorGrab;
+    let mode = match grab {
+        CursorGrab::None => CursorGrabMode::None,
+        CursorGrab::Confined => CursorGrabMode::Confined,
+        CursorGrab::Locked => CursorGrabMode::Locked,
+    };
+    let mut result = window.set_cursor_grab(mode).await;
+    if result.is_err() && grab == CursorGrab::Locked {
+        result = window.set_cursor_grab(CursorGrabMode::Confined).await;
+    }
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            crate::trace::warn(format_args!("Unable to grab the cursor: {}", e));
+            false
+        }
+    }
+}
+
+/// Tracks the opengl windows of an application. The context of a lone window stays current between frames, because making a context
+/// current and not current are slow driver calls on some platforms. Contexts are made not current after every frame when there are
+/// multiple windows.
+#[derive(Clone, Default)]
+pub struct ContextTracker {
+    /// The number of open windows, and the id of the window whose context was made current last
+    i: Arc<Mutex<(usize, Option<WindowId>)>>,
+}
+
+impl ContextTracker {
+    /// Count a newly created window
+    pub fn add_window(&self) {
+        self.i.lock_recover().0 += 1;
+    }
+
+    /// Stop counting a window that has closed
+    pub fn remove_window(&self, id: WindowId) {
+        let mut i = self.i.lock_recover();
+        i.0 = i.0.saturating_sub(1);
+        if i.1 == Some(id) {
+            i.1 = None;
+        }
+    }
+
+    /// Make the context of a window current, unless it is the lone window and its context is still current
     pub fn make_current(
-        self,
-    ) -> Result<ContextHolder<PossiblyCurrentContext>, glutin::error::Error> {
-        let c = self.context.make_current(&self.ws)?;
-        let s = ContextHolder::<PossiblyCurrentContext> {
-            context: c,
-            window: self.window,
-            ws: self.ws,
-            display: self.display,
-            options: self.options,
-        };
-        Ok(s)
+        &self,
+        id: WindowId,
+        context: &mut ContextHolder,
+    ) -> Result<(), ContextError> {
+        let mut i = self.i.lock_recover();
+        if i.0 == 1 && i.1 == Some(id) && context.is_current() {
+            return Ok(());
+        }
+        i.1 = None;
+        context.make_current()?;
+        i.1 = Some(id);
+        Ok(())
+    }
+
+    /// Make the context of a window not current and destroy its surface, because the application was suspended
+    pub fn suspend(&self, id: WindowId, context: &mut ContextHolder) -> Result<(), ContextError> {
+        let mut i = self.i.lock_recover();
+        if i.1 == Some(id) {
+            i.1 = None;
+        }
+        context.suspend()
+    }
+
+    /// Make the context of a window not current, unless it is the lone window
+    pub fn make_not_current(
+        &self,
+        id: WindowId,
+        context: &mut ContextHolder,
+    ) -> Result<(), ContextError> {
+        let mut i = self.i.lock_recover();
+        if i.0 == 1 && i.1 == Some(id) {
+            return Ok(());
+        }
+        if i.1 == Some(id) {
+            i.1 = None;
+        }
+        context.make_not_current()
+    }
+
+    /// Make the context of a window that is closing not current, even when it is the lone window
+    pub fn release(&self, id: WindowId, context: &mut ContextHolder) -> Result<(), ContextError> {
+        let mut i = self.i.lock_recover();
+        if i.1 == Some(id) {
+            i.1 = None;
+        }
+        context.make_not_current()
+    }
+}
+
+/// The requirements on the common data of an application for a thread safety of the event loop. The `tracked_window` macro checks them, so
+/// that common data missing a bound gives an error naming the bound instead of errors inside the generated code.
+#[diagnostic::on_unimplemented(
+    message = "the common data `{Self}` of the windows must be `Send` and `'static` for the thread safety `{TS}`",
+    label = "used as the common data of the windows"
+)]
+pub trait CommonData<TS> {}
+
+impl<T: Send + 'static> CommonData<async_winit::ThreadSafe> for T {}
+
+/// A future returned by the async functions of [TrackedWindowDyn]
+pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + 'a>>;
+
+/// An object safe version of the `TrackedWindow` trait generated by the `tracked_window` macro, where the async functions return boxed
+/// futures. Windows implementing it are kept as `Box<dyn TrackedWindowDyn<C, R>>`, the `DynWindow` of the generated module, instead of in
+/// an enum with enum_dispatch. `C` is the common data of the application and `R` is the `RedrawResponse` of the generated module. The
+/// functions are the same as those of `TrackedWindow`. The macros are still needed, they generate the `MultiWindow` that runs the windows.
+pub trait TrackedWindowDyn<C, R> {
+    /// Returns true if the window is a root window. Windows are not root windows by default.
+    fn is_root(&self) -> bool {
+        false
+    }
+
+    /// Returns true when the window is allowed to close. Windows are always allowed to close by default.
+    fn can_quit(&mut self, _c: &mut C) -> bool {
+        true
+    }
+
+    /// Sets whether or not the window is a root window. Does nothing by default.
+    fn set_root(&mut self, _root: bool) {}
+
+    /// Called when the window gains or loses focus. Does nothing by default.
+    fn focus_changed(&mut self, _focused: bool) {}
+
+    /// Called with the relative motion of the mouse while the window grabs the cursor. Does nothing by default.
+    fn raw_mouse_motion(&mut self, _c: &mut C, _delta: (f64, f64)) {}
+
+    /// Called with the image on the clipboard after the user pastes into the window. Does nothing by default.
+    fn pasted_image(&mut self, _c: &mut C, _image: &arboard::ImageData<'static>) {}
+
+    /// Called with a payload dragged from a window of the application when the mouse button is released over this window, at a
+    /// position in points in this window. Does nothing by default.
+    fn dropped_payload(
+        &mut self,
+        _c: &mut C,
+        _payload: &crate::drag::DragPayload,
+        _pos: egui::Pos2,
+    ) {
+    }
+
+    /// Receives raw events for the window before egui processes them. Return true to mark the event as consumed. Does nothing by default.
+    fn handle_event<'a>(
+        &'a mut self,
+        _c: &'a mut C,
+        _event: &'a WindowEventDescriptor,
+    ) -> BoxFuture<'a, bool> {
+        Box::pin(async { false })
+    }
+
+    /// Runs the redraw for the window
+    fn redraw<'a>(
+        &'a mut self,
+        c: &'a mut C,
+        egui: &'a mut egui_glow_async::EguiGlow,
+        window: &'a async_winit::window::Window<async_winit::ThreadSafe>,
+        clipboard: Arc<Mutex<crate::clipboard::Clipboard>>,
+    ) -> BoxFuture<'a, R>;
+
+    /// Called once the opengl context of the window is created. Does nothing by default.
+    fn opengl_init(&mut self, _info: &GlInfo) {}
+
+    /// Called after the opengl context of the window was lost and recreated. Does nothing by default.
+    fn context_recreated(&mut self, _info: &GlInfo) {}
+
+    /// Called when the application is suspended, only on android. Does nothing by default.
+    fn suspended(&mut self, _c: &mut C) {}
+
+    /// Called when the application resumes after being suspended, only on android. Does nothing by default.
+    fn resumed(&mut self, _c: &mut C) {}
+
+    /// Allows opengl rendering to be done underneath all of the egui stuff of the window
+    /// # Safety
+    ///
+    /// opengl functions are unsafe. This function would require calling opengl functions.
+    unsafe fn opengl_before<'a>(
+        &'a mut self,
+        _c: &'a mut C,
+        _gl: &'a Arc<egui_glow_async::painter::Context>,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async {})
+    }
+
+    /// Allows opengl rendering to be done on top of all of the egui stuff of the window
+    /// # Safety
+    ///
+    /// opengl functions are unsafe. This function would require calling opengl functions.
+    unsafe fn opengl_after<'a>(
+        &'a mut self,
+        _c: &'a mut C,
+        _gl: &'a Arc<egui_glow_async::painter::Context>,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async {})
     }
 }
 
 /// The options for a window.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Default)]
 pub struct TrackedWindowOptions {
     /// Should the window be vsynced. Check github issues to see if this property actually does what it is supposed to.
     pub vsync: bool,
     /// Optionally sets the shader version for the window.
     pub shader: Option<egui_glow_async::ShaderVersion>,
+    /// Should the window be transparent. This selects an opengl configuration with an alpha channel and creates a transparent window.
+    /// On X11 this requires a running compositor. On Wayland and Windows, parts of the window drawn with alpha show the desktop.
+    /// On macos the window must also be undecorated for the shadow to match the transparent contents.
+    pub transparent: bool,
+    /// The number of bits requested for the depth buffer of the window. The depth buffer is cleared before every frame.
+    pub depth_bits: Option<u8>,
+    /// The number of bits requested for the stencil buffer of the window. The stencil buffer is cleared before every frame.
+    pub stencil_bits: Option<u8>,
+    /// The preferred opengl context for the window. The default context of the display is used when not set.
+    pub gl_context: Option<GlContextPreference>,
+    /// Contexts to try, in order, when the preferred opengl context can not be created
+    pub gl_context_fallbacks: &'static [GlContextPreference],
+    /// Picks the opengl configuration to try first, returning an index into the list of configurations that match the other options.
+    /// The remaining configurations are tried afterwards, in order of increasing sample count.
+    pub config_picker: Option<fn(&[glutin::config::Config]) -> usize>,
+    /// Controls the srgb conversion of the framebuffer for custom opengl rendering. egui always draws with srgb conversion disabled.
+    pub srgb: SrgbMode,
+    /// The swap mode of the window. When not set, the vsync option selects between waiting for one vertical sync and not waiting.
+    pub swap_mode: Option<SwapMode>,
+    /// Limits the repaints of the window while it is in the background. When not set, the policy of the `MultiWindow` is used.
+    pub repaint_throttle: Option<RepaintThrottle>,
 }
 
-#[derive(Error, Debug)]
-/// Enumerates the kinds of errors that display creation can have.
-pub enum DisplayCreationError {}
+impl TrackedWindowOptions {
+    /// Get the swap mode selected by the options
+    pub fn swap_mode(&self) -> SwapMode {
+        self.swap_mode.unwrap_or(if self.vsync {
+            SwapMode::Wait(NonZeroU32::MIN)
+        } else {
+            SwapMode::Immediate
+        })
+    }
+}
+
+/// Determines how a window waits for vertical sync when presenting a frame
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SwapMode {
+    /// Present frames without waiting for vertical sync
+    Immediate,
+    /// Wait for the specified number of vertical syncs between frames. A value of 2 gives half rate vsync.
+    Wait(NonZeroU32),
+    /// Wait for vertical sync, allowing late frames to be presented immediately. glutin does not expose adaptive vsync,
+    /// so this currently waits for one vertical sync on every platform.
+    Adaptive,
+}
+
+impl SwapMode {
+    /// Get the glutin swap interval for the swap mode
+    pub fn interval(&self) -> glutin::surface::SwapInterval {
+        match self {
+            SwapMode::Immediate => glutin::surface::SwapInterval::DontWait,
+            SwapMode::Wait(n) => glutin::surface::SwapInterval::Wait(*n),
+            SwapMode::Adaptive => glutin::surface::SwapInterval::Wait(NonZeroU32::MIN),
+        }
+    }
+}
+
+/// Controls the srgb conversion of the framebuffer of a window while the window runs the custom opengl rendering
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SrgbMode {
+    /// Request an srgb capable surface and always enable `FRAMEBUFFER_SRGB`
+    #[default]
+    ForceEnabled,
+    /// Never enable `FRAMEBUFFER_SRGB`, for rendering that already outputs srgb and for drivers that do not support it
+    Disabled,
+    /// Enable `FRAMEBUFFER_SRGB` when the chosen configuration is srgb capable
+    Auto,
+}
+
+impl SrgbMode {
+    /// Returns true when srgb conversion should be used with a configuration
+    pub fn enabled(&self, config: &glutin::config::Config) -> bool {
+        use glutin::prelude::GlConfig;
+        match self {
+            SrgbMode::ForceEnabled => true,
+            SrgbMode::Disabled => false,
+            SrgbMode::Auto => config.srgb_capable(),
+        }
+    }
+}
+
+/// The opengl api of a context
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GlApi {
+    /// Desktop opengl
+    OpenGl,
+    /// Opengl ES
+    Gles,
+}
+
+/// The opengl profile of a context
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GlProfile {
+    /// The core profile
+    Core,
+    /// The compatibility profile
+    Compatibility,
+}
+
+/// Describes an opengl context to request for a window
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GlContextPreference {
+    /// The api of the context
+    pub api: GlApi,
+    /// The version (major, minor) of the context. The latest version available is used when not set.
+    pub version: Option<(u8, u8)>,
+    /// The profile of the context, only used for desktop opengl
+    pub profile: Option<GlProfile>,
+}
+
+impl GlContextPreference {
+    /// Build the glutin context attributes for the preference
+    pub fn attributes(
+        &self,
+        rwh: Option<raw_window_handle_5::RawWindowHandle>,
+    ) -> glutin::context::ContextAttributes {
+        self.builder().build(rwh)
+    }
+
+    /// Build the glutin context attributes builder for the preference
+    pub fn builder(&self) -> glutin::context::ContextAttributesBuilder {
+        let version = self
+            .version
+            .map(|(major, minor)| glutin::context::Version::new(major, minor));
+        let api = match self.api {
+            GlApi::OpenGl => glutin::context::ContextApi::OpenGl(version),
+            GlApi::Gles => glutin::context::ContextApi::Gles(version),
+        };
+        let mut builder = glutin::context::ContextAttributesBuilder::new().with_context_api(api);
+        if let Some(profile) = self.profile {
+            builder = builder.with_profile(match profile {
+                GlProfile::Core => glutin::context::GlProfile::Core,
+                GlProfile::Compatibility => glutin::context::GlProfile::Compatibility,
+            });
+        }
+        builder
+    }
+}
+
+/// Build the list of context attributes to try for a window, in order of preference. The contexts share objects with the share context when one is given.
+pub fn context_attributes(
+    options: &TrackedWindowOptions,
+    rwh: Option<raw_window_handle_5::RawWindowHandle>,
+    share: Option<&NotCurrentContext>,
+) -> Vec<glutin::context::ContextAttributes> {
+    let mut builders: Vec<glutin::context::ContextAttributesBuilder> = options
+        .gl_context
+        .iter()
+        .chain(options.gl_context_fallbacks.iter())
+        .map(|p| p.builder())
+        .collect();
+    if builders.is_empty() {
+        builders.push(glutin::context::ContextAttributesBuilder::new());
+    }
+    builders
+        .into_iter()
+        .map(|b| match share {
+            Some(share) => b.with_sharing(share),
+            None => b,
+        })
+        .map(|b| b.build(rwh))
+        .collect()
+}
+
+/// The opengl context that the contexts of windows share objects with, when sharing is enabled. Objects that can be shared include
+/// textures, buffers, renderbuffers, shaders and programs. Container objects such as vertex arrays and framebuffers are never shared.
+/// egui texture ids are specific to the painter of each window, a shared texture must be registered with
+/// [register_native_texture](egui_glow_async::Painter::register_native_texture) in every window that displays it.
+#[derive(Clone, Default)]
+pub struct SharedGlContext {
+    /// True when contexts are shared, and the context that is shared with. The context is created along with the first window.
+    i: Arc<Mutex<(bool, Option<NotCurrentContext>)>>,
+}
+
+impl SharedGlContext {
+    /// Enable or disable sharing for windows created after this call
+    pub fn set_enabled(&self, enabled: bool) {
+        self.i.lock_recover().0 = enabled;
+    }
+
+    /// Returns true when sharing is enabled
+    pub fn enabled(&self) -> bool {
+        self.i.lock_recover().0
+    }
+
+    /// Create the context for a window. Returns the context and true when the context shares objects with the contexts of other windows.
+    /// When sharing is enabled but not possible, such as for a window on a different display or adapter, an isolated context is created
+    /// and a warning is logged.
+    pub fn create_context(
+        &self,
+        display: &glutin::display::Display,
+        config: &glutin::config::Config,
+        options: &TrackedWindowOptions,
+        rwh: Option<raw_window_handle_5::RawWindowHandle>,
+    ) -> Option<(NotCurrentContext, bool)> {
+        let create = |share: Option<&NotCurrentContext>| {
+            context_attributes(options, rwh, share)
+                .iter()
+                .find_map(|attr| unsafe { display.create_context(config, attr) }.ok())
+        };
+        let mut i = self.i.lock_recover();
+        if i.0 {
+            if i.1.is_none() {
+                i.1 = create(None);
+            }
+            if let Some(context) = i.1.as_ref().and_then(|share| create(Some(share))) {
+                return Some((context, true));
+            }
+            crate::trace::warn(format_args!("Unable to share the opengl context of a window, it cannot use objects from other windows"));
+        }
+        create(None).map(|context| (context, false))
+    }
+}
+
+/// A setting shared with every window. The generation of the setting is incremented on every change, allowing windows to detect
+/// that the setting needs to be applied again.
+pub struct SharedSetting<T> {
+    /// The generation and value of the setting
+    i: Arc<Mutex<(u64, T)>>,
+}
+
+impl<T> Clone for SharedSetting<T> {
+    fn clone(&self) -> Self {
+        Self { i: self.i.clone() }
+    }
+}
+
+impl<T: Clone> SharedSetting<T> {
+    /// Construct a new setting
+    pub fn new(value: T) -> Self {
+        Self {
+            i: Arc::new(Mutex::new((0, value))),
+        }
+    }
+
+    /// Replace the value of the setting
+    pub fn set(&self, value: T) {
+        let mut i = self.i.lock_recover();
+        i.0 += 1;
+        i.1 = value;
+    }
+
+    /// Modify the value of the setting
+    pub fn modify(&self, f: impl FnOnce(&mut T)) {
+        let mut i = self.i.lock_recover();
+        i.0 += 1;
+        f(&mut i.1);
+    }
+
+    /// Get the current generation and value of the setting
+    pub fn get(&self) -> (u64, T) {
+        self.i.lock_recover().clone()
+    }
+
+    /// Get the current generation and value of the setting, if the setting has changed since the specified generation.
+    /// A generation of None means the setting has never been applied.
+    pub fn changed_since(&self, generation: Option<u64>) -> Option<(u64, T)> {
+        let i = self.i.lock_recover();
+        if Some(i.0) != generation {
+            Some(i.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// The theme preference for all windows of the application
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ThemePreference {
+    /// Always use dark mode
+    Dark,
+    /// Always use light mode
+    Light,
+    /// Follow the dark or light mode setting of the operating system
+    #[default]
+    System,
+}
+
+impl ThemePreference {
+    /// Returns true if dark mode should be used, given the theme reported by the operating system
+    pub fn is_dark(&self, system: Option<async_winit::window::Theme>) -> bool {
+        match self {
+            ThemePreference::Dark => true,
+            ThemePreference::Light => false,
+            ThemePreference::System => system != Some(async_winit::window::Theme::Light),
+        }
+    }
+
+    /// Apply the theme to an egui context. Only the visuals are changed, and only when switching between dark and light mode,
+    /// so other customizations to the style are preserved.
+    pub fn apply(&self, ctx: &egui::Context, system: Option<async_winit::window::Theme>) {
+        let dark = self.is_dark(system);
+        if ctx.style().visuals.dark_mode != dark {
+            ctx.style_mut(|s| {
+                s.visuals = if dark {
+                    egui::Visuals::dark()
+                } else {
+                    egui::Visuals::light()
+                };
+            });
+        }
+    }
+}
+
+/// Commands that a window can issue to modify itself. These are applied after the frame that requested them.
+#[derive(Clone, Debug)]
+pub enum WindowCommand {
+    /// Set the zoom factor of the window. This is multiplied with the native scale factor of the window.
+    SetZoomFactor(f32),
+    /// Change the swap mode of the window, taking effect on the next frame
+    SetSwapMode(SwapMode),
+    /// Show or hide the window. A hidden root window keeps the application running, so an application with a tray icon can hide
+    /// its windows and show them again from the tray.
+    SetVisible(bool),
+    /// Move the window to another monitor, keeping its placement relative to the monitor
+    MoveToMonitor(crate::monitor::MonitorSelector),
+    /// Show the window fullscreen, or leave fullscreen with None. The position and size of the window are restored when it leaves fullscreen.
+    SetFullscreen(Option<crate::monitor::FullscreenMode>),
+    /// Show or hide the title bar and borders of the window
+    SetDecorations(bool),
+    /// Allow or prevent resizing the window by the user
+    SetResizable(bool),
+    /// Start moving the window with the mouse, such as from a custom title bar. Only works while a mouse button is held down.
+    StartDrag,
+    /// Start resizing the window with the mouse from an edge or corner. Only works while a mouse button is held down.
+    StartResize(egui::viewport::ResizeDirection),
+    /// Confine or lock the cursor to the window, or release it. Locking falls back to confining on platforms that cannot lock the
+    /// cursor. The cursor is released when the window loses focus. While the cursor is grabbed, the window receives raw mouse motion.
+    SetCursorGrab(egui::viewport::CursorGrab),
+    /// Show or hide the cursor while it is over the window. The cursor is shown again when the window loses focus.
+    SetCursorVisible(bool),
+    /// Set the opacity of the whole window, from 0.0 (invisible) to 1.0 (opaque). This works with an opaque opengl configuration,
+    /// unlike per pixel transparency. Platforms without support ignore it, see [crate::registry::WindowInfo::opacity_supported].
+    SetOpacity(f32),
+    /// Let the mouse pass through the window (true) to the windows underneath, or let the window receive the mouse again (false).
+    /// Together with a transparent window that is always on top, this makes an overlay that does not get in the way of other
+    /// applications. Keyboard input still goes to the window while it has focus.
+    SetMousePassthrough(bool),
+    /// Let the window receive keyboard and pointer input (true), or ignore it while the window keeps drawing (false), such as while a
+    /// modal operation runs in another window. A window without input shows no hover effects.
+    SetInputEnabled(bool),
+    /// Capture the next frame of the window, including the custom opengl rendering, and send it into the channel. A channel that is
+    /// full or closed when the frame is captured misses the frame, see [crate::screenshot].
+    CaptureFrame(async_channel::Sender<Arc<egui::ColorImage>>),
+}
+
+/// The contents of the last frame drawn by a window, used to skip drawing frames that would look the same
+/// and to reuse the tessellation of shapes that did not change.
+#[derive(Default)]
+pub struct LastFrame {
+    /// The pixels per point of the frame
+    pixels_per_point: f32,
+    /// The size of the window in pixels
+    size: (u32, u32),
+    /// The shapes drawn in the frame
+    shapes: Vec<egui::epaint::ClippedShape>,
+    /// The tessellated shapes, None when the shapes have not been tessellated yet
+    primitives: Option<Vec<egui::ClippedPrimitive>>,
+}
+
+impl LastFrame {
+    /// Returns true when a frame would look different from the last frame drawn. The frame is remembered for the next call.
+    pub fn changed(
+        &mut self,
+        pixels_per_point: f32,
+        size: (u32, u32),
+        shapes: &[egui::epaint::ClippedShape],
+        textures: &egui::TexturesDelta,
+    ) -> bool {
+        let same_shapes = self.pixels_per_point == pixels_per_point && self.shapes == shapes;
+        if !same_shapes {
+            self.pixels_per_point = pixels_per_point;
+            self.shapes = shapes.to_vec();
+            self.primitives = None;
+        }
+        if same_shapes && textures.is_empty() && self.size == size {
+            return false;
+        }
+        self.size = size;
+        true
+    }
+
+    /// The tessellated shapes of the frame. The shapes are only tessellated again when they changed since the last call.
+    pub fn primitives(&mut self, ctx: &egui::Context) -> &[egui::ClippedPrimitive] {
+        let (shapes, pixels_per_point) = (&self.shapes, self.pixels_per_point);
+        self.primitives
+            .get_or_insert_with(|| ctx.tessellate(shapes.clone(), pixels_per_point))
+    }
+}
+
+/// Counts the frames drawn by a window, and the frames skipped because they would look the same as the last frame.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FrameStats {
+    /// The number of frames drawn
+    pub drawn: u64,
+    /// The number of frames skipped
+    pub skipped: u64,
+    /// The total time spent in frames, including making the opengl context current
+    pub frame_time: std::time::Duration,
+}
+
+impl FrameStats {
+    /// The id used to store the statistics in the egui context of the window
+    fn id() -> egui::Id {
+        egui::Id::new("egui_multiwin_frame_stats")
+    }
+
+    /// Get the frame statistics of the window that owns an egui context
+    pub fn get(ctx: &egui::Context) -> Self {
+        ctx.data(|d| d.get_temp(Self::id()).unwrap_or_default())
+    }
+
+    /// Count a frame of the window that owns an egui context
+    pub fn record(ctx: &egui::Context, drawn: bool) {
+        ctx.data_mut(|d| {
+            let stats = d.get_temp_mut_or_default::<Self>(Self::id());
+            if drawn {
+                stats.drawn += 1;
+            } else {
+                stats.skipped += 1;
+            }
+        });
+    }
+
+    /// Add the time spent in a frame of the window that owns an egui context
+    pub fn record_time(ctx: &egui::Context, time: std::time::Duration) {
+        ctx.data_mut(|d| d.get_temp_mut_or_default::<Self>(Self::id()).frame_time += time);
+    }
+
+    /// The average time spent in a frame
+    pub fn average_frame_time(&self) -> std::time::Duration {
+        let frames = (self.drawn + self.skipped).max(1);
+        self.frame_time / frames as u32
+    }
+}
+
+/// Limits how often a window repaints while it does not have focus and the mouse is not over it, so that background windows with
+/// animations or frequent repaint requests do not use a full core. Repaints requested sooner are delayed, not dropped, so animations
+/// keep running at the lower rate. A window returns to the full rate as soon as it gains focus or the mouse enters it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RepaintThrottle {
+    /// Background windows repaint as often as focused windows
+    Unlimited,
+    /// Background windows repaint at most this many times per second
+    MaxFps(NonZeroU32),
+}
+
+impl Default for RepaintThrottle {
+    fn default() -> Self {
+        Self::MaxFps(NonZeroU32::new(10).unwrap())
+    }
+}
+
+impl RepaintThrottle {
+    /// The shortest time between two frames of a throttled window, None when unlimited
+    pub fn interval(&self) -> Option<std::time::Duration> {
+        match self {
+            Self::Unlimited => None,
+            Self::MaxFps(fps) => Some(std::time::Duration::from_secs(1) / fps.get()),
+        }
+    }
+
+    /// The id used to store the throttle state in the egui context of the window
+    fn id() -> egui::Id {
+        egui::Id::new("egui_multiwin_throttled")
+    }
+
+    /// Returns true when the window that owns an egui context is currently throttled. Applications can use this to slow down
+    /// expensive updates that only matter while the window is in the foreground.
+    pub fn is_throttled(ctx: &egui::Context) -> bool {
+        ctx.data(|d| d.get_temp(Self::id()).unwrap_or(false))
+    }
+
+    /// Store the throttle state of the window that owns an egui context
+    pub fn set_throttled(ctx: &egui::Context, throttled: bool) {
+        ctx.data_mut(|d| d.insert_temp(Self::id(), throttled));
+    }
+}
+
+/// Tracks the focus and hover state of a window, and schedules the repaints of the window while it is throttled
+#[derive(Default)]
+pub struct ThrottleState {
+    /// True when the window has focus
+    focused: bool,
+    /// True when the mouse is over the window
+    hovered: bool,
+    /// When the last frame was drawn
+    last_frame: Option<std::time::Instant>,
+    /// When the window should be repainted next, if a repaint is scheduled
+    next_repaint: Option<std::time::Instant>,
+    /// The shortest time between frames, even while the window has focus
+    frame_limit: Option<std::time::Duration>,
+}
+
+impl ThrottleState {
+    /// Limit how often the window repaints, even while it has focus, such as to save power
+    pub fn set_frame_limit(&mut self, limit: RepaintThrottle) {
+        self.frame_limit = limit.interval();
+    }
+
+    /// Record a change of the focus of the window. Returns true when the window should repaint because it is no longer throttled.
+    pub fn set_focused(&mut self, focused: bool) -> bool {
+        self.focused = focused;
+        self.unthrottled()
+    }
+
+    /// Record the mouse entering (true) or leaving (false) the window. Returns true when the window should repaint because it is no
+    /// longer throttled.
+    pub fn set_hovered(&mut self, hovered: bool) -> bool {
+        self.hovered = hovered;
+        self.unthrottled()
+    }
+
+    /// Returns true when the window is not throttled and a delayed repaint was scheduled, which is cancelled
+    fn unthrottled(&mut self) -> bool {
+        (self.focused || self.hovered) && self.next_repaint.take().is_some()
+    }
+
+    /// The shortest time between frames of the window under a policy for windows in the background, None when the window may repaint
+    /// as often as it asks
+    pub fn interval(&self, policy: RepaintThrottle) -> Option<std::time::Duration> {
+        let background = if self.focused || self.hovered {
+            None
+        } else {
+            policy.interval()
+        };
+        background.max(self.frame_limit)
+    }
+
+    /// Returns true when the window is in the background and throttled under a policy
+    pub fn throttled(&self, policy: RepaintThrottle) -> bool {
+        !(self.focused || self.hovered) && policy.interval().is_some()
+    }
+
+    /// Check if a redraw requested at `now` may be drawn. A redraw that comes too soon after the last frame is scheduled for later
+    /// and false is returned.
+    pub fn allow_redraw(&mut self, now: std::time::Instant, policy: RepaintThrottle) -> bool {
+        let earliest = match (self.interval(policy), self.last_frame) {
+            (Some(interval), Some(last)) => last + interval,
+            _ => return true,
+        };
+        if now >= earliest {
+            return true;
+        }
+        self.schedule(earliest);
+        false
+    }
+
+    /// Record a frame drawn at `now`, scheduling the repaint that egui asked for. A throttled window repaints no sooner than the
+    /// interval of the policy.
+    pub fn frame_drawn(
+        &mut self,
+        now: std::time::Instant,
+        repaint_after: Option<std::time::Duration>,
+        policy: RepaintThrottle,
+    ) {
+        self.last_frame = Some(now);
+        self.next_repaint = None;
+        let Some(after) = repaint_after else {
+            return;
+        };
+        let after = match self.interval(policy) {
+            Some(interval) => after.max(interval),
+            None => after,
+        };
+        // egui asks for a repaint after a very long time when it does not need one
+        if let Some(at) = now.checked_add(after) {
+            self.schedule(at);
+        }
+    }
+
+    /// Schedule a repaint asked for at `now` with a delay, such as from another thread. The repaint is still throttled when it is due.
+    pub fn request_repaint(&mut self, now: std::time::Instant, delay: std::time::Duration) {
+        if let Some(at) = now.checked_add(delay) {
+            self.schedule(at);
+        }
+    }
+
+    /// Schedule a repaint, keeping an earlier repaint that is already scheduled
+    fn schedule(&mut self, at: std::time::Instant) {
+        self.next_repaint = Some(self.next_repaint.map_or(at, |n| n.min(at)));
+    }
+
+    /// When the window should be repainted next, if a repaint is scheduled
+    pub fn next_repaint(&self) -> Option<std::time::Instant> {
+        self.next_repaint
+    }
+
+    /// Take the scheduled repaint, after it is due
+    pub fn take_repaint(&mut self) {
+        self.next_repaint = None;
+    }
+}
+
+/// Queues file hover and drop events for a window until the next frame begins.
+#[derive(Default)]
+pub struct FileDropQueue {
+    /// The files currently hovering over the window
+    hovered: Vec<PathBuf>,
+    /// The files dropped on the window since the last frame
+    dropped: Vec<PathBuf>,
+}
+
+impl FileDropQueue {
+    /// Register the file hover and drop handlers for a window. Dropped files trigger a redraw of the window.
+    pub fn register(
+        queue: &Arc<Mutex<Self>>,
+        window: &Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
+    ) {
+        let q = queue.clone();
+        let w = window.clone();
+        window.hovered_file().wait_direct_async(move |path| {
+            let q = q.clone();
+            let w = w.clone();
+            let path = path.clone();
+            async move {
+                q.lock_recover().hovered.push(path);
+                w.request_redraw();
+                false
+            }
+        });
+        let q = queue.clone();
+        let w = window.clone();
+        window.hovered_file_cancelled().wait_direct_async(move |_| {
+            let q = q.clone();
+            let w = w.clone();
+            async move {
+                q.lock_recover().hovered.clear();
+                w.request_redraw();
+                false
+            }
+        });
+        let q = queue.clone();
+        let w = window.clone();
+        window.dropped_file().wait_direct_async(move |path| {
+            let q = q.clone();
+            let w = w.clone();
+            let path = path.clone();
+            async move {
+                let mut q = q.lock_recover();
+                q.hovered.clear();
+                q.dropped.push(path);
+                drop(q);
+                w.request_redraw();
+                false
+            }
+        });
+    }
+
+    /// Place the queued files into the input for egui. Dropped files are only delivered once.
+    pub fn apply(&mut self, input: &mut egui::RawInput) {
+        input.hovered_files = self
+            .hovered
+            .iter()
+            .map(|p| egui::HoveredFile {
+                path: Some(p.clone()),
+                ..Default::default()
+            })
+            .collect();
+        input
+            .dropped_files
+            .extend(self.dropped.drain(..).map(|p| egui::DroppedFile {
+                path: Some(p),
+                ..Default::default()
+            }));
+    }
+}
+
+/// Tracks the ime (input method editor) state of a window, allowing composed text input for languages like Japanese.
+#[derive(Default)]
+pub struct ImeState {
+    /// True when the window currently allows ime input
+    allowed: bool,
+    /// True when an ime composition has been reported to egui as enabled
+    enabled: bool,
+    /// The egui events generated by the ime since the last frame
+    events: Vec<egui::Event>,
+}
+
+impl ImeState {
+    /// Register the ime event handler for a window. Ime events trigger a redraw of the window.
+    pub fn register(
+        state: &Arc<Mutex<Self>>,
+        window: &Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
+    ) {
+        let st = state.clone();
+        let w = window.clone();
+        window.ime().wait_direct_async(move |ime| {
+            let st = st.clone();
+            let w = w.clone();
+            let ime = ime.clone();
+            async move {
+                st.lock_recover().process(ime);
+                w.request_redraw();
+                false
+            }
+        });
+    }
+
+    /// Convert an ime event into egui events
+    fn process(&mut self, ime: async_winit::event::Ime) {
+        match ime {
+            async_winit::event::Ime::Enabled => {
+                self.enable();
+            }
+            async_winit::event::Ime::Preedit(text, Some(_cursor)) => {
+                self.enable();
+                self.events
+                    .push(egui::Event::Ime(egui::ImeEvent::Preedit(text)));
+            }
+            async_winit::event::Ime::Commit(text) => {
+                self.enable();
+                self.events.push(egui::Event::Ime(egui::ImeEvent::Commit(text)));
+                self.disable();
+            }
+            async_winit::event::Ime::Disabled | async_winit::event::Ime::Preedit(_, None) => {
+                self.disable();
+            }
+        }
+    }
+
+    /// Report the ime as enabled to egui, if it is not already enabled
+    fn enable(&mut self) {
+        if !self.enabled {
+            self.events.push(egui::Event::Ime(egui::ImeEvent::Enabled));
+            self.enabled = true;
+        }
+    }
+
+    /// Report the ime as disabled to egui, if it is not already disabled
+    fn disable(&mut self) {
+        if self.enabled {
+            self.events.push(egui::Event::Ime(egui::ImeEvent::Disabled));
+            self.enabled = false;
+        }
+    }
+
+    /// Place the queued ime events into the input for egui.
+    pub fn apply(&mut self, input: &mut egui::RawInput) {
+        input.events.append(&mut self.events);
+    }
+
+    /// Allow or disallow ime input on the window depending on whether egui wants text input,
+    /// and move the ime candidate window next to the text cursor.
+    pub async fn update_window(
+        state: &Arc<Mutex<Self>>,
+        window: &async_winit::window::Window<async_winit::ThreadSafe>,
+        ime: Option<egui::output::IMEOutput>,
+        pixels_per_point: f32,
+    ) {
+        let allow = ime.is_some();
+        let changed = {
+            let mut s = state.lock_recover();
+            let changed = s.allowed != allow;
+            s.allowed = allow;
+            changed
+        };
+        if changed {
+            window.set_ime_allowed(allow).await;
+        }
+        if let Some(ime) = ime {
+            let rect = ime.cursor_rect;
+            window
+                .set_ime_cursor_area(
+                    async_winit::dpi::PhysicalPosition {
+                        x: pixels_per_point * rect.min.x,
+                        y: pixels_per_point * rect.min.y,
+                    },
+                    async_winit::dpi::PhysicalSize {
+                        width: pixels_per_point * rect.width(),
+                        height: pixels_per_point * rect.height(),
+                    },
+                )
+                .await;
+        }
+    }
+}
+
+/// A touch reported by a window, in physical pixels
+#[derive(Clone, Debug)]
+struct TouchPoint {
+    /// The touch device, such as a touch screen or a touch pad
+    device: u64,
+    /// The identifier of the finger, unique while the finger touches the device
+    id: u64,
+    /// The phase of the touch
+    phase: egui::TouchPhase,
+    /// The position of the touch
+    position: (f64, f64),
+    /// The normalized pressure of the touch, if the device reports pressure
+    force: Option<f32>,
+}
+
+/// Queues touch events for a window until the next frame begins. Every touch is delivered to egui as a touch event, which egui uses
+/// for gestures such as pinch zoom. The first finger touching the window also acts as the mouse pointer, so taps click and dragging
+/// scrolls.
+#[derive(Default)]
+pub struct TouchState {
+    /// The touches since the last frame
+    pending: Vec<TouchPoint>,
+    /// The finger acting as the mouse pointer
+    pointer_touch: Option<u64>,
+}
+
+impl TouchState {
+    /// Register the touch handler for a window. Touches trigger a redraw of the window.
+    pub fn register(
+        state: &Arc<Mutex<Self>>,
+        window: &Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
+    ) {
+        let st = state.clone();
+        let w = window.clone();
+        window.touch().wait_direct_async(move |touch| {
+            let st = st.clone();
+            let w = w.clone();
+            let point = TouchPoint {
+                device: {
+                    use std::hash::{Hash, Hasher};
+                    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                    touch.device_id.hash(&mut hasher);
+                    hasher.finish()
+                },
+                id: touch.id,
+                phase: match touch.phase {
+                    async_winit::event::TouchPhase::Started => egui::TouchPhase::Start,
+                    async_winit::event::TouchPhase::Moved => egui::TouchPhase::Move,
+                    async_winit::event::TouchPhase::Ended => egui::TouchPhase::End,
+                    async_winit::event::TouchPhase::Cancelled => egui::TouchPhase::Cancel,
+                },
+                position: (touch.location.x, touch.location.y),
+                force: touch.force.map(|f| f.normalized() as f32),
+            };
+            async move {
+                st.lock_recover().pending.push(point);
+                w.request_redraw();
+                false
+            }
+        });
+    }
+
+    /// Place the queued touches into the input for egui, converting the positions to points
+    pub fn apply(&mut self, input: &mut egui::RawInput, pixels_per_point: f32) {
+        let modifiers = input.modifiers;
+        for t in self.pending.drain(..) {
+            let pos = egui::pos2(
+                t.position.0 as f32 / pixels_per_point,
+                t.position.1 as f32 / pixels_per_point,
+            );
+            input.events.push(egui::Event::Touch {
+                device_id: egui::TouchDeviceId(t.device),
+                id: egui::TouchId::from(t.id),
+                phase: t.phase,
+                pos,
+                force: t.force,
+            });
+            let is_pointer = self.pointer_touch == Some(t.id);
+            let button = |pressed| egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed,
+                modifiers,
+            };
+            match t.phase {
+                egui::TouchPhase::Start if self.pointer_touch.is_none() => {
+                    self.pointer_touch = Some(t.id);
+                    input.events.push(egui::Event::PointerMoved(pos));
+                    input.events.push(button(true));
+                }
+                egui::TouchPhase::Move if is_pointer => {
+                    input.events.push(egui::Event::PointerMoved(pos));
+                }
+                egui::TouchPhase::End if is_pointer => {
+                    self.pointer_touch = None;
+                    input.events.push(button(false));
+                    input.events.push(egui::Event::PointerGone);
+                }
+                egui::TouchPhase::Cancel if is_pointer => {
+                    self.pointer_touch = None;
+                    input.events.push(egui::Event::PointerGone);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Remove the pointer input of a frame, for a window that lets the mouse pass through. The pointer is reported as gone, so that egui
+/// does not keep showing hover effects or repainting for a pointer the window no longer receives.
+pub fn remove_pointer_input(input: &mut egui::RawInput) {
+    input.events.retain(|e| {
+        !matches!(
+            e,
+            egui::Event::PointerMoved(_)
+                | egui::Event::PointerButton { .. }
+                | egui::Event::PointerGone
+                | egui::Event::MouseMoved(_)
+                | egui::Event::Scroll(_)
+                | egui::Event::Zoom(_)
+                | egui::Event::Touch { .. }
+        )
+    });
+    input.events.push(egui::Event::PointerGone);
+}
+
+/// Remove the keyboard and pointer input of a frame, for a window that ignores input. The time, size and pixels per point of the frame
+/// are kept. Key releases are kept so that keys held down when the input was disabled are not stuck, and the pointer is reported as
+/// gone.
+pub fn remove_input(input: &mut egui::RawInput) {
+    input.events.retain(|e| {
+        matches!(
+            e,
+            egui::Event::Key { pressed: false, .. }
+                | egui::Event::WindowFocused(_)
+                | egui::Event::Screenshot { .. }
+        )
+    });
+    input.events.push(egui::Event::PointerGone);
+    input.modifiers = egui::Modifiers::default();
+    input.hovered_files.clear();
+    input.dropped_files.clear();
+}
+
+/// Fill the egui viewport information with the current state of a window. egui uses this information to keep popups
+/// and tooltips inside of the window and the monitor.
+pub async fn update_viewport_info(
+    info: &mut egui::ViewportInfo,
+    window: &async_winit::window::Window<async_winit::ThreadSafe>,
+    pixels_per_point: f32,
+    focused: bool,
+) {
+    /// Convert a physical position and size into a rectangle in points
+    fn to_rect(
+        pos: async_winit::dpi::PhysicalPosition<i32>,
+        size: async_winit::dpi::PhysicalSize<u32>,
+        pixels_per_point: f32,
+    ) -> egui::Rect {
+        egui::Rect::from_min_size(
+            egui::pos2(pos.x as f32, pos.y as f32),
+            egui::vec2(size.width as f32, size.height as f32),
+        ) / pixels_per_point
+    }
+
+    let inner_size = window.inner_size().await;
+    info.inner_rect = window
+        .inner_position()
+        .await
+        .ok()
+        .map(|pos| to_rect(pos, inner_size, pixels_per_point));
+    let outer_size = window.outer_size().await;
+    info.outer_rect = window
+        .outer_position()
+        .await
+        .ok()
+        .map(|pos| to_rect(pos, outer_size, pixels_per_point));
+    info.monitor_size = window.current_monitor().await.map(|m| {
+        let size = m.size();
+        egui::vec2(size.width as f32, size.height as f32) / pixels_per_point
+    });
+    info.native_pixels_per_point = Some(window.scale_factor().await as f32);
+    info.minimized = window.is_minimized().await;
+    info.maximized = Some(window.is_maximized().await);
+    info.focused = Some(focused);
+}
+
+/// Install the loaders built into the library on the egui context of a window. With the `image-loaders` feature, these are the
+/// image loaders of egui_extras, so that `ui.image("file://...")` shows images. Otherwise nothing is installed.
+pub fn install_loaders(ctx: &egui::Context) {
+    #[cfg(feature = "image-loaders")]
+    egui_extras::install_image_loaders(ctx);
+    #[cfg(not(feature = "image-loaders"))]
+    let _ = ctx;
+}
+
+/// Get the position of the mouse on the screen in physical pixels, from the egui context of a window. While a mouse button is held
+/// down, the window keeps receiving the position of the mouse after it leaves the window, so this follows a drag across the screen,
+/// such as a tab being dragged out of the window. Returns None when the mouse position is not known, or when the platform does not
+/// report the position of windows, such as wayland.
+pub fn screen_pointer_position(
+    ctx: &egui::Context,
+) -> Option<async_winit::dpi::PhysicalPosition<i32>> {
+    let (pointer, inner) = ctx.input(|i| (i.pointer.latest_pos(), i.viewport().inner_rect));
+    Some(to_screen(inner?.min, pointer?, ctx.pixels_per_point()))
+}
+
+/// Convert a position in a window into a position on the screen in physical pixels, given the top left corner of the inside of the
+/// window on the screen. Both positions are in points.
+fn to_screen(
+    inner: egui::Pos2,
+    pos: egui::Pos2,
+    pixels_per_point: f32,
+) -> async_winit::dpi::PhysicalPosition<i32> {
+    let screen = (inner + pos.to_vec2()) * pixels_per_point;
+    async_winit::dpi::PhysicalPosition::new(screen.x.round() as i32, screen.y.round() as i32)
+}
+
+/// Raw events for a window, delivered to the window before egui processes them.
+#[derive(Clone, Debug)]
+pub enum WindowEventDescriptor {
+    /// A keyboard event, including the key repeat information
+    KeyboardInput(async_winit::event::KeyEvent),
+    /// A mouse button was pressed or released. This includes buttons that egui ignores.
+    MouseInput {
+        /// Pressed or released
+        state: async_winit::event::ElementState,
+        /// The button
+        button: async_winit::event::MouseButton,
+    },
+    /// The window was moved to a new position
+    Moved(async_winit::dpi::PhysicalPosition<i32>),
+    /// The window was resized
+    Resized(async_winit::dpi::PhysicalSize<u32>),
+    /// The theme of the window changed
+    ThemeChanged(async_winit::window::Theme),
+    /// The window gained (true) or lost (false) focus
+    Focused(bool),
+    /// The window was hidden (true) or shown again (false), such as by being minimized or covered by other windows
+    Occluded(bool),
+    /// The application was suspended. The surfaces of all windows are destroyed until the application resumes. Only delivered on android.
+    Suspended,
+    /// The application resumed after being suspended. Only delivered on android.
+    Resumed,
+    /// The relative motion of the mouse, not limited by the edges of the window or screen. Only delivered while the window grabs the cursor.
+    RawMouseMotion(f64, f64),
+    /// The mouse entered the window
+    CursorEntered,
+    /// The mouse left the window
+    CursorLeft,
+}
+
+/// A raw window event waiting to be processed, along with the channel used to report if the event was consumed.
+pub type PendingWindowEvent = (WindowEventDescriptor, async_channel::Sender<bool>);
+
+/// The reasons the draw loop of a window wakes up
+pub enum DrawWake {
+    /// The window needs to be drawn
+    Redraw,
+    /// The user asked to close the window, such as with the close button of the title bar
+    CloseRequested,
+    /// An event arrived for the window
+    Event(PendingWindowEvent),
+    /// A repaint scheduled by egui or delayed by the repaint throttle is due
+    Repaint,
+    /// egui was asked to repaint the window after a delay, such as by a background task with `egui::Context::request_repaint`
+    RepaintRequested(std::time::Duration),
+}
+
+impl WindowEventDescriptor {
+    /// Returns true when the platform may have discarded the pixels of the window, so the next frame must be drawn even when it looks
+    /// the same as the last frame. This happens when the window is shown again after being minimized or covered, and on x11 without a
+    /// compositor when the window is moved, resized or raised. The platform asks for a redraw in all of these cases, but that redraw can
+    /// not be told apart from redraws requested by the application.
+    pub fn damages_pixels(&self) -> bool {
+        matches!(
+            self,
+            Self::Moved(_) | Self::Resized(_) | Self::Focused(true) | Self::Occluded(false)
+        )
+    }
+
+    /// Register the handlers that forward raw events of a window into a channel. The handlers wait for the event
+    /// to be processed, so the events stay in order relative to redraws.
+    pub fn register(
+        window: &Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
+        sender: async_channel::Sender<PendingWindowEvent>,
+    ) {
+        let s = sender.clone();
+        window.keyboard_input().wait_direct_async(move |k| {
+            forward(s.clone(), Self::KeyboardInput(k.event.clone()))
+        });
+        let s = sender.clone();
+        window.mouse_input().wait_direct_async(move |m| {
+            forward(
+                s.clone(),
+                Self::MouseInput {
+                    state: m.state,
+                    button: m.button,
+                },
+            )
+        });
+        let s = sender.clone();
+        window
+            .moved()
+            .wait_direct_async(move |p| forward(s.clone(), Self::Moved(*p)));
+        let s = sender.clone();
+        window
+            .resized()
+            .wait_direct_async(move |size| forward(s.clone(), Self::Resized(*size)));
+        let s = sender.clone();
+        window
+            .theme_changed()
+            .wait_direct_async(move |t| forward(s.clone(), Self::ThemeChanged(*t)));
+        let s = sender.clone();
+        window
+            .cursor_entered()
+            .wait_direct_async(move |_| forward(s.clone(), Self::CursorEntered));
+        let s = sender.clone();
+        window
+            .cursor_left()
+            .wait_direct_async(move |_| forward(s.clone(), Self::CursorLeft));
+        let s = sender.clone();
+        window
+            .focused()
+            .wait_direct_async(move |f| forward(s.clone(), Self::Focused(*f)));
+        let s = sender;
+        window
+            .occluded()
+            .wait_direct_async(move |o| forward(s.clone(), Self::Occluded(*o)));
+    }
+
+    /// Register the handlers that forward the suspend and resume events of the application to a window. Only android suspends
+    /// applications, so nothing is registered on other platforms and desktop windows never see these events.
+    pub fn register_lifecycle(
+        event_loop: &async_winit::event_loop::EventLoopWindowTarget<async_winit::ThreadSafe>,
+        sender: async_channel::Sender<PendingWindowEvent>,
+    ) {
+        if !cfg!(target_os = "android") {
+            return;
+        }
+        let s = sender.clone();
+        event_loop.suspended().wait_direct_async(move |_| {
+            let s = s.clone();
+            async move {
+                forward(s, Self::Suspended).await;
+                false
+            }
+        });
+        let s = sender;
+        event_loop.resumed().wait_direct_async(move |_| {
+            let s = s.clone();
+            async move {
+                forward(s, Self::Resumed).await;
+                false
+            }
+        });
+    }
+
+    /// Register the handler that forwards raw mouse motion to a window, while the window grabs the cursor
+    pub fn register_raw_motion(
+        event_loop: &async_winit::event_loop::EventLoopWindowTarget<async_winit::ThreadSafe>,
+        sender: async_channel::Sender<PendingWindowEvent>,
+        grabbed: Arc<std::sync::atomic::AtomicBool>,
+    ) {
+        event_loop
+            .device_event()
+            .wait_direct_async(move |(_, event)| {
+                let motion = match event {
+                    async_winit::event::DeviceEvent::MouseMotion { delta }
+                        if grabbed.load(std::sync::atomic::Ordering::Relaxed) =>
+                    {
+                        Some(Self::RawMouseMotion(delta.0, delta.1))
+                    }
+                    _ => None,
+                };
+                let s = sender.clone();
+                async move {
+                    if let Some(motion) = motion {
+                        forward(s, motion).await;
+                    }
+                    false
+                }
+            });
+    }
+}
+
+/// The sources that wake the draw loop of a window, see [DrawWake]. The redraw notifications coalesce, so a storm of redraw requests
+/// never blocks the event loop.
+pub struct DrawSignals {
+    /// The raw events of the window
+    events: async_channel::Receiver<PendingWindowEvent>,
+    /// Sends the repaints that egui asks for outside of a frame
+    repaint_sender: async_channel::Sender<std::time::Duration>,
+    /// Receives the repaints that egui asks for outside of a frame
+    repaints: async_channel::Receiver<std::time::Duration>,
+    /// The platform asked for a redraw
+    redraw_needed: crate::sync::Notify,
+    /// The redraw that the platform asked for was drawn
+    drawn: crate::sync::Notify,
+    /// The user asked to close the window
+    close: crate::sync::Notify,
+}
+
+impl DrawSignals {
+    /// Register the handlers of a new window that wake its draw loop. They are registered before egui registers its own handlers, so
+    /// the window sees the events first. The close button notifies `close`, except for viewport windows, which forward it to their
+    /// viewport and close when the parent window stops showing the viewport.
+    pub fn register(
+        id: WindowId,
+        window: &Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
+        event_loop: &async_winit::event_loop::EventLoopWindowTarget<async_winit::ThreadSafe>,
+        close: crate::sync::Notify,
+        cursor_grab: Arc<std::sync::atomic::AtomicBool>,
+        viewport: Option<Arc<Mutex<crate::viewport::ViewportState>>>,
+    ) -> Self {
+        let (event_sender, events) = async_channel::unbounded();
+        let (repaint_sender, repaints) = async_channel::unbounded();
+        WindowEventDescriptor::register_lifecycle(event_loop, event_sender.clone());
+        WindowEventDescriptor::register_raw_motion(event_loop, event_sender.clone(), cursor_grab);
+        WindowEventDescriptor::register(window, event_sender);
+        let is_viewport = viewport.is_some();
+        if let Some(state) = viewport {
+            window.close_requested().wait_direct_async(move |_| {
+                state.lock_recover().request_close();
+                async { false }
+            });
+        }
+        let c = close.clone();
+        window.close_requested().wait_direct_async(move |_| {
+            if !is_viewport {
+                // Notifying never blocks, even when the window has already stopped drawing
+                c.notify();
+                crate::trace::info(format_args!("Close requested for window {}", id));
+            }
+            async { false }
+        });
+        let redraw_needed = crate::sync::Notify::new();
+        let drawn = crate::sync::Notify::new();
+        let (r, d) = (redraw_needed.clone(), drawn.clone());
+        // This runs the drawing on the proper thread, preventing async-winit from trying to run two draw events at the same time
+        window.redraw_requested().wait_direct_async(move |_| {
+            let redraw = r.clone();
+            let drawn = d.clone();
+            async move {
+                // The window is closing when the notifications are closed
+                if redraw.notify() {
+                    drawn.wait().await;
+                }
+                true
+            }
+        });
+        Self {
+            events,
+            repaint_sender,
+            repaints,
+            redraw_needed,
+            drawn,
+            close,
+        }
+    }
+
+    /// The callback that sends the repaints egui asks for outside of a frame, such as from a background task, to the draw loop
+    pub fn repaint_callback(&self) -> impl Fn(egui::RequestRepaintInfo) + Send + Sync + 'static {
+        let requests = self.repaint_sender.clone();
+        move |r| {
+            let _ = requests.try_send(r.delay);
+        }
+    }
+
+    /// Wait for the next reason to draw or to handle an event. The first frame is drawn without waiting for the platform to ask for it,
+    /// so that a new window shows its contents as soon as possible, such as a torn off tab under the mouse.
+    pub async fn next(
+        &self,
+        throttle: &ThrottleState,
+        first_frame: bool,
+    ) -> Result<DrawWake, crate::Error> {
+        use futures_lite::FutureExt;
+        if first_frame {
+            return Ok(DrawWake::Redraw);
+        }
+        let redraw = async {
+            if self.redraw_needed.wait().await {
+                Ok(DrawWake::Redraw)
+            } else {
+                Err(crate::Error::ChannelClosed)
+            }
+        };
+        let close = async {
+            if self.close.wait().await {
+                Ok(DrawWake::CloseRequested)
+            } else {
+                Err(crate::Error::ChannelClosed)
+            }
+        };
+        let event = async {
+            self.events
+                .recv()
+                .await
+                .map(DrawWake::Event)
+                .map_err(crate::Error::from)
+        };
+        let next_repaint = throttle.next_repaint();
+        let repaint = async {
+            match next_repaint {
+                Some(at) => {
+                    async_winit::Timer::after(
+                        at.saturating_duration_since(std::time::Instant::now()),
+                    )
+                    .await;
+                    Ok(DrawWake::Repaint)
+                }
+                None => std::future::pending().await,
+            }
+        };
+        let requested = async {
+            self.repaints
+                .recv()
+                .await
+                .map(DrawWake::RepaintRequested)
+                .map_err(crate::Error::from)
+        };
+        redraw.or(close).or(event).or(repaint).or(requested).await
+    }
+
+    /// A frame was drawn, or skipped, for the redraw that the platform asked for
+    pub fn frame_done(&self) {
+        self.drawn.notify();
+    }
+
+    /// The draw loop ended. Redraw requests that arrive while the window closes return immediately.
+    pub fn close(&self) {
+        self.redraw_needed.close();
+        self.drawn.close();
+    }
+}
+
+/// Send an event to a window and wait for the window to report if it consumed the event
+async fn forward(
+    sender: async_channel::Sender<PendingWindowEvent>,
+    event: WindowEventDescriptor,
+) -> bool {
+    let (t, r) = async_channel::bounded(1);
+    if sender.send((event, t)).await.is_err() {
+        return false;
+    }
+    r.recv().await.unwrap_or(false)
+}
+
+/// The errors that can occur when creating a window
+pub type DisplayCreationError = crate::Error;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pointer_is_converted_to_the_screen() {
+        let p = to_screen(egui::pos2(100.0, 50.0), egui::pos2(10.5, -20.0), 2.0);
+        assert_eq!((p.x, p.y), (221, 60));
+    }
+
+    #[test]
+    fn background_repaints_are_delayed() {
+        let policy = RepaintThrottle::default();
+        let interval = policy.interval().unwrap();
+        let start = std::time::Instant::now();
+        let mut state = ThrottleState::default();
+        assert!(state.allow_redraw(start, policy));
+        state.frame_drawn(start, Some(std::time::Duration::ZERO), policy);
+        assert_eq!(state.next_repaint(), Some(start + interval));
+        assert!(!state.allow_redraw(start + interval / 2, policy));
+        assert!(state.allow_redraw(start + interval, policy));
+        // Focus cancels the delayed repaint and returns to the full rate
+        assert!(state.set_focused(true));
+        assert!(!state.throttled(policy));
+        state.frame_drawn(start, Some(std::time::Duration::ZERO), policy);
+        assert_eq!(state.next_repaint(), Some(start));
+        state.frame_drawn(start, Some(std::time::Duration::MAX), policy);
+        assert_eq!(state.next_repaint(), None);
+        assert!(!state.throttled(RepaintThrottle::Unlimited));
+        // The frame limit applies to focused windows too
+        state.set_frame_limit(RepaintThrottle::MaxFps(NonZeroU32::new(30).unwrap()));
+        assert!(!state.allow_redraw(start + interval / 10, policy));
+        assert!(!state.throttled(policy));
+    }
+
+    #[test]
+    fn first_finger_is_the_pointer() {
+        let mut touch = TouchState::default();
+        let mut input = egui::RawInput::default();
+        for (id, phase, x) in [
+            (1, egui::TouchPhase::Start, 20.0),
+            (2, egui::TouchPhase::Start, 60.0),
+            (2, egui::TouchPhase::Move, 80.0),
+            (1, egui::TouchPhase::End, 20.0),
+        ] {
+            touch.pending.push(TouchPoint {
+                device: 7,
+                id,
+                phase,
+                position: (x, 10.0),
+                force: None,
+            });
+        }
+        touch.apply(&mut input, 2.0);
+        let touches = input
+            .events
+            .iter()
+            .filter(|e| matches!(e, egui::Event::Touch { .. }))
+            .count();
+        assert_eq!(touches, 4);
+        let pointer: Vec<_> = input
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                egui::Event::PointerMoved(p) => Some(("move", p.x)),
+                egui::Event::PointerButton { pos, pressed, .. } => {
+                    Some((if *pressed { "press" } else { "release" }, pos.x))
+                }
+                egui::Event::PointerGone => Some(("gone", 0.0)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            pointer,
+            [
+                ("move", 10.0),
+                ("press", 10.0),
+                ("release", 10.0),
+                ("gone", 0.0)
+            ]
+        );
+        assert_eq!(touch.pointer_touch, None);
+    }
+
+    #[test]
+    fn passthrough_removes_the_pointer() {
+        let mut input = egui::RawInput {
+            events: vec![
+                egui::Event::PointerMoved(egui::pos2(1.0, 2.0)),
+                egui::Event::Text("a".to_string()),
+                egui::Event::Scroll(egui::vec2(0.0, 5.0)),
+            ],
+            ..Default::default()
+        };
+        remove_pointer_input(&mut input);
+        assert_eq!(
+            input.events,
+            [egui::Event::Text("a".to_string()), egui::Event::PointerGone]
+        );
+        let key = |pressed| egui::Event::Key {
+            key: egui::Key::A,
+            physical_key: None,
+            pressed,
+            repeat: false,
+            modifiers: egui::Modifiers::SHIFT,
+        };
+        input.events = vec![key(true), egui::Event::Text("a".to_string()), key(false)];
+        input.modifiers = egui::Modifiers::SHIFT;
+        input.time = Some(2.5);
+        remove_input(&mut input);
+        assert_eq!(input.events, [key(false), egui::Event::PointerGone]);
+        assert_eq!(input.modifiers, egui::Modifiers::default());
+        assert_eq!(input.time, Some(2.5));
+    }
+}
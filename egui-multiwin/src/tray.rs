@@ -0,0 +1,92 @@
+//! Contains the system tray icon of the application. Creating a tray icon requires the `tray` feature.
+//!
+//! On linux, the tray icon uses gtk. Gtk must be initialized on the thread that creates the tray icon, and a gtk main loop must run for
+//! the icon to appear. Click events on the icon itself are not reported on linux, only selections from the menu.
+
+/// The events reported by the tray icon
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrayEvent {
+    /// The tray icon was clicked with the left mouse button
+    Clicked,
+    /// The tray icon was double clicked
+    DoubleClicked,
+    /// An item of the tray menu was selected, identified by the id given to [Tray::new]
+    Menu(String),
+}
+
+/// The errors that can occur when creating a tray icon
+#[derive(thiserror::Error, Debug)]
+pub enum TrayError {
+    /// The icon image is invalid
+    #[cfg(feature = "tray")]
+    #[error("The tray icon is invalid: {0}")]
+    Icon(#[from] tray_icon::BadIcon),
+    /// The menu could not be created
+    #[cfg(feature = "tray")]
+    #[error("Unable to create the tray menu: {0}")]
+    Menu(#[from] tray_icon::menu::Error),
+    /// The tray icon could not be created
+    #[cfg(feature = "tray")]
+    #[error("Unable to create the tray icon: {0}")]
+    Tray(#[from] tray_icon::Error),
+}
+
+/// A tray icon with a menu. The icon is removed when the tray is dropped. Only one tray can report events in an application.
+pub struct Tray {
+    /// The tray icon
+    #[cfg(feature = "tray")]
+    _icon: tray_icon::TrayIcon,
+    /// Receives the events of the tray icon
+    events: async_channel::Receiver<TrayEvent>,
+}
+
+impl Tray {
+    /// Create a tray icon from rgba pixel data, 4 bytes per pixel. The menu is a list of ids and labels, the id is reported
+    /// with [TrayEvent::Menu] when the item is selected.
+    #[cfg(feature = "tray")]
+    pub fn new(
+        tooltip: &str,
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+        menu: &[(&str, &str)],
+    ) -> Result<Self, TrayError> {
+        let (sender, events) = async_channel::unbounded();
+        let s = sender.clone();
+        tray_icon::menu::MenuEvent::set_event_handler(Some(
+            move |e: tray_icon::menu::MenuEvent| {
+                let _ = s.try_send(TrayEvent::Menu(e.id.0));
+            },
+        ));
+        tray_icon::TrayIconEvent::set_event_handler(Some(move |e: tray_icon::TrayIconEvent| {
+            let event = match e {
+                tray_icon::TrayIconEvent::Click {
+                    button: tray_icon::MouseButton::Left,
+                    button_state: tray_icon::MouseButtonState::Up,
+                    ..
+                } => TrayEvent::Clicked,
+                tray_icon::TrayIconEvent::DoubleClick { .. } => TrayEvent::DoubleClicked,
+                _ => return,
+            };
+            let _ = sender.try_send(event);
+        }));
+        let m = tray_icon::menu::Menu::new();
+        for (id, label) in menu {
+            m.append(&tray_icon::menu::MenuItem::with_id(*id, label, true, None))?;
+        }
+        let icon = tray_icon::TrayIconBuilder::new()
+            .with_tooltip(tooltip)
+            .with_icon(tray_icon::Icon::from_rgba(rgba, width, height)?)
+            .with_menu(Box::new(m))
+            .build()?;
+        Ok(Self {
+            _icon: icon,
+            events,
+        })
+    }
+
+    /// Wait for the next event of the tray icon. Returns None if events can no longer be received.
+    pub async fn next_event(&self) -> Option<TrayEvent> {
+        self.events.recv().await.ok()
+    }
+}
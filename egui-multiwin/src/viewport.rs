@@ -0,0 +1,427 @@
+//! Contains support for the egui viewports of a window.
+//!
+//! Each viewport window shares a state with the window that created the viewport. The parent window updates the state
+//! with the latest egui output for the viewport, and the viewport window applies the changes when it redraws.
+//!
+//! The ui of an immediate viewport runs inside of the frame of the parent window, but the windows of this crate draw
+//! asynchronously. The parent window runs the ui of the viewport with the egui context of the viewport window, which
+//! stores the output until the viewport window draws it. The input of the viewport window is stored until the next
+//! frame of the parent window.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::sync::LockRecover;
+
+/// The state of a viewport, shared between the parent window and the window that displays the viewport.
+#[derive(Default)]
+pub struct ViewportState {
+    /// True when the viewport is an immediate viewport
+    immediate: bool,
+    /// The egui context of the window displaying an immediate viewport
+    ctx: Option<egui::Context>,
+    /// The input gathered by the viewport window, waiting for the next frame of the parent window
+    input: egui::RawInput,
+    /// The output of the viewport, waiting to be drawn by the viewport window
+    output: Option<egui::FullOutput>,
+    /// The window displaying the viewport
+    window: Option<Arc<async_winit::window::Window<async_winit::ThreadSafe>>>,
+    /// The parent window, that runs the ui of the viewport
+    parent: Option<Arc<async_winit::window::Window<async_winit::ThreadSafe>>>,
+    /// The builder last requested for the viewport
+    builder: egui::ViewportBuilder,
+    /// The parts of the builder that changed since the viewport window last applied them
+    builder_changes: Option<egui::ViewportBuilder>,
+    /// True when the parent window has closed, the viewport window should close as well
+    closed: bool,
+    /// A close request of the viewport window, waiting to be delivered to the parent window
+    parent_close_event: bool,
+    /// A close request of the viewport window, waiting to be delivered to the viewport itself
+    viewport_close_event: bool,
+}
+
+/// The viewports of a parent window, by viewport id
+pub type Viewports = Arc<Mutex<HashMap<egui::ViewportId, Arc<Mutex<ViewportState>>>>>;
+
+impl ViewportState {
+    /// Returns true when the viewport is an immediate viewport
+    pub fn is_immediate(&self) -> bool {
+        self.immediate
+    }
+
+    /// Mark the viewport as an immediate viewport
+    pub fn set_immediate(&mut self) {
+        self.immediate = true;
+    }
+
+    /// Attach the window that displays the viewport. Output produced before the window was attached is discarded.
+    pub fn attach(
+        &mut self,
+        ctx: &egui::Context,
+        window: &Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
+    ) {
+        if self.window.is_none() {
+            if self.immediate {
+                self.ctx = Some(ctx.clone());
+            }
+            self.window = Some(window.clone());
+            self.output = None;
+        }
+    }
+
+    /// Set the parent window, that runs the ui of the viewport
+    pub fn set_parent(
+        &mut self,
+        parent: &Arc<async_winit::window::Window<async_winit::ThreadSafe>>,
+    ) {
+        self.parent = Some(parent.clone());
+    }
+
+    /// Store the input of the viewport window for the next frame of the parent window. The parent window is
+    /// woken when there are new events, or when there is nothing to draw yet.
+    pub fn queue_input(&mut self, mut input: egui::RawInput) {
+        let wake = !input.events.is_empty() || self.output.is_none();
+        let mut events = std::mem::take(&mut self.input.events);
+        events.append(&mut input.events);
+        input.events = events;
+        self.input = input;
+        if wake {
+            if let Some(parent) = &self.parent {
+                parent.request_redraw();
+            }
+        }
+    }
+
+    /// Take the output of the viewport that has not been drawn yet
+    pub fn take_output(&mut self) -> Option<egui::FullOutput> {
+        self.output.take()
+    }
+
+    /// Store output for the viewport window, keeping texture changes of output that was never drawn
+    fn store_output(&mut self, mut output: egui::FullOutput) {
+        if let Some(old) = self.output.take() {
+            let mut textures = old.textures_delta;
+            textures.append(output.textures_delta);
+            output.textures_delta = textures;
+        }
+        self.output = Some(output);
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Set the builder requested for the viewport. The viewport window is woken when the builder changed.
+    pub fn update_builder(&mut self, builder: &egui::ViewportBuilder) {
+        if self.builder == *builder {
+            return;
+        }
+        let changes = builder_changes(&self.builder, builder);
+        self.builder = builder.clone();
+        match &mut self.builder_changes {
+            Some(c) => {
+                c.patch(changes);
+            }
+            None => self.builder_changes = Some(changes),
+        }
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Returns true when the parent window has closed
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Mark the viewport as closed, waking the viewport window so that it closes
+    pub fn close(&mut self) {
+        self.closed = true;
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// The user requested to close the viewport window. The close request is delivered to the parent window and to the
+    /// viewport. The viewport window closes when the parent window stops showing the viewport.
+    pub fn request_close(&mut self) {
+        self.parent_close_event = true;
+        self.viewport_close_event = true;
+        if let Some(parent) = &self.parent {
+            parent.request_redraw();
+        }
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    /// Take the close request waiting for the parent window
+    pub fn take_parent_close_event(&mut self) -> bool {
+        std::mem::take(&mut self.parent_close_event)
+    }
+
+    /// Take the close request waiting for the viewport
+    pub fn take_viewport_close_event(&mut self) -> bool {
+        std::mem::take(&mut self.viewport_close_event)
+    }
+
+    /// Take the changes to the builder that have not been applied to the viewport window yet
+    pub fn take_builder_changes(&mut self) -> Option<egui::ViewportBuilder> {
+        self.builder_changes.take()
+    }
+}
+
+/// Build a builder containing only the settings that differ between two builders
+fn builder_changes(
+    old: &egui::ViewportBuilder,
+    new: &egui::ViewportBuilder,
+) -> egui::ViewportBuilder {
+    let mut changes = egui::ViewportBuilder::default();
+    macro_rules! changed {
+        ($($field:ident),*) => {
+            $(
+                if old.$field != new.$field {
+                    changes.$field = new.$field.clone();
+                }
+            )*
+        };
+    }
+    changed!(
+        title,
+        position,
+        inner_size,
+        min_inner_size,
+        max_inner_size,
+        fullscreen,
+        maximized,
+        resizable,
+        decorations,
+        transparent,
+        window_level,
+        icon,
+        visible,
+        mouse_passthrough
+    );
+    changes
+}
+
+/// Convert the viewport commands sent by the ui of a window, such as with [egui::Context::send_viewport_cmd], to window commands.
+/// Commands without an equivalent window command are ignored.
+pub fn window_commands(
+    commands: &[egui::ViewportCommand],
+) -> Vec<crate::tracked_window::WindowCommand> {
+    use crate::tracked_window::WindowCommand;
+    commands
+        .iter()
+        .filter_map(|c| match c {
+            egui::ViewportCommand::StartDrag => Some(WindowCommand::StartDrag),
+            egui::ViewportCommand::BeginResize(d) => Some(WindowCommand::StartResize(*d)),
+            egui::ViewportCommand::Decorations(d) => Some(WindowCommand::SetDecorations(*d)),
+            egui::ViewportCommand::Resizable(r) => Some(WindowCommand::SetResizable(*r)),
+            egui::ViewportCommand::Visible(v) => Some(WindowCommand::SetVisible(*v)),
+            egui::ViewportCommand::CursorGrab(g) => Some(WindowCommand::SetCursorGrab(*g)),
+            egui::ViewportCommand::CursorVisible(v) => Some(WindowCommand::SetCursorVisible(*v)),
+            egui::ViewportCommand::MousePassthrough(p) => {
+                Some(WindowCommand::SetMousePassthrough(*p))
+            }
+            egui::ViewportCommand::Fullscreen(f) => Some(WindowCommand::SetFullscreen(
+                f.then_some(crate::monitor::FullscreenMode::Borderless(
+                    crate::monitor::MonitorSelector::Current,
+                )),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Convert the direction of a resize from egui
+pub fn resize_direction(
+    d: egui::viewport::ResizeDirection,
+) -> async_winit::window::ResizeDirection {
+    use async_winit::window::ResizeDirection as W;
+    use egui::viewport::ResizeDirection as E;
+    match d {
+        E::North => W::North,
+        E::South => W::South,
+        E::East => W::East,
+        E::West => W::West,
+        E::NorthEast => W::NorthEast,
+        E::SouthEast => W::SouthEast,
+        E::NorthWest => W::NorthWest,
+        E::SouthWest => W::SouthWest,
+    }
+}
+
+/// Create the builder for a viewport window from the egui viewport builder. Sizes and positions are converted from points
+/// using the zoom factor of the parent window.
+pub fn window_builder(
+    vb: &egui::ViewportBuilder,
+    zoom_factor: f32,
+) -> async_winit::window::WindowBuilder {
+    /// Convert a size in points to a logical size
+    fn size(s: egui::Vec2, zoom_factor: f32) -> async_winit::dpi::LogicalSize<f32> {
+        async_winit::dpi::LogicalSize {
+            width: s.x * zoom_factor,
+            height: s.y * zoom_factor,
+        }
+    }
+
+    let mut builder = async_winit::window::WindowBuilder::new()
+        .with_title(
+            vb.title
+                .clone()
+                .unwrap_or_else(|| "egui window".to_string()),
+        )
+        .with_resizable(vb.resizable.unwrap_or(true))
+        .with_decorations(vb.decorations.unwrap_or(true))
+        .with_transparent(vb.transparent.unwrap_or(false))
+        .with_visible(vb.visible.unwrap_or(true))
+        .with_maximized(vb.maximized.unwrap_or(false));
+    if let Some(s) = vb.inner_size {
+        builder = builder.with_inner_size(size(s, zoom_factor));
+    }
+    if let Some(s) = vb.min_inner_size {
+        builder = builder.with_min_inner_size(size(s, zoom_factor));
+    }
+    if let Some(s) = vb.max_inner_size {
+        builder = builder.with_max_inner_size(size(s, zoom_factor));
+    }
+    if let Some(p) = vb.position {
+        builder = builder.with_position(async_winit::dpi::LogicalPosition {
+            x: p.x * zoom_factor,
+            y: p.y * zoom_factor,
+        });
+    }
+    if let Some(level) = vb.window_level {
+        builder = builder.with_window_level(match level {
+            egui::WindowLevel::Normal => async_winit::window::WindowLevel::Normal,
+            egui::WindowLevel::AlwaysOnBottom => async_winit::window::WindowLevel::AlwaysOnBottom,
+            egui::WindowLevel::AlwaysOnTop => async_winit::window::WindowLevel::AlwaysOnTop,
+        });
+    }
+    if let Some(icon) = &vb.icon {
+        let icon =
+            async_winit::window::Icon::from_rgba(icon.rgba.clone(), icon.width, icon.height).ok();
+        builder = builder.with_window_icon(icon);
+    }
+    builder
+}
+
+/// Get the viewports of a parent window, stored in the egui context of the parent window
+pub fn viewports(ctx: &egui::Context) -> Viewports {
+    ctx.data_mut(|d| {
+        d.get_temp_mut_or_default::<Viewports>(egui::Id::new("egui_multiwin_viewports"))
+            .clone()
+    })
+}
+
+/// Get the state of a viewport of a parent window, creating it if required
+pub fn viewport_state(ctx: &egui::Context, id: egui::ViewportId) -> Arc<Mutex<ViewportState>> {
+    viewports(ctx)
+        .lock()
+        .unwrap()
+        .entry(id)
+        .or_default()
+        .clone()
+}
+
+/// Place the close requests of viewport windows into the input of the parent window
+pub fn deliver_close_events(viewports: &Viewports, input: &mut egui::RawInput) {
+    for (id, state) in viewports.lock_recover().iter() {
+        if state.lock_recover().take_parent_close_event() {
+            input
+                .viewports
+                .entry(*id)
+                .or_default()
+                .events
+                .push(egui::ViewportEvent::Close);
+        }
+    }
+}
+
+/// Close all viewports of a parent window, used when the parent window closes
+pub fn close_all(viewports: &Viewports) {
+    for (_id, state) in viewports.lock_recover().drain() {
+        state.lock_recover().close();
+    }
+}
+
+/// Install the renderer for immediate viewports on the current thread. egui calls the renderer from `show_viewport_immediate`.
+pub fn install_immediate_renderer() {
+    egui::Context::set_immediate_viewport_renderer(render_immediate_viewport);
+}
+
+/// Run the ui of an immediate viewport with the egui context of the window displaying the viewport. egui requires
+/// the ui to be run, so it runs with a temporary context until the window displaying the viewport exists.
+fn render_immediate_viewport(ctx: &egui::Context, viewport: egui::ImmediateViewport<'_>) {
+    let state = viewport_state(ctx, viewport.ids.this);
+    let (vctx, input) = {
+        let mut s = state.lock_recover();
+        s.set_immediate();
+        let vctx = s.ctx.get_or_insert_with(egui::Context::default).clone();
+        (vctx, s.input.take())
+    };
+    let mut cb = viewport.viewport_ui_cb;
+    let output = vctx.run(input, |ctx| cb(ctx));
+    state.lock_recover().store_output(output);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close_all_closes_every_viewport() {
+        let viewports = Viewports::default();
+        let a = Arc::new(Mutex::new(ViewportState::default()));
+        let b = Arc::new(Mutex::new(ViewportState::default()));
+        viewports
+            .lock()
+            .unwrap()
+            .insert(egui::ViewportId::from_hash_of("a"), a.clone());
+        viewports
+            .lock()
+            .unwrap()
+            .insert(egui::ViewportId::from_hash_of("b"), b.clone());
+        assert!(!a.lock().unwrap().is_closed());
+        close_all(&viewports);
+        assert!(a.lock().unwrap().is_closed());
+        assert!(b.lock().unwrap().is_closed());
+        assert!(viewports.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn close_request_reaches_parent_once() {
+        let viewports = Viewports::default();
+        let id = egui::ViewportId::from_hash_of("a");
+        let a = Arc::new(Mutex::new(ViewportState::default()));
+        viewports.lock().unwrap().insert(id, a.clone());
+        a.lock().unwrap().request_close();
+
+        let mut input = egui::RawInput::default();
+        deliver_close_events(&viewports, &mut input);
+        assert!(input.viewports[&id].close_requested());
+
+        let mut input = egui::RawInput::default();
+        deliver_close_events(&viewports, &mut input);
+        assert!(!input.viewports.contains_key(&id));
+
+        assert!(a.lock().unwrap().take_viewport_close_event());
+        assert!(!a.lock().unwrap().is_closed());
+    }
+
+    #[test]
+    fn drag_commands_become_window_commands() {
+        use crate::tracked_window::WindowCommand;
+        let commands = window_commands(&[
+            egui::ViewportCommand::StartDrag,
+            egui::ViewportCommand::Title("ignored".to_string()),
+            egui::ViewportCommand::BeginResize(egui::viewport::ResizeDirection::SouthEast),
+        ]);
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(commands[0], WindowCommand::StartDrag));
+        assert!(matches!(
+            commands[1],
+            WindowCommand::StartResize(egui::viewport::ResizeDirection::SouthEast)
+        ));
+    }
+}
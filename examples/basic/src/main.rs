@@ -30,12 +30,26 @@ pub struct AppCommon {
 #[tokio::main]
 async fn main() {
     println!("Startup 1");
-    let mut multi_window: MultiWindow = MultiWindow::new();
+    let mut multi_window: MultiWindow =
+        MultiWindow::new_or_panic().with_persistence("egui-multiwin-basic-windows.json");
     println!("Startup 2");
     multi_window.add_font(
         "computermodern".to_string(),
         egui_multiwin::egui::FontData::from_static(COMPUTER_MODERN_FONT),
     );
+    multi_window
+        .register_shortcut(
+            egui_multiwin::egui::KeyboardShortcut::new(
+                egui_multiwin::egui::Modifiers::COMMAND,
+                egui_multiwin::egui::Key::N,
+            ),
+            |_c| {
+                Some(vec![popup_window::PopupWindow::request(
+                    "shortcut popup".to_string(),
+                )])
+            },
+        )
+        .unwrap();
     println!("Startup 3");
     let root_window = root::RootWindow::request();
     println!("Startup 4");
@@ -45,9 +59,9 @@ async fn main() {
     let ac = AppCommon { clicks: 0 };
     println!("Startup 6");
 
-    multi_window.add(root_window).await;
+    let _e = multi_window.add(root_window).await;
     println!("Startup 7");
-    multi_window.add(root_window2).await;
+    let _e = multi_window.add(root_window2).await;
     println!("Startup 8");
     multi_window.run(ac).unwrap();
     println!("Startup 9");
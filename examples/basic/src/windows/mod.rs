@@ -6,8 +6,11 @@ use crate::egui_multiwin_dynamic::tracked_window::{RedrawResponse, TrackedWindow
 use egui_multiwin::egui_glow_async::EguiGlow;
 use std::sync::{Arc, Mutex};
 
+pub mod mouselook_window;
 pub mod popup_window;
 pub mod root;
+pub mod stubborn_window;
+pub mod titlebar_window;
 pub mod transparent_window;
 
 /// The windows for the program
@@ -19,4 +22,10 @@ pub enum MyWindows {
     Popup(popup_window::PopupWindow),
     /// A popup window with a transparent background
     Transparent(transparent_window::PopupWindow),
+    /// A window that refuses to close the first two times
+    Stubborn(stubborn_window::StubbornWindow),
+    /// A window that draws its own title bar
+    Titlebar(titlebar_window::TitlebarWindow),
+    /// A window that grabs the cursor for camera style controls
+    Mouselook(mouselook_window::MouselookWindow),
 }
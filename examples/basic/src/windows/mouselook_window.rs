@@ -0,0 +1,141 @@
+//! This is an example of a window that grabs the cursor for camera style controls. A right click locks and hides the cursor, after
+//! which the raw motion of the mouse turns the view. Another right click, or switching to another window, releases the cursor.
+use std::sync::{Arc, Mutex};
+
+use crate::egui_multiwin_dynamic::{
+    multi_window::NewWindowRequest,
+    tracked_window::{RedrawResponse, ThreadSafety, TrackedWindow},
+};
+use egui_multiwin::egui;
+use egui_multiwin::egui_glow_async::EguiGlow;
+use egui_multiwin::tracked_window::WindowCommand;
+
+use crate::AppCommon;
+
+/// The degrees turned for each unit of raw mouse motion
+const SENSITIVITY: f64 = 0.1;
+
+/// The window with camera style controls
+pub struct MouselookWindow {
+    /// True while the window grabs the cursor
+    grabbed: bool,
+    /// The direction of the view in degrees, left and right
+    yaw: f64,
+    /// The direction of the view in degrees, up and down
+    pitch: f64,
+}
+
+impl MouselookWindow {
+    /// Request a new window
+    pub fn request() -> NewWindowRequest {
+        NewWindowRequest::new(
+            super::MyWindows::Mouselook(MouselookWindow {
+                grabbed: false,
+                yaw: 0.0,
+                pitch: 0.0,
+            }),
+            egui_multiwin::async_winit::window::WindowBuilder::new()
+                .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
+                    width: 400.0,
+                    height: 200.0,
+                })
+                .with_title("Mouse look"),
+            egui_multiwin::tracked_window::TrackedWindowOptions {
+                vsync: false,
+                shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
+            },
+        )
+    }
+}
+
+impl TrackedWindow for MouselookWindow {
+    fn focus_changed(&mut self, focused: bool) {
+        // The cursor is released when the window loses focus
+        if !focused {
+            self.grabbed = false;
+        }
+    }
+
+    fn raw_mouse_motion(&mut self, _c: &mut AppCommon, delta: (f64, f64)) {
+        self.yaw = (self.yaw + delta.0 * SENSITIVITY).rem_euclid(360.0);
+        self.pitch = (self.pitch - delta.1 * SENSITIVITY).clamp(-90.0, 90.0);
+    }
+
+    async fn redraw(
+        &mut self,
+        _c: &mut AppCommon,
+        egui: &mut EguiGlow,
+        _window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> RedrawResponse {
+        self.ui(&egui.egui_ctx)
+    }
+}
+
+impl MouselookWindow {
+    /// The user interface of the window, separate from the redraw so that it can be tested without a window
+    fn ui(&mut self, egui_ctx: &egui::Context) -> RedrawResponse {
+        let mut commands = Vec::new();
+        egui::CentralPanel::default().show(egui_ctx, |ui| {
+            let response = ui.interact(ui.max_rect(), ui.id().with("look"), egui::Sense::click());
+            if self.grabbed {
+                ui.heading("Move the mouse to look around, right click to release the cursor");
+            } else {
+                ui.heading("Right click to grab the cursor");
+            }
+            ui.label(format!("Yaw {:.1}°, pitch {:.1}°", self.yaw, self.pitch));
+            if response.secondary_clicked() {
+                self.grabbed = !self.grabbed;
+                let grab = if self.grabbed {
+                    egui::viewport::CursorGrab::Locked
+                } else {
+                    egui::viewport::CursorGrab::None
+                };
+                commands.push(WindowCommand::SetCursorGrab(grab));
+                commands.push(WindowCommand::SetCursorVisible(!self.grabbed));
+            }
+        });
+        RedrawResponse {
+            commands,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui_multiwin::testing::TestHarness;
+
+    #[test]
+    fn right_click_toggles_the_grab() {
+        let mut window = MouselookWindow {
+            grabbed: false,
+            yaw: 0.0,
+            pitch: 0.0,
+        };
+        let mut harness = TestHarness::new(egui::vec2(400.0, 200.0));
+        harness.run(|ctx| window.ui(ctx));
+        harness.click_button(egui::pos2(200.0, 150.0), egui::PointerButton::Secondary);
+        let (_, rr) = harness.run(|ctx| window.ui(ctx));
+        assert!(window.grabbed);
+        assert!(matches!(
+            rr.commands[..],
+            [
+                WindowCommand::SetCursorGrab(egui::viewport::CursorGrab::Locked),
+                WindowCommand::SetCursorVisible(false)
+            ]
+        ));
+        window.focus_changed(false);
+        assert!(!window.grabbed);
+    }
+}
@@ -3,11 +3,12 @@ use std::sync::{Arc, Mutex};
 
 use crate::egui_multiwin_dynamic::{
     multi_window::NewWindowRequest,
-    tracked_window::{RedrawResponse, TrackedWindow},
+    tracked_window::{RedrawResponse, ThreadSafety, TrackedWindow},
 };
 use egui_multiwin::egui_glow_async::glow;
 use egui_multiwin::egui_glow_async::EguiGlow;
 use egui_multiwin::egui::containers::panel::AsyncClosure;
+use egui_multiwin::tracked_window::WindowCommand;
 
 use crate::AppCommon;
 
@@ -15,6 +16,10 @@ use crate::AppCommon;
 pub struct PopupWindow {
     /// The label for the window
     pub input: String,
+    /// True when the window shows its title bar and borders
+    decorated: bool,
+    /// The opacity of the whole window
+    opacity: f32,
 }
 
 impl PopupWindow {
@@ -23,6 +28,8 @@ impl PopupWindow {
         NewWindowRequest::new(
             super::MyWindows::Popup(PopupWindow {
                 input: label.clone(),
+                decorated: true,
+                opacity: 1.0,
             }),
             egui_multiwin::async_winit::window::WindowBuilder::new()
                 .with_resizable(false)
@@ -34,6 +41,15 @@ impl PopupWindow {
             egui_multiwin::tracked_window::TrackedWindowOptions {
                 vsync: false,
                 shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
             },
         )
     }
@@ -118,10 +134,27 @@ impl TrackedWindow for PopupWindow {
         &mut self,
         c: &mut AppCommon,
         egui: &mut EguiGlow,
-        window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>,
-        _clipboard: Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
+        window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
     ) -> RedrawResponse {
         let egui_ctx = &egui.egui_ctx;
+        let mut commands = Vec::new();
+        let opacity_supported = egui_multiwin::opacity::supported(window);
+        egui_multiwin::egui::TopBottomPanel::top("decorations").show(egui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.checkbox(&mut self.decorated, "Title bar").changed() {
+                    commands.push(WindowCommand::SetDecorations(self.decorated));
+                }
+                // The slider is hidden where the platform cannot change the opacity of the window
+                if opacity_supported
+                    && ui
+                        .add(egui_multiwin::egui::Slider::new(&mut self.opacity, 0.2..=1.0).text("Opacity"))
+                        .changed()
+                {
+                    commands.push(WindowCommand::SetOpacity(self.opacity));
+                }
+            });
+        });
         let quit = Arc::new(Mutex::new(false));
         let quit2 = quit.clone();
         egui_multiwin::egui::CentralPanel::default()
@@ -150,6 +183,8 @@ impl TrackedWindow for PopupWindow {
         RedrawResponse {
             quit,
             new_windows: Vec::new(),
+            commands,
+            ..Default::default()
         }
     }
 }
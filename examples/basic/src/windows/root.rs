@@ -1,12 +1,15 @@
 //! Code for the root window of the project.
 
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 
 use crate::egui_multiwin_dynamic::{
     multi_window::NewWindowRequest,
-    tracked_window::{RedrawResponse, TrackedWindow},
+    tracked_window::{RedrawResponse, ThreadSafety, TrackedWindow},
 };
-use egui_multiwin::egui::FontId;
+use egui_multiwin::egui::{FontId, Key, Modifiers};
+use egui_multiwin::registry::WindowId;
+use egui_multiwin::tracked_window::WindowCommand;
 use egui_multiwin::egui_glow_async::EguiGlow;
 use egui_multiwin::egui::containers::panel::AsyncClosure;
 
@@ -22,10 +25,18 @@ pub struct RootWindow {
     pub num_popups_created: u32,
     /// True when the groot viewport should be visible
     summon_groot: bool,
+    /// True when the immediate viewport should be visible
+    summon_immediate: bool,
+    /// The number of times the button in the groot viewport was clicked
+    groot_clicks: Arc<AtomicU32>,
     /// The last time an update was performed
     prev_time: std::time::Instant,
     /// The calculated frames per second of the application
     fps: Option<f32>,
+    /// The zoom factor of the window
+    zoom: f32,
+    /// True when the window is fullscreen
+    fullscreen: bool,
 }
 
 impl RootWindow {
@@ -36,8 +47,12 @@ impl RootWindow {
                 button_press_count: 0,
                 num_popups_created: 0,
                 summon_groot: false,
+                summon_immediate: false,
+                groot_clicks: Arc::new(AtomicU32::new(0)),
                 prev_time: std::time::Instant::now(),
                 fps: None,
+                zoom: 1.0,
+                fullscreen: false,
             }),
             egui_multiwin::async_winit::window::WindowBuilder::new()
                 .with_resizable(true)
@@ -49,11 +64,34 @@ impl RootWindow {
             egui_multiwin::tracked_window::TrackedWindowOptions {
                 vsync: false,
                 shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
             },
         )
+        .with_persist_key("root")
     }
 }
 
+/// Returns true when the user requested to close the window of a viewport
+fn viewport_close_requested(
+    ctx: &egui_multiwin::egui::Context,
+    id: egui_multiwin::egui::viewport::ViewportId,
+) -> bool {
+    ctx.input(|i| {
+        i.raw
+            .viewports
+            .get(&id)
+            .map_or(false, |v| v.close_requested())
+    })
+}
+
 impl TrackedWindow for RootWindow {
     fn is_root(&self) -> bool {
         true
@@ -65,8 +103,8 @@ impl TrackedWindow for RootWindow {
         &mut self,
         c: &mut AppCommon,
         egui: &mut EguiGlow,
-        window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>,
-        _clipboard: Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
+        window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
     ) -> RedrawResponse {
         let mut quit = false;
 
@@ -85,6 +123,32 @@ impl TrackedWindow for RootWindow {
         }
 
         let mut windows_to_create = vec![];
+        let mut commands = vec![];
+
+        let (zoom_in, zoom_out, toggle_fullscreen) = egui_ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::COMMAND, Key::Equals)
+                    || i.consume_key(Modifiers::COMMAND, Key::Plus),
+                i.consume_key(Modifiers::COMMAND, Key::Minus),
+                i.consume_key(Modifiers::NONE, Key::F11),
+            )
+        });
+        if toggle_fullscreen {
+            self.fullscreen = !self.fullscreen;
+            commands.push(WindowCommand::SetFullscreen(self.fullscreen.then_some(
+                egui_multiwin::monitor::FullscreenMode::Borderless(
+                    egui_multiwin::monitor::MonitorSelector::Current,
+                ),
+            )));
+        }
+        if zoom_in {
+            self.zoom = (self.zoom + 0.1).min(5.0);
+            commands.push(WindowCommand::SetZoomFactor(self.zoom));
+        }
+        if zoom_out {
+            self.zoom = (self.zoom - 0.1).max(0.2);
+            commands.push(WindowCommand::SetZoomFactor(self.zoom));
+        }
 
         egui_multiwin::egui::SidePanel::left("my_side_panel")
             .show_async(egui_ctx, |ui| {
@@ -96,6 +160,35 @@ impl TrackedWindow for RootWindow {
                     )));
                     self.num_popups_created += 1;
                 }
+                if ui.button("New 20 popups").clicked() {
+                    for _ in 0..20 {
+                        windows_to_create.push(PopupWindow::request(format!(
+                            "popup window #{}",
+                            self.num_popups_created
+                        )));
+                        self.num_popups_created += 1;
+                    }
+                }
+                if ui.button("New large text popup").clicked() {
+                    let mut style = (*egui_ctx.style()).clone();
+                    for font in style.text_styles.values_mut() {
+                        font.size *= 1.5;
+                    }
+                    windows_to_create.push(
+                        PopupWindow::request(format!("popup window #{}", self.num_popups_created))
+                            .with_style(style),
+                    );
+                    self.num_popups_created += 1;
+                }
+                if ui.button("New modal dialog").clicked() {
+                    let mut dialog =
+                        PopupWindow::request(format!("modal window #{}", self.num_popups_created));
+                    if let Some(id) = WindowId::of(egui_ctx) {
+                        dialog = dialog.modal_for(id);
+                    }
+                    windows_to_create.push(dialog);
+                    self.num_popups_created += 1;
+                }
                 if ui.button("New transparent window").clicked() {
                     windows_to_create.push(
                         crate::windows::transparent_window::PopupWindow::request(
@@ -103,6 +196,18 @@ impl TrackedWindow for RootWindow {
                         ),
                     );
                 }
+                if ui.button("New custom title bar window").clicked() {
+                    windows_to_create
+                        .push(crate::windows::titlebar_window::TitlebarWindow::request());
+                }
+                if ui.button("New mouse look window").clicked() {
+                    windows_to_create
+                        .push(crate::windows::mouselook_window::MouselookWindow::request());
+                }
+                if ui.button("New stubborn window").clicked() {
+                    windows_to_create
+                        .push(crate::windows::stubborn_window::StubbornWindow::request());
+                }
                 if ui.button("Quit").clicked() {
                     quit = true;
                 }
@@ -119,16 +224,55 @@ impl TrackedWindow for RootWindow {
                 });
                 ui.label(t);
                 ui.checkbox(&mut self.summon_groot, "summon groot");
+                ui.label(format!(
+                    "groot was clicked {} times",
+                    self.groot_clicks.load(Ordering::Relaxed)
+                ));
+                if self.summon_groot {
+                    let id = egui_multiwin::egui::viewport::ViewportId::from_hash_of("Testing");
+                    if viewport_close_requested(egui_ctx, id) {
+                        self.summon_groot = false;
+                    }
+                }
                 if self.summon_groot {
+                    let clicks = self.groot_clicks.clone();
                     egui_ctx.show_viewport_deferred(
                         egui_multiwin::egui::viewport::ViewportId::from_hash_of("Testing"),
                         egui_multiwin::egui::viewport::ViewportBuilder {
                             title: Some("Test title".to_string()),
                             ..Default::default()
                         },
-                        |a, _b| {
+                        move |a, _b| {
                             egui_multiwin::egui::CentralPanel::default().show(a, |ui| {
                                 ui.label("I am groot");
+                                if ui.button("Click groot").clicked() {
+                                    clicks.fetch_add(1, Ordering::Relaxed);
+                                }
+                            });
+                        },
+                    );
+                }
+                ui.checkbox(&mut self.summon_immediate, "summon immediate viewport");
+                if self.summon_immediate {
+                    let id = egui_multiwin::egui::viewport::ViewportId::from_hash_of("Immediate");
+                    if viewport_close_requested(egui_ctx, id) {
+                        self.summon_immediate = false;
+                    }
+                }
+                if self.summon_immediate {
+                    let count = &mut self.button_press_count;
+                    egui_ctx.show_viewport_immediate(
+                        egui_multiwin::egui::viewport::ViewportId::from_hash_of("Immediate"),
+                        egui_multiwin::egui::viewport::ViewportBuilder {
+                            title: Some("Immediate viewport".to_string()),
+                            ..Default::default()
+                        },
+                        |a, _b| {
+                            egui_multiwin::egui::CentralPanel::default().show(a, |ui| {
+                                if ui.button("Click me").clicked() {
+                                    *count += 1;
+                                }
+                                ui.label(format!("Clicked {} times", count));
                             });
                         },
                     );
@@ -138,6 +282,8 @@ impl TrackedWindow for RootWindow {
         RedrawResponse {
             quit,
             new_windows: windows_to_create,
+            commands,
+            ..Default::default()
         }
     }
 }
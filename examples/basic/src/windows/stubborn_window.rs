@@ -0,0 +1,77 @@
+//! This is an example of a window that refuses to close. The first two attempts to close it with the close button are refused,
+//! the third attempt closes it.
+use std::sync::{Arc, Mutex};
+
+use crate::egui_multiwin_dynamic::{
+    multi_window::NewWindowRequest,
+    tracked_window::{RedrawResponse, ThreadSafety, TrackedWindow},
+};
+use egui_multiwin::egui_glow_async::EguiGlow;
+
+use crate::AppCommon;
+
+/// The number of attempts needed to close the window
+const ATTEMPTS_NEEDED: u32 = 3;
+
+/// The window that refuses to close
+pub struct StubbornWindow {
+    /// The number of times closing the window was attempted
+    attempts: u32,
+}
+
+impl StubbornWindow {
+    /// Request a new window
+    pub fn request() -> NewWindowRequest {
+        NewWindowRequest::new(
+            super::MyWindows::Stubborn(StubbornWindow { attempts: 0 }),
+            egui_multiwin::async_winit::window::WindowBuilder::new()
+                .with_resizable(false)
+                .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
+                    width: 400.0,
+                    height: 200.0,
+                })
+                .with_title("Stubborn window"),
+            egui_multiwin::tracked_window::TrackedWindowOptions {
+                vsync: false,
+                shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
+            },
+        )
+    }
+}
+
+impl TrackedWindow for StubbornWindow {
+    fn can_quit(&mut self, _c: &mut AppCommon) -> bool {
+        self.attempts += 1;
+        self.attempts >= ATTEMPTS_NEEDED
+    }
+
+    async fn redraw(
+        &mut self,
+        _c: &mut AppCommon,
+        egui: &mut EguiGlow,
+        _window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> RedrawResponse {
+        egui_multiwin::egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
+            if self.attempts == 0 {
+                ui.heading("Try to close this window");
+            } else {
+                ui.heading(format!(
+                    "Refused to close {} of {} times",
+                    self.attempts,
+                    ATTEMPTS_NEEDED - 1
+                ));
+            }
+        });
+        RedrawResponse::default()
+    }
+}
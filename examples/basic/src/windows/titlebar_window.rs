@@ -0,0 +1,135 @@
+//! This is an example of a window without decorations that draws its own title bar. Dragging the title bar moves the window with
+//! an egui viewport command, dragging the corner grip resizes the window with a window command.
+use std::sync::{Arc, Mutex};
+
+use crate::egui_multiwin_dynamic::{
+    multi_window::NewWindowRequest,
+    tracked_window::{RedrawResponse, ThreadSafety, TrackedWindow},
+};
+use egui_multiwin::egui;
+use egui_multiwin::egui_glow_async::EguiGlow;
+use egui_multiwin::tracked_window::WindowCommand;
+
+use crate::AppCommon;
+
+/// The window with a custom title bar
+pub struct TitlebarWindow {}
+
+impl TitlebarWindow {
+    /// Request a new window
+    pub fn request() -> NewWindowRequest {
+        NewWindowRequest::new(
+            super::MyWindows::Titlebar(TitlebarWindow {}),
+            egui_multiwin::async_winit::window::WindowBuilder::new()
+                .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
+                    width: 400.0,
+                    height: 200.0,
+                })
+                .with_title("Custom title bar"),
+            egui_multiwin::tracked_window::TrackedWindowOptions {
+                vsync: false,
+                shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
+            },
+        )
+        .with_decorations(false)
+    }
+}
+
+impl TitlebarWindow {
+    /// The user interface of the window, separate from the redraw so that it can be tested without a window
+    fn ui(&mut self, egui_ctx: &egui::Context) -> RedrawResponse {
+        let mut quit = false;
+        let mut commands = Vec::new();
+        egui::TopBottomPanel::top("titlebar").show(egui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("X").clicked() {
+                    quit = true;
+                }
+                // The rest of the title bar moves the window, the button above consumes its own presses
+                let (rect, _) = ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
+                let title = ui.interact(rect, ui.id().with("title"), egui::Sense::click_and_drag());
+                ui.painter().text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "Drag here to move the window",
+                    egui::FontId::default(),
+                    ui.visuals().text_color(),
+                );
+                if title.drag_started_by(egui::PointerButton::Primary) {
+                    egui_ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                }
+            });
+        });
+        egui::CentralPanel::default().show(egui_ctx, |ui| {
+            ui.label("Drag the bottom right corner to resize the window");
+            let corner = egui::Rect::from_min_size(
+                ui.max_rect().max - egui::vec2(16.0, 16.0),
+                egui::vec2(16.0, 16.0),
+            );
+            let grip = ui.interact(corner, ui.id().with("grip"), egui::Sense::drag());
+            ui.painter()
+                .rect_filled(corner, 0.0, ui.visuals().widgets.inactive.bg_fill);
+            if grip.drag_started_by(egui::PointerButton::Primary) {
+                commands.push(WindowCommand::StartResize(
+                    egui::viewport::ResizeDirection::SouthEast,
+                ));
+            }
+        });
+        RedrawResponse {
+            quit,
+            commands,
+            ..Default::default()
+        }
+    }
+}
+
+impl TrackedWindow for TitlebarWindow {
+    async fn redraw(
+        &mut self,
+        _c: &mut AppCommon,
+        egui: &mut EguiGlow,
+        _window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> RedrawResponse {
+        self.ui(&egui.egui_ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui_multiwin::testing::TestHarness;
+
+    #[test]
+    fn close_button_quits() {
+        let mut window = TitlebarWindow {};
+        let mut harness = TestHarness::new(egui::vec2(400.0, 200.0));
+        assert!(!harness.run(|ctx| window.ui(ctx)).1.quit);
+        harness.click(egui::pos2(14.0, 10.0));
+        assert!(harness.run(|ctx| window.ui(ctx)).1.quit);
+    }
+
+    #[test]
+    fn corner_grip_resizes() {
+        let mut window = TitlebarWindow {};
+        let mut harness = TestHarness::new(egui::vec2(400.0, 200.0));
+        harness.run(|ctx| window.ui(ctx));
+        harness.pointer_button(egui::pos2(384.0, 184.0), egui::PointerButton::Primary, true);
+        let (_, rr) = harness.run(|ctx| window.ui(ctx));
+        assert!(matches!(
+            rr.commands[..],
+            [WindowCommand::StartResize(
+                egui::viewport::ResizeDirection::SouthEast
+            )]
+        ));
+    }
+}
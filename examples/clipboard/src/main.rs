@@ -2,6 +2,9 @@
 #![deny(clippy::missing_docs_in_private_items)]
 
 //! An example of how the clipboard can be used
+//!
+//! Run with `--smoke-test` to open the root window, copy text, and exit, which checks that the clipboard is set up and torn down
+//! cleanly. The program exits with an error when the copied text can not be read back.
 
 use egui_multiwin_dynamic::multi_window::MultiWindow;
 
@@ -24,21 +27,29 @@ use windows::{
 pub struct AppCommon {
     /// Number of times a button has been clicked
     clicks: u32,
+    /// True when running the smoke test
+    smoke_test: bool,
 }
 
 #[tokio::main]
 async fn main() {
-    let mut multi_window: MultiWindow = MultiWindow::new();
+    let mut multi_window: MultiWindow = MultiWindow::new_or_panic();
     multi_window.add_font(
         "computermodern".to_string(),
         egui_multiwin::egui::FontData::from_static(COMPUTER_MODERN_FONT),
     );
+    let smoke_test = std::env::args().any(|a| a == "--smoke-test");
     let root_window = root::RootWindow::request();
-    let root_window2 = popup_window::PopupWindow::request("initial popup".to_string());
 
-    let ac = AppCommon { clicks: 0 };
+    let ac = AppCommon {
+        clicks: 0,
+        smoke_test,
+    };
 
     let _e = multi_window.add(root_window).await;
-    let _e = multi_window.add(root_window2).await;
+    if !smoke_test {
+        let root_window2 = popup_window::PopupWindow::request("initial popup".to_string());
+        let _e = multi_window.add(root_window2).await;
+    }
     multi_window.run(ac).unwrap();
 }
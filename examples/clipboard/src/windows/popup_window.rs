@@ -3,7 +3,7 @@ use std::sync::{Arc, Mutex};
 
 use crate::egui_multiwin_dynamic::{
     multi_window::NewWindowRequest,
-    tracked_window::{RedrawResponse, TrackedWindow},
+    tracked_window::{RedrawResponse, ThreadSafety, TrackedWindow},
 };
 use egui_multiwin::egui_glow_async::glow;
 use egui_multiwin::egui_glow_async::EguiGlow;
@@ -34,6 +34,15 @@ impl PopupWindow {
             egui_multiwin::tracked_window::TrackedWindowOptions {
                 vsync: false,
                 shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
             },
         )
     }
@@ -118,8 +127,8 @@ impl TrackedWindow for PopupWindow {
         &mut self,
         c: &mut AppCommon,
         egui: &mut EguiGlow,
-        window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>,
-        _clipboard: Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
+        window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
     ) -> RedrawResponse {
         let quit = Arc::new(Mutex::new(false));
         let quit2 = quit.clone();
@@ -150,6 +159,7 @@ impl TrackedWindow for PopupWindow {
         RedrawResponse {
             quit,
             new_windows: Vec::new(),
+            ..Default::default()
         }
     }
 }
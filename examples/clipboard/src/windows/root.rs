@@ -4,7 +4,7 @@ use std::sync::{Arc, Mutex};
 
 use crate::egui_multiwin_dynamic::{
     multi_window::NewWindowRequest,
-    tracked_window::{RedrawResponse, TrackedWindow},
+    tracked_window::{RedrawResponse, ThreadSafety, TrackedWindow},
 };
 use egui_multiwin::egui::FontId;
 use egui_multiwin::egui_glow_async::EguiGlow;
@@ -42,6 +42,15 @@ impl RootWindow {
             egui_multiwin::tracked_window::TrackedWindowOptions {
                 vsync: false,
                 shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
             },
         )
     }
@@ -58,13 +67,30 @@ impl TrackedWindow for RootWindow {
         &mut self,
         c: &mut AppCommon,
         egui: &mut EguiGlow,
-        _window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>,
-        clipboard: Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
+        _window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
     ) -> RedrawResponse {
         let mut quit = false;
 
         let mut windows_to_create = vec![];
 
+        if c.smoke_test {
+            let text = "egui-multiwin smoke test";
+            let mut clipboard = clipboard.lock().unwrap();
+            if let Err(e) = clipboard.set_text(text) {
+                eprintln!("Unable to copy text: {}", e);
+                std::process::exit(1);
+            }
+            match clipboard.get_text() {
+                Ok(s) if s == text => {}
+                r => {
+                    eprintln!("The copied text was not read back: {:?}", r);
+                    std::process::exit(1);
+                }
+            }
+            quit = true;
+        }
+
         egui_multiwin::egui::SidePanel::left("my_side_panel").show(&egui.egui_ctx, |ui| {
             ui.heading("Hello World!");
             if ui.button("New popup").clicked() {
@@ -102,6 +128,7 @@ impl TrackedWindow for RootWindow {
         RedrawResponse {
             quit,
             new_windows: windows_to_create,
+            ..Default::default()
         }
     }
 }
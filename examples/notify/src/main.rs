@@ -0,0 +1,162 @@
+#![deny(missing_docs)]
+#![deny(clippy::missing_docs_in_private_items)]
+
+//! Shows notifications pushed into one window by a background task, using the egui context of the window from the registry. Crates
+//! like egui-notify work the same way with the context.
+
+/// Macro generated code
+pub mod egui_multiwin_dynamic {
+    egui_multiwin::tracked_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+    egui_multiwin::multi_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+}
+
+use std::sync::Mutex;
+
+use egui_multiwin::egui;
+use egui_multiwin::egui_glow_async::EguiGlow;
+use egui_multiwin::tracked_window::{BoxFuture, TrackedWindowDyn};
+use egui_multiwin_dynamic::multi_window::NewWindowRequest;
+use egui_multiwin_dynamic::tracked_window::RedrawResponse;
+use egui_multiwin_dynamic::tracked_window::ThreadSafety;
+use std::sync::Arc;
+
+/// Data common to all windows
+pub struct AppCommon {}
+
+/// How long a notification is shown, in seconds
+const TOAST_SECONDS: f64 = 3.0;
+
+/// A notification shown in the corner of a window
+#[derive(Clone)]
+struct Toast {
+    /// The text of the notification
+    text: String,
+    /// When the notification was first shown, in the time of egui
+    shown_at: Option<f64>,
+}
+
+/// The id of the notifications in the egui context of a window
+fn toasts_id() -> egui::Id {
+    egui::Id::new("toasts")
+}
+
+/// Add a notification to a window, from any thread
+fn push_toast(ctx: &egui::Context, text: String) {
+    ctx.data_mut(|d| {
+        d.get_temp_mut_or_default::<Vec<Toast>>(toasts_id())
+            .push(Toast {
+                text,
+                shown_at: None,
+            })
+    });
+    ctx.request_repaint();
+}
+
+/// Show the notifications of a window, removing them once they have been shown long enough
+fn show_toasts(ctx: &egui::Context) {
+    let now = ctx.input(|i| i.time);
+    let toasts = ctx.data_mut(|d| {
+        let toasts = d.get_temp_mut_or_default::<Vec<Toast>>(toasts_id());
+        toasts.retain(|t| t.shown_at.map_or(true, |at| now < at + TOAST_SECONDS));
+        for t in toasts.iter_mut() {
+            t.shown_at.get_or_insert(now);
+        }
+        toasts.clone()
+    });
+    egui::Area::new(egui::Id::new("toast_area"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+        .show(ctx, |ui| {
+            for t in &toasts {
+                egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(&t.text));
+            }
+        });
+    if let Some(first) = toasts.iter().filter_map(|t| t.shown_at).reduce(f64::min) {
+        let left = (first + TOAST_SECONDS - now).max(0.0);
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(left));
+    }
+}
+
+/// A window that shows notifications
+pub struct NotifyWindow {
+    /// The text shown in the window
+    label: &'static str,
+}
+
+impl NotifyWindow {
+    /// Create a request to create a window
+    pub fn request(label: &'static str) -> NewWindowRequest {
+        NewWindowRequest::new(
+            Box::new(NotifyWindow { label }),
+            egui_multiwin::async_winit::window::WindowBuilder::new()
+                .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
+                    width: 400.0,
+                    height: 200.0,
+                })
+                .with_title(label),
+            egui_multiwin::tracked_window::TrackedWindowOptions {
+                vsync: false,
+                shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
+            },
+        )
+    }
+}
+
+impl TrackedWindowDyn<AppCommon, RedrawResponse> for NotifyWindow {
+    fn is_root(&self) -> bool {
+        true
+    }
+
+    fn redraw<'a>(
+        &'a mut self,
+        _c: &'a mut AppCommon,
+        egui: &'a mut EguiGlow,
+        _window: &'a egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> BoxFuture<'a, RedrawResponse> {
+        Box::pin(async move {
+            egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
+                ui.heading(self.label);
+            });
+            show_toasts(&egui.egui_ctx);
+            RedrawResponse::default()
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let mut multi_window = egui_multiwin_dynamic::multi_window::MultiWindow::new_or_panic();
+    let _e = multi_window
+        .add(NotifyWindow::request("This window stays quiet"))
+        .await;
+    let noisy = multi_window
+        .add(NotifyWindow::request("This window gets notifications"))
+        .await
+        .unwrap();
+    let registry = multi_window.registry();
+    tokio::spawn(async move {
+        for n in 1.. {
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            // The context is published once the window is running, and removed when it closes
+            if let Some(ctx) = registry.context(noisy) {
+                push_toast(&ctx, format!("Notification {}", n));
+            }
+        }
+    });
+    multi_window.run(AppCommon {}).unwrap();
+}
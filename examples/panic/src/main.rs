@@ -0,0 +1,137 @@
+#![deny(missing_docs)]
+#![deny(clippy::missing_docs_in_private_items)]
+
+//! Shows that a window that panics is closed while the other windows keep running
+
+/// Macro generated code
+pub mod egui_multiwin_dynamic {
+    egui_multiwin::tracked_window!(crate::AppCommon, crate::MyWindows);
+    egui_multiwin::multi_window!(crate::AppCommon, crate::MyWindows);
+}
+
+use std::sync::Mutex;
+
+/// The windows for the program
+#[enum_dispatch(TrackedWindow)]
+pub enum MyWindows {
+    /// A window that keeps running
+    Steady(SteadyWindow),
+    /// A window that panics
+    Faulty(FaultyWindow),
+}
+
+use egui_multiwin::egui_glow_async::EguiGlow;
+use egui_multiwin::enum_dispatch::enum_dispatch;
+use egui_multiwin_dynamic::multi_window::NewWindowRequest;
+use egui_multiwin_dynamic::tracked_window::RedrawResponse;
+use egui_multiwin_dynamic::tracked_window::ThreadSafety;
+use egui_multiwin_dynamic::tracked_window::TrackedWindow;
+use std::sync::Arc;
+
+/// Data common to all windows
+pub struct AppCommon {
+    /// Number of frames drawn by all windows
+    frames: u32,
+}
+
+/// Create a request for a window
+fn request(window: MyWindows, title: &str) -> NewWindowRequest {
+    NewWindowRequest::new(
+        window,
+        egui_multiwin::async_winit::window::WindowBuilder::new()
+            .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
+                width: 400.0,
+                height: 200.0,
+            })
+            .with_title(title),
+        egui_multiwin::tracked_window::TrackedWindowOptions {
+            vsync: false,
+            shader: None,
+            transparent: false,
+            depth_bits: None,
+            stencil_bits: None,
+            gl_context: None,
+            gl_context_fallbacks: &[],
+            config_picker: None,
+            srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+            swap_mode: None,
+            repaint_throttle: None,
+        },
+    )
+}
+
+/// A window that keeps drawing after the other window panicked
+pub struct SteadyWindow {}
+
+impl TrackedWindow for SteadyWindow {
+    fn is_root(&self) -> bool {
+        true
+    }
+
+    async fn redraw(
+        &mut self,
+        c: &mut AppCommon,
+        egui: &mut EguiGlow,
+        _window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> RedrawResponse {
+        c.frames += 1;
+        egui_multiwin::egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
+            ui.heading(format!("{} frames drawn", c.frames));
+        });
+        RedrawResponse::default()
+    }
+}
+
+/// A window that panics on its third frame. Move the mouse over the window to draw frames.
+pub struct FaultyWindow {
+    /// The number of frames drawn by this window
+    frames: u32,
+}
+
+impl TrackedWindow for FaultyWindow {
+    fn is_root(&self) -> bool {
+        false
+    }
+
+    async fn redraw(
+        &mut self,
+        c: &mut AppCommon,
+        egui: &mut EguiGlow,
+        _window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> RedrawResponse {
+        self.frames += 1;
+        c.frames += 1;
+        if self.frames == 3 {
+            panic!("The faulty window panicked on its third frame");
+        }
+        egui_multiwin::egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
+            ui.heading(format!("Frame {} of this window", self.frames));
+        });
+        RedrawResponse::default()
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let mut multi_window = egui_multiwin_dynamic::multi_window::MultiWindow::new_or_panic();
+    multi_window.on_window_panic(|id, payload| {
+        println!(
+            "Window {} panicked: {}",
+            id,
+            egui_multiwin::error::panic_message(payload)
+        );
+    });
+    let ac = AppCommon { frames: 0 };
+    let _e = multi_window
+        .add(request(MyWindows::Steady(SteadyWindow {}), "Steady window"))
+        .await;
+    let _e = multi_window
+        .add(request(
+            MyWindows::Faulty(FaultyWindow { frames: 0 }),
+            "Faulty window",
+        ))
+        .await;
+    multi_window.run(ac).unwrap();
+}
@@ -0,0 +1,119 @@
+#![deny(missing_docs)]
+#![deny(clippy::missing_docs_in_private_items)]
+
+//! Shows the profiling of windows with puffin. Build with `--features profiling`, then connect puffin_viewer to the address printed
+//! at startup to see the frames of both windows.
+
+/// Macro generated code
+pub mod egui_multiwin_dynamic {
+    egui_multiwin::tracked_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+    egui_multiwin::multi_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+}
+
+use std::sync::Mutex;
+
+use egui_multiwin::egui_glow_async::EguiGlow;
+use egui_multiwin::profile::puffin;
+use egui_multiwin::tracked_window::{BoxFuture, TrackedWindowDyn};
+use egui_multiwin_dynamic::multi_window::NewWindowRequest;
+use egui_multiwin_dynamic::tracked_window::RedrawResponse;
+use egui_multiwin_dynamic::tracked_window::ThreadSafety;
+use std::sync::Arc;
+
+/// Data common to all windows
+pub struct AppCommon {}
+
+/// A window that keeps redrawing, so that there is always something to profile
+pub struct BusyWindow {
+    /// The number of the window
+    number: u32,
+    /// The number of frames drawn
+    frames: u64,
+}
+
+impl BusyWindow {
+    /// Create a request to create a window
+    pub fn request(number: u32) -> NewWindowRequest {
+        NewWindowRequest::new(
+            Box::new(BusyWindow { number, frames: 0 }),
+            egui_multiwin::async_winit::window::WindowBuilder::new()
+                .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
+                    width: 300.0,
+                    height: 200.0,
+                })
+                .with_title(format!("Profiled window {}", number)),
+            egui_multiwin::tracked_window::TrackedWindowOptions {
+                vsync: true,
+                shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
+            },
+        )
+    }
+}
+
+impl TrackedWindowDyn<AppCommon, RedrawResponse> for BusyWindow {
+    fn is_root(&self) -> bool {
+        true
+    }
+
+    fn redraw<'a>(
+        &'a mut self,
+        _c: &'a mut AppCommon,
+        egui: &'a mut EguiGlow,
+        window: &'a egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> BoxFuture<'a, RedrawResponse> {
+        Box::pin(async move {
+            // A scope of the application, shown inside the redraw scope of the window
+            puffin::profile_scope!("busy window ui");
+            self.frames += 1;
+            egui_multiwin::egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
+                ui.heading(format!("Window {}", self.number));
+                ui.label(format!("{} frames", self.frames));
+                for i in 0..100 {
+                    ui.label(format!("Line {}", i));
+                }
+            });
+            window.request_redraw();
+            RedrawResponse {
+                force_redraw: true,
+                ..Default::default()
+            }
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let address = format!("127.0.0.1:{}", puffin_http::DEFAULT_PORT);
+    let _server = match puffin_http::Server::new(&address) {
+        Ok(server) => {
+            println!("Connect puffin_viewer to {}", address);
+            Some(server)
+        }
+        Err(e) => {
+            println!("Unable to start the puffin server: {}", e);
+            None
+        }
+    };
+    puffin::set_scopes_on(true);
+    let mut multi_window = egui_multiwin_dynamic::multi_window::MultiWindow::new_or_panic();
+    let _e = multi_window
+        .add_all(vec![BusyWindow::request(1), BusyWindow::request(2)])
+        .await;
+    multi_window.run(AppCommon {}).unwrap();
+}
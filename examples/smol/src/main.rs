@@ -0,0 +1,102 @@
+#![deny(missing_docs)]
+#![deny(clippy::missing_docs_in_private_items)]
+
+//! Shows an application that uses smol instead of tokio. The library is built without its default `tokio` feature, a background
+//! future counts seconds with a smol timer.
+
+/// Macro generated code
+pub mod egui_multiwin_dynamic {
+    egui_multiwin::tracked_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+    egui_multiwin::multi_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+}
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use egui_multiwin::egui_glow_async::EguiGlow;
+use egui_multiwin::smol;
+use egui_multiwin::tracked_window::{BoxFuture, TrackedWindowDyn};
+use egui_multiwin_dynamic::multi_window::NewWindowRequest;
+use egui_multiwin_dynamic::tracked_window::RedrawResponse;
+use egui_multiwin_dynamic::tracked_window::ThreadSafety;
+use std::sync::Arc;
+
+/// Data common to all windows
+pub struct AppCommon {
+    /// The number of seconds counted by the background future
+    seconds: Arc<AtomicU32>,
+}
+
+/// The window showing the seconds
+pub struct ClockWindow {}
+
+impl ClockWindow {
+    /// Create a request to create a window
+    pub fn request() -> NewWindowRequest {
+        NewWindowRequest::new(
+            Box::new(ClockWindow {}),
+            egui_multiwin::async_winit::window::WindowBuilder::new()
+                .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
+                    width: 300.0,
+                    height: 100.0,
+                })
+                .with_title("smol"),
+            egui_multiwin::tracked_window::TrackedWindowOptions {
+                vsync: false,
+                shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
+            },
+        )
+    }
+}
+
+impl TrackedWindowDyn<AppCommon, RedrawResponse> for ClockWindow {
+    fn is_root(&self) -> bool {
+        true
+    }
+
+    fn redraw<'a>(
+        &'a mut self,
+        c: &'a mut AppCommon,
+        egui: &'a mut EguiGlow,
+        _window: &'a egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> BoxFuture<'a, RedrawResponse> {
+        Box::pin(async move {
+            egui_multiwin::egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
+                ui.heading(format!("{} seconds", c.seconds.load(Ordering::Relaxed)));
+            });
+            egui.egui_ctx.request_repaint_after(Duration::from_secs(1));
+            RedrawResponse::default()
+        })
+    }
+}
+
+fn main() {
+    let mut multi_window = egui_multiwin_dynamic::multi_window::MultiWindow::new_or_panic();
+    let seconds = Arc::new(AtomicU32::new(0));
+    let counter = seconds.clone();
+    multi_window.add_daemon(async move {
+        loop {
+            smol::Timer::after(Duration::from_secs(1)).await;
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    let _e = smol::block_on(multi_window.add(ClockWindow::request()));
+    multi_window.run(AppCommon { seconds }).unwrap();
+}
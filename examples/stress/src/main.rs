@@ -0,0 +1,136 @@
+#![deny(missing_docs)]
+#![deny(clippy::missing_docs_in_private_items)]
+
+//! A stress test that redraws several windows continuously, checking for opengl errors after every frame.
+//! The program exits once every window has drawn the required number of frames, and panics on the first opengl error.
+
+/// Macro generated code
+pub mod egui_multiwin_dynamic {
+    egui_multiwin::tracked_window!(crate::AppCommon, crate::MyWindows);
+    egui_multiwin::multi_window!(crate::AppCommon, crate::MyWindows);
+}
+
+use std::sync::Mutex;
+
+/// The windows for the program
+#[enum_dispatch(TrackedWindow)]
+pub enum MyWindows {
+    /// A window that redraws continuously
+    Stress(StressWindow),
+}
+
+use egui_multiwin::egui_glow_async::glow;
+use egui_multiwin::egui_glow_async::EguiGlow;
+use egui_multiwin::enum_dispatch::enum_dispatch;
+use egui_multiwin_dynamic::multi_window::NewWindowRequest;
+use egui_multiwin_dynamic::tracked_window::RedrawResponse;
+use egui_multiwin_dynamic::tracked_window::ThreadSafety;
+use egui_multiwin_dynamic::tracked_window::TrackedWindow;
+use std::sync::Arc;
+
+/// The number of windows to redraw at the same time
+const WINDOWS: u32 = 5;
+
+/// The number of frames each window draws before closing
+const FRAMES: u32 = 3000;
+
+/// Data common to all windows
+pub struct AppCommon {
+    /// The number of windows that have drawn all of their frames
+    finished: u32,
+}
+
+/// A window that redraws continuously
+pub struct StressWindow {
+    /// The number of the window
+    number: u32,
+    /// The number of frames drawn by the window
+    frames: u32,
+}
+
+impl StressWindow {
+    /// Create a request to create a window
+    pub fn request(number: u32) -> NewWindowRequest {
+        NewWindowRequest::new(
+            MyWindows::Stress(StressWindow { number, frames: 0 }),
+            egui_multiwin::async_winit::window::WindowBuilder::new()
+                .with_resizable(false)
+                .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
+                    width: 300.0,
+                    height: 100.0,
+                })
+                .with_title(format!("Stress window {}", number)),
+            egui_multiwin::tracked_window::TrackedWindowOptions {
+                vsync: false,
+                shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
+            },
+        )
+    }
+}
+
+impl TrackedWindow for StressWindow {
+    fn is_root(&self) -> bool {
+        true
+    }
+
+    async unsafe fn opengl_after(
+        &mut self,
+        _c: &mut AppCommon,
+        gl: &std::sync::Arc<egui_multiwin::egui_glow_async::painter::Context>,
+    ) {
+        use glow::HasContext;
+        let error = gl.get_error();
+        if error != glow::NO_ERROR {
+            panic!(
+                "Opengl error {:#x} in window {} on frame {}",
+                error, self.number, self.frames
+            );
+        }
+    }
+
+    async fn redraw(
+        &mut self,
+        c: &mut AppCommon,
+        egui: &mut EguiGlow,
+        window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> RedrawResponse {
+        self.frames += 1;
+        egui_multiwin::egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
+            ui.heading(format!("Window {} frame {}", self.number, self.frames));
+            ui.label(format!("{} of {} windows finished", c.finished, WINDOWS));
+        });
+        let quit = self.frames >= FRAMES;
+        if quit {
+            c.finished += 1;
+        } else {
+            window.request_redraw();
+        }
+        RedrawResponse {
+            quit,
+            new_windows: Vec::new(),
+            // Draw every frame, exercising the full opengl path of the window
+            force_redraw: true,
+            ..Default::default()
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let mut multi_window = egui_multiwin_dynamic::multi_window::MultiWindow::new_or_panic();
+    let _e = multi_window
+        .add_all((0..WINDOWS).map(StressWindow::request).collect())
+        .await;
+    let ac = AppCommon { finished: 0 };
+    multi_window.run(ac).unwrap();
+}
@@ -1,28 +1,27 @@
 #![deny(missing_docs)]
 #![deny(clippy::missing_docs_in_private_items)]
 
-//! Shows a very simple example with minimal code
+//! Shows a very simple example with minimal code. The windows are boxed trait objects, so no enum of the windows is needed.
 
 /// Macro generated code
 pub mod egui_multiwin_dynamic {
-    egui_multiwin::tracked_window!(crate::AppCommon, crate::MyWindows);
-    egui_multiwin::multi_window!(crate::AppCommon, crate::MyWindows);
+    egui_multiwin::tracked_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+    egui_multiwin::multi_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
 }
 
 use std::sync::Mutex;
 
-/// The windows for the program
-#[enum_dispatch(TrackedWindow)]
-pub enum MyWindows {
-    /// A popup window
-    Popup(PopupWindow),
-}
-
 use egui_multiwin::egui_glow_async::EguiGlow;
-use egui_multiwin::enum_dispatch::enum_dispatch;
+use egui_multiwin::tracked_window::{BoxFuture, TrackedWindowDyn};
 use egui_multiwin_dynamic::multi_window::NewWindowRequest;
 use egui_multiwin_dynamic::tracked_window::RedrawResponse;
-use egui_multiwin_dynamic::tracked_window::TrackedWindow;
+use egui_multiwin_dynamic::tracked_window::ThreadSafety;
 use std::sync::Arc;
 
 /// Data common to all windows
@@ -38,7 +37,7 @@ impl PopupWindow {
     /// Create a request to create a window
     pub fn request() -> NewWindowRequest {
         NewWindowRequest::new(
-            MyWindows::Popup(PopupWindow {}),
+            Box::new(PopupWindow {}),
             egui_multiwin::async_winit::window::WindowBuilder::new()
                 .with_resizable(false)
                 .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
@@ -49,39 +48,51 @@ impl PopupWindow {
             egui_multiwin::tracked_window::TrackedWindowOptions {
                 vsync: false,
                 shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
             },
         )
     }
 }
 
-impl TrackedWindow for PopupWindow {
+impl TrackedWindowDyn<AppCommon, RedrawResponse> for PopupWindow {
     fn is_root(&self) -> bool {
         true
     }
 
-    async fn redraw(
-        &mut self,
-        c: &mut AppCommon,
-        egui: &mut EguiGlow,
-        _window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>,
-        _clipboard: Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
-    ) -> RedrawResponse {
-        let quit = false;
-        egui_multiwin::egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
-            ui.heading(format!("number {}", c.clicks));
-        });
-        RedrawResponse {
-            quit,
-            new_windows: Vec::new(),
-        }
+    fn redraw<'a>(
+        &'a mut self,
+        c: &'a mut AppCommon,
+        egui: &'a mut EguiGlow,
+        _window: &'a egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> BoxFuture<'a, RedrawResponse> {
+        Box::pin(async move {
+            let quit = false;
+            egui_multiwin::egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
+                ui.heading(format!("number {}", c.clicks));
+            });
+            RedrawResponse {
+                quit,
+                new_windows: Vec::new(),
+                ..Default::default()
+            }
+        })
     }
 }
 
 #[tokio::main]
 async fn main() {
-    let mut multi_window = egui_multiwin_dynamic::multi_window::MultiWindow::new();
+    let mut multi_window = egui_multiwin_dynamic::multi_window::MultiWindow::new_or_panic();
     let root_window = PopupWindow::request();
     let ac = AppCommon { clicks: 0 };
-    multi_window.add(root_window).await;
+    let _e = multi_window.add(root_window).await;
     multi_window.run(ac).unwrap();
 }
@@ -0,0 +1,180 @@
+#![deny(missing_docs)]
+#![deny(clippy::missing_docs_in_private_items)]
+
+//! Shows tabs that are torn off into windows of their own. Drag a tab and release it outside of the window, a new window holding the
+//! tab is opened where the mouse was released.
+
+/// Macro generated code
+pub mod egui_multiwin_dynamic {
+    egui_multiwin::tracked_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+    egui_multiwin::multi_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+}
+
+use std::sync::Mutex;
+
+use egui_multiwin::egui;
+use egui_multiwin::egui_glow_async::EguiGlow;
+use egui_multiwin::tracked_window::{screen_pointer_position, BoxFuture, TrackedWindowDyn};
+use egui_multiwin_dynamic::multi_window::NewWindowRequest;
+use egui_multiwin_dynamic::tracked_window::RedrawResponse;
+use egui_multiwin_dynamic::tracked_window::ThreadSafety;
+use std::sync::Arc;
+
+/// Data common to all windows
+pub struct AppCommon {}
+
+/// A tab, which can be moved into a window of its own
+pub struct Tab {
+    /// The title of the tab
+    title: String,
+    /// The text edited in the tab
+    text: String,
+}
+
+/// A window holding tabs
+pub struct TabWindow {
+    /// The tabs of the window
+    tabs: Vec<Tab>,
+    /// The index of the tab that is shown
+    selected: usize,
+}
+
+impl TabWindow {
+    /// Create a request to create a window holding tabs
+    pub fn request(tabs: Vec<Tab>) -> NewWindowRequest {
+        NewWindowRequest::new(
+            Box::new(TabWindow { tabs, selected: 0 }),
+            egui_multiwin::async_winit::window::WindowBuilder::new()
+                .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
+                    width: 400.0,
+                    height: 200.0,
+                })
+                .with_title("Tabs"),
+            egui_multiwin::tracked_window::TrackedWindowOptions {
+                vsync: false,
+                shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
+            },
+        )
+    }
+
+    /// The user interface of the window, separate from the redraw so that it can be tested without a window
+    fn ui(&mut self, ctx: &egui::Context) -> RedrawResponse {
+        let mut torn_off = None;
+        egui::TopBottomPanel::top("tabs").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                for (i, tab) in self.tabs.iter().enumerate() {
+                    let response = ui
+                        .selectable_label(i == self.selected, &tab.title)
+                        .interact(egui::Sense::drag());
+                    if response.clicked() {
+                        self.selected = i;
+                    }
+                    // The last tab stays, so that the window is never empty
+                    let outside = ctx
+                        .input(|input| input.pointer.latest_pos())
+                        .map_or(false, |p| !ctx.screen_rect().contains(p));
+                    if response.drag_stopped() && outside && self.tabs.len() > 1 {
+                        torn_off = Some(i);
+                    }
+                }
+            });
+        });
+        let mut new_windows = Vec::new();
+        if let Some(i) = torn_off {
+            let tab = self.tabs.remove(i);
+            self.selected = self.selected.min(self.tabs.len() - 1);
+            let mut request = TabWindow::request(vec![tab]);
+            if let Some(position) = screen_pointer_position(ctx) {
+                request = request.with_outer_position(position);
+            }
+            new_windows.push(request);
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(tab) = self.tabs.get_mut(self.selected) {
+                ui.text_edit_multiline(&mut tab.text);
+            }
+        });
+        RedrawResponse {
+            new_windows,
+            ..Default::default()
+        }
+    }
+}
+
+impl TrackedWindowDyn<AppCommon, RedrawResponse> for TabWindow {
+    fn is_root(&self) -> bool {
+        true
+    }
+
+    fn redraw<'a>(
+        &'a mut self,
+        _c: &'a mut AppCommon,
+        egui: &'a mut EguiGlow,
+        _window: &'a egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> BoxFuture<'a, RedrawResponse> {
+        Box::pin(async move { self.ui(&egui.egui_ctx) })
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let mut multi_window = egui_multiwin_dynamic::multi_window::MultiWindow::new_or_panic();
+    let tabs = ["First", "Second", "Third"]
+        .iter()
+        .map(|title| Tab {
+            title: title.to_string(),
+            text: format!("The text of the {} tab", title.to_lowercase()),
+        })
+        .collect();
+    let _e = multi_window.add(TabWindow::request(tabs)).await;
+    multi_window.run(AppCommon {}).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui_multiwin::testing::TestHarness;
+
+    #[test]
+    fn tab_released_outside_is_torn_off() {
+        let tab = |title: &str| Tab {
+            title: title.to_string(),
+            text: String::new(),
+        };
+        let mut window = TabWindow {
+            tabs: vec![tab("First"), tab("Second")],
+            selected: 0,
+        };
+        let mut harness = TestHarness::new(egui::vec2(400.0, 200.0));
+        harness.run(|ctx| window.ui(ctx));
+        let second = egui::pos2(60.0, 12.0);
+        harness.pointer_button(second, egui::PointerButton::Primary, true);
+        harness.run(|ctx| window.ui(ctx));
+        harness.move_pointer(egui::pos2(300.0, 100.0));
+        harness.run(|ctx| window.ui(ctx));
+        harness.move_pointer(egui::pos2(500.0, 100.0));
+        assert!(harness.run(|ctx| window.ui(ctx)).1.new_windows.is_empty());
+        let released = egui::pos2(500.0, 100.0);
+        harness.pointer_button(released, egui::PointerButton::Primary, false);
+        let (_, rr) = harness.run(|ctx| window.ui(ctx));
+        assert_eq!(rr.new_windows.len(), 1);
+        assert_eq!(window.tabs.len(), 1);
+        assert_eq!(window.tabs[0].title, "First");
+    }
+}
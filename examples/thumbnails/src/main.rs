@@ -0,0 +1,149 @@
+#![deny(missing_docs)]
+#![deny(clippy::missing_docs_in_private_items)]
+
+//! Shows the same thumbnails in several windows, with the textures shared by all windows. Each thumbnail is uploaded once, and the
+//! windows copy it on the graphics card because their opengl contexts are shared.
+
+/// Macro generated code
+pub mod egui_multiwin_dynamic {
+    egui_multiwin::tracked_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+    egui_multiwin::multi_window!(
+        crate::AppCommon,
+        crate::egui_multiwin_dynamic::tracked_window::DynWindow
+    );
+}
+
+use std::sync::Mutex;
+
+use egui_multiwin::egui;
+use egui_multiwin::egui_glow_async::EguiGlow;
+use egui_multiwin::textures::{AppTextureManager, TextureToken};
+use egui_multiwin::tracked_window::{BoxFuture, TrackedWindowDyn};
+use egui_multiwin_dynamic::multi_window::NewWindowRequest;
+use egui_multiwin_dynamic::tracked_window::RedrawResponse;
+use egui_multiwin_dynamic::tracked_window::ThreadSafety;
+use std::sync::Arc;
+
+/// The number of thumbnails
+const THUMBNAILS: usize = 100;
+
+/// Data common to all windows
+pub struct AppCommon {
+    /// The textures shared by the windows
+    textures: AppTextureManager,
+    /// The thumbnails
+    thumbnails: Vec<TextureToken>,
+}
+
+/// Make a thumbnail, a gradient with a different color for each number
+fn thumbnail(n: usize) -> egui::ColorImage {
+    let size = 64;
+    let hue = n as f32 / THUMBNAILS as f32;
+    let mut image = egui::ColorImage::new([size, size], egui::Color32::BLACK);
+    for y in 0..size {
+        for x in 0..size {
+            let value = (x + y) as f32 / (2 * size) as f32 + 0.25;
+            image[(x, y)] = egui::ecolor::Hsva::new(hue, 0.8, value, 1.0).into();
+        }
+    }
+    image
+}
+
+/// A window showing every thumbnail
+pub struct ThumbnailWindow {}
+
+impl ThumbnailWindow {
+    /// Create a request to create a window
+    pub fn request(title: &str) -> NewWindowRequest {
+        NewWindowRequest::new(
+            Box::new(ThumbnailWindow {}),
+            egui_multiwin::async_winit::window::WindowBuilder::new()
+                .with_inner_size(egui_multiwin::async_winit::dpi::LogicalSize {
+                    width: 500.0,
+                    height: 400.0,
+                })
+                .with_title(title),
+            egui_multiwin::tracked_window::TrackedWindowOptions {
+                vsync: false,
+                shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
+            },
+        )
+    }
+}
+
+impl TrackedWindowDyn<AppCommon, RedrawResponse> for ThumbnailWindow {
+    fn is_root(&self) -> bool {
+        true
+    }
+
+    fn redraw<'a>(
+        &'a mut self,
+        c: &'a mut AppCommon,
+        egui: &'a mut EguiGlow,
+        _window: &'a egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
+    ) -> BoxFuture<'a, RedrawResponse> {
+        Box::pin(async move {
+            let ids: Vec<egui::TextureId> = c
+                .thumbnails
+                .iter()
+                .filter_map(|t| c.textures.texture_id(*t, egui))
+                .collect();
+            egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
+                ui.label(format!(
+                    "{} thumbnails, {} uploads",
+                    ids.len(),
+                    c.textures.uploads()
+                ));
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for id in ids {
+                            ui.image((id, egui::vec2(64.0, 64.0)));
+                        }
+                    });
+                });
+            });
+            RedrawResponse::default()
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let mut multi_window = egui_multiwin_dynamic::multi_window::MultiWindow::new_or_panic();
+    // Windows with an isolated context upload every thumbnail themselves
+    multi_window.share_contexts(true);
+    let textures = multi_window.textures();
+    let thumbnails = (0..THUMBNAILS)
+        .map(|n| {
+            textures.add(
+                format!("thumbnail {}", n),
+                thumbnail(n),
+                egui::TextureOptions::LINEAR,
+            )
+        })
+        .collect();
+    for n in 1..=3 {
+        let _e = multi_window
+            .add(ThumbnailWindow::request(&format!("Thumbnails {}", n)))
+            .await;
+    }
+    multi_window
+        .run(AppCommon {
+            textures,
+            thumbnails,
+        })
+        .unwrap();
+}
@@ -25,11 +25,14 @@ use windows::{
 pub struct AppCommon {
     /// The number of times a button has been clicked
     clicks: u32,
+    /// A texture uploaded once and displayed by every popup window, possible because the windows share opengl contexts
+    shared_texture: Option<egui_multiwin::egui_glow_async::glow::Texture>,
 }
 
 #[tokio::main]
 async fn main() {
-    let mut multi_window: MultiWindow = MultiWindow::new();
+    let mut multi_window: MultiWindow = MultiWindow::new_or_panic();
+    multi_window.share_contexts(true);
     multi_window.add_font(
         "computermodern".to_string(),
         egui_multiwin::egui::FontData::from_static(COMPUTER_MODERN_FONT),
@@ -37,7 +40,10 @@ async fn main() {
     let root_window = root::RootWindow::request();
     let root_window2 = popup_window::PopupWindow::request("initial popup".to_string());
 
-    let ac = AppCommon { clicks: 0 };
+    let ac = AppCommon {
+        clicks: 0,
+        shared_texture: None,
+    };
 
     let _e = multi_window.add(root_window).await;
     let _e = multi_window.add(root_window2).await;
@@ -3,11 +3,12 @@ use std::sync::{Arc, Mutex};
 
 use crate::egui_multiwin_dynamic::{
     multi_window::NewWindowRequest,
-    tracked_window::{RedrawResponse, TrackedWindow},
+    tracked_window::{RedrawResponse, ThreadSafety, TrackedWindow},
 };
 use egui_multiwin::egui_glow_async::glow;
 use egui_multiwin::egui_glow_async::EguiGlow;
 use egui_multiwin::egui::containers::panel::AsyncClosure;
+use egui_multiwin::tracked_window::FrameStats;
 
 use crate::AppCommon;
 
@@ -15,6 +16,8 @@ use crate::AppCommon;
 pub struct PopupWindow {
     /// The label for the popup window
     pub input: String,
+    /// The egui id of the shared texture, registered with the painter of this window
+    texture: Option<egui_multiwin::egui::TextureId>,
 }
 
 impl PopupWindow {
@@ -23,6 +26,7 @@ impl PopupWindow {
         NewWindowRequest::new(
             super::MyWindows::Popup(PopupWindow {
                 input: label.clone(),
+                texture: None,
             }),
             egui_multiwin::async_winit::window::WindowBuilder::new()
                 .with_resizable(false)
@@ -34,11 +38,48 @@ impl PopupWindow {
             egui_multiwin::tracked_window::TrackedWindowOptions {
                 vsync: false,
                 shader: None,
+                transparent: false,
+                depth_bits: Some(24),
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
             },
         )
     }
 }
 
+/// Upload a small checkerboard texture
+unsafe fn upload_checkerboard(gl: &egui_multiwin::egui_glow_async::painter::Context) -> glow::Texture {
+    use glow::HasContext;
+    let mut pixels = Vec::with_capacity(8 * 8 * 4);
+    for y in 0..8 {
+        for x in 0..8 {
+            let v = if (x + y) % 2 == 0 { 255 } else { 64 };
+            pixels.extend_from_slice(&[v, v / 2, 255 - v, 255]);
+        }
+    }
+    let texture = gl.create_texture().expect("Cannot create texture");
+    gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+    gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::NEAREST as i32);
+    gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::NEAREST as i32);
+    gl.tex_image_2d(
+        glow::TEXTURE_2D,
+        0,
+        glow::RGBA8 as i32,
+        8,
+        8,
+        0,
+        glow::RGBA,
+        glow::UNSIGNED_BYTE,
+        Some(&pixels),
+    );
+    texture
+}
+
 impl TrackedWindow for PopupWindow {
     async unsafe fn opengl_after(
         &mut self,
@@ -53,15 +94,18 @@ impl TrackedWindow for PopupWindow {
         gl.bind_vertex_array(Some(vertex_array));
         let program = gl.create_program().expect("Cannot create program");
         let (vertex_shader_source, fragment_shader_source) = (
-            r#"const vec2 verts[3] = vec2[3](
-                vec2(0.5f, 1.0f),
-                vec2(0.0f, 0.0f),
-                vec2(1.0f, 0.0f)
+            r#"const vec3 verts[6] = vec3[6](
+                vec3(0.5f, 1.0f, 0.0f),
+                vec3(0.0f, 0.0f, 0.0f),
+                vec3(1.0f, 0.0f, 0.0f),
+                vec3(0.75f, 1.0f, 0.5f),
+                vec3(0.25f, 0.0f, 0.5f),
+                vec3(1.25f, 0.0f, 0.5f)
             );
             out vec2 vert;
             void main() {
-                vert = verts[gl_VertexID];
-                gl_Position = vec4(vert - 0.5, 0.0, 1.0);
+                vert = verts[gl_VertexID].xy;
+                gl_Position = vec4(verts[gl_VertexID].xy - 0.5, verts[gl_VertexID].z, 1.0);
             }"#,
             r#"precision mediump float;
             in vec2 vert;
@@ -107,7 +151,11 @@ impl TrackedWindow for PopupWindow {
 
         gl.use_program(Some(program));
 
-        gl.draw_arrays(glow::TRIANGLES, 0, 3);
+        // The second triangle is drawn last, but the depth buffer keeps it behind the first triangle
+        gl.enable(glow::DEPTH_TEST);
+        gl.depth_func(glow::LESS);
+        gl.draw_arrays(glow::TRIANGLES, 0, 6);
+        gl.disable(glow::DEPTH_TEST);
     }
 
     fn can_quit(&mut self, c: &mut AppCommon) -> bool {
@@ -118,12 +166,20 @@ impl TrackedWindow for PopupWindow {
         &mut self,
         c: &mut AppCommon,
         egui: &mut EguiGlow,
-        window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>,
-        _clipboard: Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
+        window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
     ) -> RedrawResponse {
         let quit = Arc::new(Mutex::new(false));
         let quit2 = quit.clone();
 
+        // The texture is uploaded by the first popup, the other popups use it through their shared contexts
+        let shared_texture = *c
+            .shared_texture
+            .get_or_insert_with(|| unsafe { upload_checkerboard(egui.painter.gl()) });
+        let texture = *self
+            .texture
+            .get_or_insert_with(|| egui.painter.register_native_texture(shared_texture));
+
         egui_multiwin::egui::CentralPanel::default()
             .show_async(&egui.egui_ctx, |ui| AsyncClosure::new(async move {
                 if ui.button("Increment").clicked() {
@@ -144,12 +200,24 @@ impl TrackedWindow for PopupWindow {
                 if ui.button("Quit").clicked() {
                     *quit2.lock().unwrap() = true;
                 }
+                ui.image(egui_multiwin::egui::load::SizedTexture::new(
+                    texture,
+                    egui_multiwin::egui::vec2(64.0, 64.0),
+                ));
+                let stats = FrameStats::get(ui.ctx());
+                ui.label(format!(
+                    "{} frames drawn, {} frames skipped, average frame time {:?}",
+                    stats.drawn,
+                    stats.skipped,
+                    stats.average_frame_time()
+                ));
             }))
             .await;
         let quit = *quit.lock().unwrap();
         RedrawResponse {
             quit,
             new_windows: Vec::new(),
+            ..Default::default()
         }
     }
 }
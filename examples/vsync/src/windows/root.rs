@@ -3,10 +3,11 @@ use std::sync::{Arc, Mutex};
 
 use crate::egui_multiwin_dynamic::{
     multi_window::NewWindowRequest,
-    tracked_window::{RedrawResponse, TrackedWindow},
+    tracked_window::{RedrawResponse, ThreadSafety, TrackedWindow},
 };
 use egui_multiwin::egui::FontId;
 use egui_multiwin::egui_glow_async::EguiGlow;
+use egui_multiwin::tracked_window::{FrameStats, SwapMode, WindowCommand};
 
 use crate::AppCommon;
 
@@ -22,6 +23,8 @@ pub struct RootWindow {
     prev_time: std::time::Instant,
     /// The calculated frames per second of the window
     fps: Option<f32>,
+    /// The swap mode of the window
+    swap_mode: SwapMode,
 }
 
 impl RootWindow {
@@ -33,6 +36,7 @@ impl RootWindow {
                 num_popups_created: 0,
                 prev_time: std::time::Instant::now(),
                 fps: None,
+                swap_mode: SwapMode::Wait(std::num::NonZeroU32::MIN),
             }),
             egui_multiwin::async_winit::window::WindowBuilder::new()
                 .with_resizable(true)
@@ -44,6 +48,15 @@ impl RootWindow {
             egui_multiwin::tracked_window::TrackedWindowOptions {
                 vsync: true,
                 shader: None,
+                transparent: false,
+                depth_bits: None,
+                stencil_bits: None,
+                gl_context: None,
+                gl_context_fallbacks: &[],
+                config_picker: None,
+                srgb: egui_multiwin::tracked_window::SrgbMode::ForceEnabled,
+                swap_mode: None,
+                repaint_throttle: None,
             },
         )
     }
@@ -60,8 +73,8 @@ impl TrackedWindow for RootWindow {
         &mut self,
         c: &mut AppCommon,
         egui: &mut EguiGlow,
-        window: &egui_multiwin::async_winit::window::Window<egui_multiwin::async_winit::ThreadSafe>,
-        _clipboard: Arc<Mutex<egui_multiwin::arboard::Clipboard>>,
+        window: &egui_multiwin::async_winit::window::Window<ThreadSafety>,
+        _clipboard: Arc<Mutex<egui_multiwin::clipboard::Clipboard>>,
     ) -> RedrawResponse {
         let mut quit = false;
 
@@ -78,6 +91,7 @@ impl TrackedWindow for RootWindow {
         }
 
         let mut windows_to_create = vec![];
+        let mut commands = vec![];
 
         egui_multiwin::egui::SidePanel::left("my_side_panel").show(&egui.egui_ctx, |ui| {
             ui.heading("Hello World!");
@@ -88,6 +102,15 @@ impl TrackedWindow for RootWindow {
                 )));
                 self.num_popups_created += 1;
             }
+            if ui.button("New 2 popups").clicked() {
+                for _ in 0..2 {
+                    windows_to_create.push(PopupWindow::request(format!(
+                        "popup window #{}",
+                        self.num_popups_created
+                    )));
+                    self.num_popups_created += 1;
+                }
+            }
             if ui.button("New transparent window").clicked() {
                 windows_to_create.push(crate::windows::transparent_window::PopupWindow::request(
                     "Transparent".to_string(),
@@ -99,6 +122,11 @@ impl TrackedWindow for RootWindow {
         });
         egui_multiwin::egui::CentralPanel::default().show(&egui.egui_ctx, |ui| {
             ui.label(format!("The fps is {}", self.fps.unwrap()));
+            // Close the popups to compare the frame time of a lone window, which keeps its opengl context current, to several windows
+            ui.label(format!(
+                "Average frame time {:?}",
+                FrameStats::get(ui.ctx()).average_frame_time()
+            ));
             ui.heading(format!("number {}", c.clicks));
             let t = egui_multiwin::egui::widget_text::RichText::new("Example custom font text");
             let t = t.font(FontId {
@@ -106,10 +134,30 @@ impl TrackedWindow for RootWindow {
                 family: egui_multiwin::egui::FontFamily::Name("computermodern".into()),
             });
             ui.label(t);
+            let prev_mode = self.swap_mode;
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.swap_mode, SwapMode::Immediate, "No vsync");
+                ui.radio_value(
+                    &mut self.swap_mode,
+                    SwapMode::Wait(std::num::NonZeroU32::MIN),
+                    "Vsync",
+                );
+                ui.radio_value(
+                    &mut self.swap_mode,
+                    SwapMode::Wait(std::num::NonZeroU32::new(2).unwrap()),
+                    "Half rate vsync",
+                );
+                ui.radio_value(&mut self.swap_mode, SwapMode::Adaptive, "Adaptive vsync");
+            });
+            if self.swap_mode != prev_mode {
+                commands.push(WindowCommand::SetSwapMode(self.swap_mode));
+            }
         });
         RedrawResponse {
             quit,
             new_windows: windows_to_create,
+            commands,
+            ..Default::default()
         }
     }
 }